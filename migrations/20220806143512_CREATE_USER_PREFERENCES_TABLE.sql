@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS user_preferences
+(
+    user_id                 INTEGER PRIMARY KEY NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    min_rating              INTEGER             NOT NULL DEFAULT 0,
+    include_review          BOOLEAN             NOT NULL DEFAULT FALSE,
+    mention                 BOOLEAN             NOT NULL DEFAULT FALSE,
+    hide_unrated            BOOLEAN             NOT NULL DEFAULT FALSE,
+    skip_shelves            TEXT                NOT NULL DEFAULT ''
+);
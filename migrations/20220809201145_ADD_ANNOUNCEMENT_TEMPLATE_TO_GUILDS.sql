@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE guilds ADD COLUMN announcement_template TEXT;
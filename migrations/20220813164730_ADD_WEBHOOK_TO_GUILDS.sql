@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE guilds ADD COLUMN webhook_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE guilds ADD COLUMN webhook_id INTEGER;
+ALTER TABLE guilds ADD COLUMN webhook_token TEXT;
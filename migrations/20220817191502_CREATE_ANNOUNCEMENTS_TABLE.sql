@@ -0,0 +1,16 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS announcements
+(
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id                 INTEGER             NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    guild_id                INTEGER             NOT NULL,
+    book_id                 TEXT                NOT NULL,
+    channel_id              INTEGER             NOT NULL,
+    message_id              INTEGER             NOT NULL,
+    thread_id               INTEGER                     ,
+    announced_at            INTEGER             NOT NULL
+);
+CREATE INDEX IF NOT EXISTS announcements_guild_book ON announcements (guild_id, book_id);
+
+ALTER TABLE guilds ADD COLUMN threads_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE guilds ADD COLUMN thread_archive_minutes INTEGER NOT NULL DEFAULT 1440;
@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE announcements ADD COLUMN rating INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE announcements ADD COLUMN webhook_id INTEGER;
+ALTER TABLE announcements ADD COLUMN retracted BOOLEAN NOT NULL DEFAULT FALSE;
+CREATE INDEX IF NOT EXISTS announcements_user ON announcements (user_id, announced_at);
+
+ALTER TABLE guilds ADD COLUMN announcement_sync TEXT NOT NULL DEFAULT 'off';
@@ -0,0 +1,27 @@
+-- Add migration script here
+ALTER TABLE announcements ADD COLUMN title TEXT NOT NULL DEFAULT '';
+ALTER TABLE announcements ADD COLUMN author TEXT NOT NULL DEFAULT '';
+ALTER TABLE announcements ADD COLUMN url TEXT NOT NULL DEFAULT '';
+CREATE INDEX IF NOT EXISTS announcements_message ON announcements (message_id);
+
+CREATE TABLE IF NOT EXISTS announcement_reactions
+(
+    announcement_id         INTEGER             NOT NULL REFERENCES announcements (id) ON DELETE CASCADE,
+    discord_user_id         INTEGER             NOT NULL,
+    kind                    TEXT                NOT NULL,
+    reacted_at              INTEGER             NOT NULL,
+    PRIMARY KEY (announcement_id, discord_user_id, kind)
+);
+
+CREATE TABLE IF NOT EXISTS want_to_read
+(
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_user_id         INTEGER             NOT NULL,
+    discord_guild_id        INTEGER             NOT NULL,
+    book_id                 TEXT                NOT NULL,
+    title                   TEXT                NOT NULL,
+    author                  TEXT                NOT NULL,
+    url                     TEXT                NOT NULL,
+    added_at                INTEGER             NOT NULL,
+    UNIQUE (discord_user_id, discord_guild_id, book_id)
+);
@@ -0,0 +1,25 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS reading_history
+(
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id                 INTEGER             NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    discord_user_id         INTEGER             NOT NULL,
+    guild_id                INTEGER             NOT NULL,
+    book_id                 TEXT                NOT NULL,
+    title                   TEXT                NOT NULL,
+    author                  TEXT                NOT NULL,
+    url                     TEXT                NOT NULL,
+    image_url               TEXT                NOT NULL,
+    rating                  INTEGER             NOT NULL,
+    pages                   INTEGER                     ,
+    shelves                 TEXT                NOT NULL DEFAULT '',
+    read_on                 TEXT                NOT NULL,
+    recorded_at             INTEGER             NOT NULL,
+    shared                  BOOLEAN             NOT NULL
+);
+CREATE INDEX IF NOT EXISTS reading_history_guild ON reading_history (guild_id, recorded_at);
+CREATE INDEX IF NOT EXISTS reading_history_user ON reading_history (user_id, book_id);
+
+ALTER TABLE guilds ADD COLUMN digest_schedule TEXT NOT NULL DEFAULT 'off';
+ALTER TABLE guilds ADD COLUMN digest_time TEXT NOT NULL DEFAULT '09:00';
+ALTER TABLE guilds ADD COLUMN last_digest_at INTEGER;
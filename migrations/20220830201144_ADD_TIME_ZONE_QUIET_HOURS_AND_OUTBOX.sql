@@ -0,0 +1,26 @@
+-- Add migration script here
+ALTER TABLE guilds ADD COLUMN time_zone TEXT NOT NULL DEFAULT 'UTC';
+ALTER TABLE guilds ADD COLUMN quiet_hours_start TEXT;
+ALTER TABLE guilds ADD COLUMN quiet_hours_end TEXT;
+
+CREATE TABLE IF NOT EXISTS outbox
+(
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id                 INTEGER             NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    discord_user_id         INTEGER             NOT NULL,
+    guild_id                INTEGER             NOT NULL,
+    book_id                 TEXT                NOT NULL,
+    title                   TEXT                NOT NULL,
+    author                  TEXT                NOT NULL,
+    url                     TEXT                NOT NULL,
+    image_url               TEXT                NOT NULL,
+    rating                  INTEGER             NOT NULL,
+    review                  TEXT                NOT NULL DEFAULT '',
+    shelves                 TEXT                NOT NULL DEFAULT '',
+    pages                   INTEGER                     ,
+    completed               TEXT                NOT NULL,
+    read_at                 TEXT                        ,
+    queued_at               INTEGER             NOT NULL,
+    release_at              INTEGER             NOT NULL
+);
+CREATE INDEX IF NOT EXISTS outbox_release ON outbox (release_at);
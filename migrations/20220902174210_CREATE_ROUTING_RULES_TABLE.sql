@@ -0,0 +1,11 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS routing_rules
+(
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id                INTEGER             NOT NULL,
+    kind                    TEXT                NOT NULL,
+    value                   TEXT                NOT NULL,
+    channel_id              INTEGER             NOT NULL,
+    created_at              INTEGER             NOT NULL
+);
+CREATE INDEX IF NOT EXISTS routing_rules_guild ON routing_rules (guild_id, id);
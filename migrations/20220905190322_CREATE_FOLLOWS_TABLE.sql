@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS follows
+(
+    follower_id             INTEGER             NOT NULL,
+    followee_id             INTEGER             NOT NULL,
+    guild_id                INTEGER             NOT NULL,
+    followed_at             INTEGER             NOT NULL,
+    PRIMARY KEY (follower_id, followee_id, guild_id)
+);
+CREATE INDEX IF NOT EXISTS follows_followee ON follows (followee_id, guild_id);
+
+ALTER TABLE user_preferences ADD COLUMN allow_followers BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,19 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS reading_goals
+(
+    user_id                 INTEGER             NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    year                    INTEGER             NOT NULL,
+    target                  INTEGER             NOT NULL,
+    set_at                  INTEGER             NOT NULL,
+    PRIMARY KEY (user_id, year)
+);
+
+CREATE TABLE IF NOT EXISTS goal_milestones
+(
+    user_id                 INTEGER             NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    year                    INTEGER             NOT NULL,
+    percent                 INTEGER             NOT NULL,
+    reached_at              INTEGER             NOT NULL,
+    announced               BOOLEAN             NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (user_id, year, percent)
+);
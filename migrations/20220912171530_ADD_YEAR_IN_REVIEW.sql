@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE guilds ADD COLUMN recap_date TEXT DEFAULT '01-01';
+ALTER TABLE guilds ADD COLUMN last_recap_year INTEGER;
+-- Existing guilds get their first recap next January rather than one for last year straight away
+UPDATE guilds SET last_recap_year = CAST(strftime('%Y', 'now') AS INTEGER);
+
+ALTER TABLE user_preferences ADD COLUMN recap BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,25 @@
+-- Add migration script here
+CREATE VIRTUAL TABLE IF NOT EXISTS reading_history_search USING fts5
+(
+    title,
+    author,
+    content = 'reading_history',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+-- Keep the index in step with the history it covers
+CREATE TRIGGER IF NOT EXISTS reading_history_search_insert AFTER INSERT ON reading_history BEGIN
+    INSERT INTO reading_history_search (rowid, title, author) VALUES (new.id, new.title, new.author);
+END;
+CREATE TRIGGER IF NOT EXISTS reading_history_search_delete AFTER DELETE ON reading_history BEGIN
+    INSERT INTO reading_history_search (reading_history_search, rowid, title, author)
+    VALUES ('delete', old.id, old.title, old.author);
+END;
+CREATE TRIGGER IF NOT EXISTS reading_history_search_update AFTER UPDATE OF title, author ON reading_history BEGIN
+    INSERT INTO reading_history_search (reading_history_search, rowid, title, author)
+    VALUES ('delete', old.id, old.title, old.author);
+    INSERT INTO reading_history_search (rowid, title, author) VALUES (new.id, new.title, new.author);
+END;
+
+INSERT INTO reading_history_search (reading_history_search) VALUES ('rebuild');
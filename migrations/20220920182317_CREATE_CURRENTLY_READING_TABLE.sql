@@ -0,0 +1,11 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS currently_reading
+(
+    user_id                 INTEGER             NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    book_id                 TEXT                NOT NULL,
+    title                   TEXT                NOT NULL,
+    author                  TEXT                NOT NULL,
+    url                     TEXT                NOT NULL,
+    seen_at                 INTEGER             NOT NULL,
+    PRIMARY KEY (user_id, book_id)
+);
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE guilds ADD COLUMN club_favourite_readers INTEGER DEFAULT 3;
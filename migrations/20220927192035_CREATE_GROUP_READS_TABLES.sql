@@ -0,0 +1,28 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS group_reads
+(
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id                INTEGER             NOT NULL,
+    book_id                 TEXT                NOT NULL,
+    title                   TEXT                NOT NULL,
+    author                  TEXT                NOT NULL,
+    url                     TEXT                NOT NULL,
+    started_by              INTEGER             NOT NULL,
+    started_at              INTEGER             NOT NULL,
+    deadline                INTEGER             NOT NULL,
+    wrapped_up              BOOLEAN             NOT NULL DEFAULT FALSE
+);
+-- Only one group read is running in a guild at a time
+CREATE UNIQUE INDEX IF NOT EXISTS group_reads_running ON group_reads (guild_id) WHERE wrapped_up = FALSE;
+
+CREATE TABLE IF NOT EXISTS group_read_members
+(
+    group_read_id           INTEGER             NOT NULL REFERENCES group_reads (id) ON DELETE CASCADE,
+    discord_user_id         INTEGER             NOT NULL,
+    joined_at               INTEGER             NOT NULL,
+    finished_at             INTEGER                     ,
+    -- Left empty when the member's preferences keep the book out of announcements
+    rating                  INTEGER                     ,
+    reported                BOOLEAN             NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (group_read_id, discord_user_id)
+);
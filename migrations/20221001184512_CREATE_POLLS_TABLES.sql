@@ -0,0 +1,40 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS polls
+(
+    id                      INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id                INTEGER             NOT NULL,
+    opened_by               INTEGER             NOT NULL,
+    opened_at               INTEGER             NOT NULL,
+    -- 'approval' or 'ranked'
+    method                  TEXT                NOT NULL DEFAULT 'approval',
+    -- Left empty while members are still nominating
+    closes_at               INTEGER                     ,
+    -- When set, the winner starts a group read that has to be finished by then
+    club_deadline           INTEGER                     ,
+    closed                  BOOLEAN             NOT NULL DEFAULT FALSE
+);
+-- Only one poll is open in a guild at a time
+CREATE UNIQUE INDEX IF NOT EXISTS polls_open ON polls (guild_id) WHERE closed = FALSE;
+
+CREATE TABLE IF NOT EXISTS poll_nominations
+(
+    poll_id                 INTEGER             NOT NULL REFERENCES polls (id) ON DELETE CASCADE,
+    book_id                 TEXT                NOT NULL,
+    title                   TEXT                NOT NULL,
+    author                  TEXT                NOT NULL,
+    url                     TEXT                NOT NULL,
+    image_url               TEXT                NOT NULL,
+    nominated_by            INTEGER             NOT NULL,
+    nominated_at            INTEGER             NOT NULL,
+    PRIMARY KEY (poll_id, book_id)
+);
+
+CREATE TABLE IF NOT EXISTS poll_votes
+(
+    poll_id                 INTEGER             NOT NULL REFERENCES polls (id) ON DELETE CASCADE,
+    discord_user_id         INTEGER             NOT NULL,
+    book_id                 TEXT                NOT NULL,
+    -- 1 for a first choice. Every approved book is ranked 1.
+    rank                    INTEGER             NOT NULL,
+    PRIMARY KEY (poll_id, discord_user_id, book_id)
+);
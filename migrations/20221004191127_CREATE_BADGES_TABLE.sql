@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS badges
+(
+    user_id                 INTEGER             NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    badge                   TEXT                NOT NULL,
+    -- The book that earned it, which is announced with it
+    book_id                 TEXT                NOT NULL,
+    earned_at               INTEGER             NOT NULL,
+    PRIMARY KEY (user_id, badge)
+);
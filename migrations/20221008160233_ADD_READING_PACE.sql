@@ -0,0 +1,13 @@
+-- Add migration script here
+-- When the book went on the member's shelves, which stands in for when they started it
+ALTER TABLE reading_history ADD COLUMN added_on TEXT;
+ALTER TABLE outbox ADD COLUMN added_at TEXT;
+
+ALTER TABLE user_preferences ADD COLUMN pace BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS streak_reminders
+(
+    user_id                 INTEGER PRIMARY KEY NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    -- The Monday of the last week the member was warned their streak was about to break
+    week_of                 TEXT                NOT NULL
+);
@@ -0,0 +1,27 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS authors
+(
+    -- Goodreads' id for the author
+    author_id               INTEGER PRIMARY KEY NOT NULL,
+    name                    TEXT                NOT NULL,
+    last_checked            INTEGER             NOT NULL DEFAULT 0
+);
+
+-- Every book of the author's seen so far, so only new ones are announced
+CREATE TABLE IF NOT EXISTS author_books
+(
+    author_id               INTEGER             NOT NULL REFERENCES authors (author_id) ON DELETE CASCADE,
+    book_id                 TEXT                NOT NULL,
+    found_at                INTEGER             NOT NULL,
+    PRIMARY KEY (author_id, book_id)
+);
+
+CREATE TABLE IF NOT EXISTS author_follows
+(
+    discord_user_id         INTEGER             NOT NULL,
+    guild_id                INTEGER             NOT NULL,
+    author_id               INTEGER             NOT NULL REFERENCES authors (author_id) ON DELETE CASCADE,
+    followed_at             INTEGER             NOT NULL,
+    PRIMARY KEY (discord_user_id, guild_id, author_id)
+);
+CREATE INDEX IF NOT EXISTS author_follows_author ON author_follows (author_id);
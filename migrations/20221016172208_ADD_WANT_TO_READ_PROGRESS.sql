@@ -0,0 +1,6 @@
+-- Add migration script here
+-- Set once the book turns up on the member's read shelf
+ALTER TABLE want_to_read ADD COLUMN finished_at INTEGER;
+ALTER TABLE want_to_read ADD COLUMN from_goodreads BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE user_preferences ADD COLUMN sync_want_to_read BOOLEAN NOT NULL DEFAULT FALSE;
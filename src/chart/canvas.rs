@@ -0,0 +1,222 @@
+pub type Rgb = [u8; 3];
+
+/// Each glyph is 3 pixels wide and 5 tall, one row per byte with the leftmost pixel in the
+/// highest of the three bits. Lower case letters are drawn as upper case.
+const GLYPH_WIDTH: i64 = 3;
+const GLYPH_HEIGHT: i64 = 5;
+
+fn glyph(c: char) -> Option<[u8; 5]> {
+    let rows = match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '*' => [0b010, 0b111, 0b010, 0b101, 0b000],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+/// An RGB image drawn in memory. Anything drawn outside the edges is clipped.
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: background
+                .iter()
+                .copied()
+                .cycle()
+                .take((width * height * 3) as usize)
+                .collect(),
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width as i64
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height as i64
+    }
+
+    #[cfg(test)]
+    pub fn pixel(&self, x: i64, y: i64) -> Option<Rgb> {
+        let index = self.index(x, y)?;
+        Some([
+            self.pixels[index],
+            self.pixels[index + 1],
+            self.pixels[index + 2],
+        ])
+    }
+
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width() || y >= self.height() {
+            return None;
+        }
+        Some(((y * self.width() + x) * 3) as usize)
+    }
+
+    fn set(&mut self, x: i64, y: i64, colour: Rgb) {
+        if let Some(index) = self.index(x, y) {
+            self.pixels[index..index + 3].copy_from_slice(&colour);
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: i64, y: i64, width: i64, height: i64, colour: Rgb) {
+        for row in y.max(0)..(y + height).min(self.height()) {
+            for column in x.max(0)..(x + width).min(self.width()) {
+                self.set(column, row, colour);
+            }
+        }
+    }
+
+    /// A straight line, drawn as squares `thickness` wide so it stays solid at any angle
+    pub fn line(&mut self, from: (i64, i64), to: (i64, i64), thickness: i64, colour: Rgb) {
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).max(1);
+        for step in 0..=steps {
+            let x = from.0 + (to.0 - from.0) * step / steps;
+            let y = from.1 + (to.1 - from.1) * step / steps;
+            self.fill_rect(
+                x - thickness / 2,
+                y - thickness / 2,
+                thickness,
+                thickness,
+                colour,
+            );
+        }
+    }
+
+    /// How wide `text` is when drawn at `scale`
+    pub fn text_width(text: &str, scale: i64) -> i64 {
+        let characters = text.chars().count() as i64;
+        if characters == 0 {
+            0
+        } else {
+            (characters * (GLYPH_WIDTH + 1) - 1) * scale
+        }
+    }
+
+    pub fn text_height(scale: i64) -> i64 {
+        GLYPH_HEIGHT * scale
+    }
+
+    /// Write `text` with its top left corner at `x`, `y`. Characters the font doesn't have are
+    /// left as gaps.
+    pub fn text(&mut self, x: i64, y: i64, text: &str, scale: i64, colour: Rgb) {
+        for (position, c) in text.chars().enumerate() {
+            let left = x + position as i64 * (GLYPH_WIDTH + 1) * scale;
+            let rows = match glyph(c) {
+                Some(rows) => rows,
+                None => continue,
+            };
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.fill_rect(
+                            left + column * scale,
+                            y + row as i64 * scale,
+                            scale,
+                            scale,
+                            colour,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn to_png(&self) -> anyhow::Result<Vec<u8>> {
+        let mut png = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&self.pixels)?;
+        }
+        Ok(png)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chart::canvas::Canvas;
+    use claim::{assert_none, assert_ok, assert_some};
+
+    const BLACK: [u8; 3] = [0, 0, 0];
+    const WHITE: [u8; 3] = [255, 255, 255];
+
+    #[test]
+    fn shapes_are_clipped_to_the_canvas() {
+        let mut canvas = Canvas::new(10, 10, BLACK);
+        canvas.fill_rect(-5, 8, 20, 20, WHITE);
+
+        assert_eq!(assert_some!(canvas.pixel(0, 9)), WHITE);
+        assert_eq!(assert_some!(canvas.pixel(9, 8)), WHITE);
+        assert_eq!(assert_some!(canvas.pixel(9, 7)), BLACK);
+        assert_none!(canvas.pixel(10, 9));
+    }
+
+    #[test]
+    fn text_is_drawn_from_the_bitmap_font() {
+        let mut canvas = Canvas::new(20, 10, BLACK);
+        canvas.text(0, 0, "1", 2, WHITE);
+
+        // The top of a 1 is only its middle column
+        assert_eq!(assert_some!(canvas.pixel(0, 0)), BLACK);
+        assert_eq!(assert_some!(canvas.pixel(2, 0)), WHITE);
+        assert_eq!(assert_some!(canvas.pixel(3, 1)), WHITE);
+        assert_eq!(Canvas::text_width("12", 2), 14);
+    }
+
+    #[test]
+    fn canvases_are_encoded_as_png() {
+        let png = assert_ok!(Canvas::new(4, 3, WHITE).to_png());
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        // The header chunk comes first, with the width and height in big endian
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 4, 0, 0, 0, 3]);
+    }
+}
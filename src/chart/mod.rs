@@ -0,0 +1,4 @@
+mod canvas;
+mod reading;
+
+pub use reading::reading_chart;
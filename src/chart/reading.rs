@@ -0,0 +1,215 @@
+use crate::chart::canvas::{Canvas, Rgb};
+use crate::model::ReadingStats;
+
+/// Discord's dark theme, so the chart sits in an embed without a box around it
+const BACKGROUND: Rgb = [0x2f, 0x31, 0x36];
+const GRID: Rgb = [0x40, 0x44, 0x4b];
+const TEXT: Rgb = [0xdc, 0xdd, 0xde];
+const BOOKS: Rgb = [0x58, 0x65, 0xf2];
+const RATINGS: Rgb = [0xfe, 0xe7, 0x5c];
+const PAGES: Rgb = [0x57, 0xf2, 0x87];
+
+const WIDTH: i64 = 640;
+const PANEL_HEIGHT: i64 = 220;
+const SCALE: i64 = 2;
+const PLOT_LEFT: i64 = 72;
+const PLOT_RIGHT: i64 = WIDTH - 24;
+const PLOT_TOP: i64 = 44;
+const PLOT_BOTTOM: i64 = PANEL_HEIGHT - 32;
+
+const MONTHS: [&str; 12] = ["J", "F", "M", "A", "M", "J", "J", "A", "S", "O", "N", "D"];
+
+/// Books per month, the spread of ratings and pages over the year, stacked in one PNG
+pub fn reading_chart(stats: &ReadingStats) -> anyhow::Result<Vec<u8>> {
+    let mut canvas = Canvas::new(WIDTH as u32, (PANEL_HEIGHT * 3) as u32, BACKGROUND);
+    books_per_month(&mut canvas, 0, &stats.monthly);
+    rating_distribution(&mut canvas, PANEL_HEIGHT, &stats.ratings);
+    pages_over_time(&mut canvas, PANEL_HEIGHT * 2, &stats.monthly_pages);
+    canvas.to_png()
+}
+
+fn books_per_month(canvas: &mut Canvas, top: i64, monthly: &[usize; 12]) {
+    let values: Vec<i64> = monthly.iter().map(|books| *books as i64).collect();
+    bar_panel(canvas, top, "Books per month", &MONTHS, &values, BOOKS);
+}
+
+fn rating_distribution(canvas: &mut Canvas, top: i64, ratings: &[usize; 5]) {
+    let values: Vec<i64> = ratings.iter().map(|books| *books as i64).collect();
+    bar_panel(
+        canvas,
+        top,
+        "Ratings",
+        &["1*", "2*", "3*", "4*", "5*"],
+        &values,
+        RATINGS,
+    );
+}
+
+/// A running total, which stops at the last month anything was read so a year in progress
+/// doesn't trail off along the bottom
+fn pages_over_time(canvas: &mut Canvas, top: i64, monthly_pages: &[i64; 12]) {
+    let mut total = 0;
+    let running: Vec<i64> = monthly_pages
+        .iter()
+        .map(|pages| {
+            total += pages;
+            total
+        })
+        .collect();
+    let max = axis_max(total);
+    axes(canvas, top, "Pages over time", &MONTHS, max);
+
+    let last = monthly_pages
+        .iter()
+        .rposition(|pages| *pages > 0)
+        .unwrap_or(0);
+    let points: Vec<(i64, i64)> = running
+        .iter()
+        .take(last + 1)
+        .enumerate()
+        .map(|(month, pages)| (slot_centre(month, MONTHS.len()), value_y(top, *pages, max)))
+        .collect();
+    for pair in points.windows(2) {
+        canvas.line(pair[0], pair[1], 3, PAGES);
+    }
+    for (x, y) in points {
+        canvas.fill_rect(x - 3, y - 3, 7, 7, PAGES);
+    }
+}
+
+fn bar_panel(
+    canvas: &mut Canvas,
+    top: i64,
+    title: &str,
+    labels: &[&str],
+    values: &[i64],
+    colour: Rgb,
+) {
+    let max = axis_max(values.iter().copied().max().unwrap_or_default());
+    axes(canvas, top, title, labels, max);
+
+    let slot = (PLOT_RIGHT - PLOT_LEFT) / labels.len() as i64;
+    let bar_width = slot * 2 / 3;
+    for (index, value) in values.iter().enumerate() {
+        if *value == 0 {
+            continue;
+        }
+        let centre = slot_centre(index, labels.len());
+        let y = value_y(top, *value, max);
+        canvas.fill_rect(
+            centre - bar_width / 2,
+            y,
+            bar_width,
+            top + PLOT_BOTTOM - y,
+            colour,
+        );
+        let label = value.to_string();
+        canvas.text(
+            centre - Canvas::text_width(&label, SCALE) / 2,
+            y - Canvas::text_height(SCALE) - 4,
+            &label,
+            SCALE,
+            TEXT,
+        );
+    }
+}
+
+/// The title, the baseline with labels under each slot, and gridlines at the top and middle
+fn axes(canvas: &mut Canvas, top: i64, title: &str, labels: &[&str], max: i64) {
+    canvas.text(16, top + 14, title, SCALE, TEXT);
+
+    for (value, labelled) in [(max, true), (max / 2, false), (0, true)] {
+        let y = value_y(top, value, max);
+        canvas.fill_rect(PLOT_LEFT, y, PLOT_RIGHT - PLOT_LEFT, 1, GRID);
+        if labelled {
+            let label = value.to_string();
+            canvas.text(
+                PLOT_LEFT - 8 - Canvas::text_width(&label, SCALE),
+                y - Canvas::text_height(SCALE) / 2,
+                &label,
+                SCALE,
+                TEXT,
+            );
+        }
+    }
+
+    for (index, label) in labels.iter().enumerate() {
+        let centre = slot_centre(index, labels.len());
+        canvas.text(
+            centre - Canvas::text_width(label, SCALE) / 2,
+            top + PLOT_BOTTOM + 10,
+            label,
+            SCALE,
+            TEXT,
+        );
+    }
+}
+
+fn slot_centre(index: usize, slots: usize) -> i64 {
+    let slot = (PLOT_RIGHT - PLOT_LEFT) / slots as i64;
+    PLOT_LEFT + slot * index as i64 + slot / 2
+}
+
+fn value_y(top: i64, value: i64, max: i64) -> i64 {
+    top + PLOT_BOTTOM - (PLOT_BOTTOM - PLOT_TOP) * value / max
+}
+
+/// The top of the axis: 1, 2 or 5 times a power of ten, and never less than `value`
+fn axis_max(value: i64) -> i64 {
+    let mut magnitude = 1;
+    loop {
+        for step in [1, 2, 5] {
+            if step * magnitude >= value {
+                return step * magnitude;
+            }
+        }
+        magnitude *= 10;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chart::canvas::Canvas;
+    use crate::chart::reading::{
+        axis_max, books_per_month, slot_centre, value_y, BACKGROUND, BOOKS, PLOT_BOTTOM,
+    };
+    use claim::assert_some;
+
+    #[test]
+    fn axes_end_on_a_round_number() {
+        assert_eq!(axis_max(0), 1);
+        assert_eq!(axis_max(3), 5);
+        assert_eq!(axis_max(5), 5);
+        assert_eq!(axis_max(11), 20);
+        assert_eq!(axis_max(4321), 5000);
+    }
+
+    #[test]
+    fn months_with_books_get_a_bar() {
+        let mut canvas = Canvas::new(640, 220, BACKGROUND);
+        let mut monthly = [0; 12];
+        monthly[2] = 4;
+        books_per_month(&mut canvas, 0, &monthly);
+
+        let march = slot_centre(2, 12);
+        let april = slot_centre(3, 12);
+        let just_above_the_baseline = PLOT_BOTTOM - 2;
+        assert_eq!(
+            assert_some!(canvas.pixel(march, just_above_the_baseline)),
+            BOOKS
+        );
+        assert_eq!(
+            assert_some!(canvas.pixel(april, just_above_the_baseline)),
+            BACKGROUND
+        );
+        // Four books on an axis that goes up to five
+        assert_eq!(
+            assert_some!(canvas.pixel(march, value_y(0, 4, 5) + 1)),
+            BOOKS
+        );
+        assert_eq!(
+            assert_some!(canvas.pixel(march, value_y(0, 4, 5) - 1)),
+            BACKGROUND
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use chrono::offset::Utc;
+use serenity::CacheAndHttp;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+use crate::crawler::{search_books, BookInfo, GovernedClient};
+use crate::discord::dm_author_followers;
+use crate::model::Author;
+
+/// How long a followed author goes between checks. New books don't come out often, and every
+/// check is a request to Goodreads on top of the feed crawl.
+const CHECK_INTERVAL_HOURS: i64 = 24;
+
+/// Looks for new books by the authors members follow, on its own clock so a slow Goodreads search
+/// never holds up the feed crawl
+pub async fn watch_authors(
+    cache_and_http: Arc<CacheAndHttp>,
+    pool: Arc<SqlitePool>,
+) -> anyhow::Result<()> {
+    let client = GovernedClient::default();
+    let pool = &*pool;
+
+    loop {
+        let checked_before = Utc::now().timestamp() - CHECK_INTERVAL_HOURS * 60 * 60;
+        for author in Author::due(pool, checked_before).await? {
+            let releases =
+                match check_author(pool, &author, &client, "https://www.goodreads.com").await {
+                    Ok(releases) => releases,
+                    Err(why) => {
+                        tracing::warn!(
+                            error.cause_chain = ?why,
+                            error.message = %why,
+                            "Author check failed because: {}",
+                            why
+                        );
+                        continue;
+                    }
+                };
+            for book in releases.iter() {
+                if let Err(why) = dm_author_followers(&cache_and_http, pool, &author, book).await {
+                    tracing::error!(
+                        error.cause_chain = ?why,
+                        error.message = %why,
+                        "Unable to DM author followers because: {}",
+                        why
+                    );
+                }
+            }
+        }
+        sleep(Duration::from_millis(1000 * 60 * 60)).await;
+    }
+}
+
+/// The author's books that weren't there last time. Goodreads' search only suggests a handful of
+/// books, so this catches what's new and popular rather than every edition.
+#[tracing::instrument(name = "Checking author's books", skip(pool, client, base_uri))]
+async fn check_author(
+    pool: &SqlitePool,
+    author: &Author,
+    client: &GovernedClient,
+    base_uri: &str,
+) -> anyhow::Result<Vec<BookInfo>> {
+    let found: Vec<BookInfo> = search_books(client, base_uri, &author.name)
+        .await?
+        .into_iter()
+        .filter(|book| book.author.id == author.author_id)
+        .collect();
+    let known = author.known_books(pool).await?;
+    let book_ids: Vec<String> = found.iter().map(|book| book.book_id.clone()).collect();
+    author.checked(pool, &book_ids).await?;
+
+    Ok(new_releases(&known, found))
+}
+
+/// Nothing is new the first time an author is looked at, or following them would announce their
+/// whole back catalogue
+fn new_releases(known: &HashSet<String>, found: Vec<BookInfo>) -> Vec<BookInfo> {
+    if known.is_empty() {
+        return Vec::new();
+    }
+    found
+        .into_iter()
+        .filter(|book| !known.contains(&book.book_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crawler::authors::new_releases;
+    use crate::crawler::book_info::AuthorInfo;
+    use crate::crawler::BookInfo;
+    use std::collections::HashSet;
+
+    fn book(book_id: &str) -> BookInfo {
+        BookInfo {
+            book_id: book_id.to_string(),
+            title: format!("Book {}", book_id),
+            image_url: String::new(),
+            author: AuthorInfo {
+                id: 1,
+                name: "Ursula K. Le Guin".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn only_books_missing_last_time_are_new() {
+        let known: HashSet<String> = ["1", "2"].iter().map(|id| id.to_string()).collect();
+
+        let releases = new_releases(&known, vec![book("1"), book("3"), book("2")]);
+        assert_eq!(releases, vec![book("3")]);
+    }
+
+    #[test]
+    fn the_first_look_at_an_author_finds_nothing_new() {
+        assert!(new_releases(&HashSet::new(), vec![book("1"), book("2")]).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::{anyhow, Context};
+use serde::Deserialize;
+
+use crate::crawler::GovernedClient;
+
+/// What Goodreads' search suggestions say about a book
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BookInfo {
+    pub book_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub image_url: String,
+    pub author: AuthorInfo,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct AuthorInfo {
+    pub id: i64,
+    pub name: String,
+}
+
+impl BookInfo {
+    pub fn url(&self) -> String {
+        format!("https://www.goodreads.com/book/show/{}", self.book_id)
+    }
+}
+
+/// The id in a Goodreads book link, such as `4981` in
+/// `https://www.goodreads.com/book/show/4981.Slaughterhouse_Five`
+pub fn book_id_from_url(url: &str) -> Option<String> {
+    let (_, rest) = url.trim().split_once("/book/show/")?;
+    let book_id: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if book_id.is_empty() {
+        None
+    } else {
+        Some(book_id)
+    }
+}
+
+/// Books matching `query` by title, author or id, best match first. This is the search box's
+/// autocomplete, which unlike the rest of the site answers in JSON.
+#[tracing::instrument(name = "Searching Goodreads books", skip(client, base_uri))]
+pub async fn search_books(
+    client: &GovernedClient,
+    base_uri: &str,
+    query: &str,
+) -> anyhow::Result<Vec<BookInfo>> {
+    let url = format!(
+        "{}/book/auto_complete?format=json&q={}",
+        base_uri,
+        encode_query(query)
+    );
+    let resp = client.get(&url).await?;
+    if resp.status().as_u16() != 200 {
+        return Err(anyhow!(
+            "GET request returned HTTP {}",
+            resp.status().as_u16()
+        ));
+    }
+    let content = resp
+        .text()
+        .await
+        .with_context(|| "Unable to get text from response")?;
+
+    serde_json::from_str(&content).with_context(|| "Unable deserialize response")
+}
+
+/// Look a book up by its Goodreads id
+pub async fn book_info(
+    client: &GovernedClient,
+    base_uri: &str,
+    book_id: &str,
+) -> anyhow::Result<Option<BookInfo>> {
+    Ok(search_books(client, base_uri, book_id)
+        .await?
+        .into_iter()
+        .find(|book| book.book_id == book_id))
+}
+
+/// Percent-encode everything but letters and digits, so the query survives as one parameter
+fn encode_query(query: &str) -> String {
+    query
+        .bytes()
+        .map(|byte| {
+            if byte.is_ascii_alphanumeric() {
+                (byte as char).to_string()
+            } else {
+                format!("%{:02X}", byte)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crawler::book_info::{book_id_from_url, book_info, encode_query};
+    use crate::crawler::GovernedClient;
+    use claim::{assert_none, assert_ok, assert_some};
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const AUTO_COMPLETE: &str = r#"[
+        {
+            "imageUrl": "https://i.gr-assets.com/images/S/compressed.photo.goodreads.com/books/1440319389i/4981._SY75_.jpg",
+            "bookId": "4981",
+            "workId": "1683562",
+            "bookUrl": "/book/show/4981.Slaughterhouse_Five",
+            "title": "Slaughterhouse-Five",
+            "bookTitleBare": "Slaughterhouse-Five",
+            "numPages": 275,
+            "avgRating": "4.10",
+            "ratingsCount": 1350000,
+            "author": {
+                "id": 2778055,
+                "name": "Kurt Vonnegut Jr.",
+                "isGoodreadsAuthor": false,
+                "profileUrl": "https://www.goodreads.com/author/show/2778055.Kurt_Vonnegut_Jr_",
+                "worksListUrl": "https://www.goodreads.com/author/list/2778055.Kurt_Vonnegut_Jr_"
+            }
+        }
+    ]"#;
+
+    #[test]
+    fn book_ids_are_taken_from_links() {
+        assert_eq!(
+            book_id_from_url("https://www.goodreads.com/book/show/4981.Slaughterhouse_Five"),
+            Some("4981".to_string())
+        );
+        assert_eq!(
+            book_id_from_url("https://www.goodreads.com/en/book/show/30659-meditations"),
+            Some("30659".to_string())
+        );
+        assert_none!(book_id_from_url(
+            "https://www.goodreads.com/author/show/2778055"
+        ));
+        assert_none!(book_id_from_url("4981"));
+    }
+
+    #[test]
+    fn queries_are_percent_encoded() {
+        assert_eq!(encode_query("le guin & co"), "le%20guin%20%26%20co");
+    }
+
+    #[tokio::test]
+    async fn book_info_finds_the_book_with_the_id() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/book/auto_complete"))
+            .and(query_param("q", "4981"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(AUTO_COMPLETE))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = GovernedClient::default();
+        let book = assert_some!(assert_ok!(
+            book_info(&client, &mock_server.uri(), "4981").await
+        ));
+        assert_eq!(book.title, "Slaughterhouse-Five");
+        assert_eq!(book.author.name, "Kurt Vonnegut Jr.");
+        assert_eq!(book.url(), "https://www.goodreads.com/book/show/4981");
+    }
+}
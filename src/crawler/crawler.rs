@@ -1,39 +1,175 @@
 use anyhow::{anyhow, Context};
+use chrono::offset::Utc;
+use chrono::Datelike;
+use governor::{Quota, RateLimiter};
+use nonzero_ext::*;
 use quick_xml::de::from_str;
 use serenity::CacheAndHttp;
 use sqlx::SqlitePool;
+use std::collections::BTreeSet;
 use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 
 use crate::crawler::{GovernedClient, Rss, RssResult};
-use crate::discord::post_book;
+use crate::discord::{
+    celebrate_milestone, post_book, retract_announcement, update_announcement, FollowerDms,
+};
 use crate::model::Book;
-use crate::model::User;
+use crate::model::{
+    Announcement, Badge, CurrentlyReading, DigestSchedule, GroupRead, GuildSettings, HistoryEntry,
+    OutboxEntry, Preferences, SyncMode, User, WantToRead,
+};
+
+/// How long announcements are kept in sync with the user's read shelf
+const SYNC_WINDOW_DAYS: i64 = 14;
+
+/// What a crawl of the user's read shelf turned up
+#[derive(Debug, Default)]
+struct ShelfUpdate {
+    new_books: Vec<Book>,
+    /// New books the user's preferences keep out of announcements
+    hidden_books: Vec<Book>,
+    changes: Vec<AnnouncementChange>,
+}
+
+/// How a recently announced book has changed on the user's read shelf
+#[derive(Debug)]
+enum AnnouncementChange {
+    Rerated(Announcement, Box<Book>),
+    Removed(Announcement),
+}
 
-pub async fn crawl(cache_and_http: Arc<CacheAndHttp>, pool: Arc<SqlitePool>) -> anyhow::Result<()> {
+pub async fn crawl(
+    cache_and_http: Arc<CacheAndHttp>,
+    pool: Arc<SqlitePool>,
+    follower_dms: Arc<FollowerDms>,
+) -> anyhow::Result<()> {
     let client = GovernedClient::default();
     let pool = &*pool;
+    // Shelves other than read are fetched whole every time, so they're checked less often
+    let shelf_checks = RateLimiter::keyed(Quota::per_hour(nonzero!(1u32)));
 
     loop {
+        shelf_checks.retain_recent();
         if let Some(mut users) = User::get_refreshable_users(&pool, 5).await? {
             for mut user in users.iter_mut() {
-                match check_rss(&mut user, &client, "https://www.goodreads.com").await {
-                    Ok(result) => {
-                        if let Some(books) = result {
-                            for book in books.iter() {
-                                post_book(
-                                    cache_and_http.clone(),
-                                    &book,
-                                    &user,
-                                    user.get_channel_id(pool).await?,
-                                )
-                                .await
-                                .context("Unable to post book to discord!")?;
-                            }
-                        }
+                // One member's guild going missing shouldn't stop everyone else being crawled
+                let (prefs, settings, recent) = match crawl_settings(pool, user).await {
+                    Ok(found) => found,
+                    Err(why) => {
+                        tracing::error!(
+                            error.cause_chain = ?why,
+                            error.message = %why,
+                            "Unable to look up settings for user ({}) because: {}",
+                            user.id,
+                            why
+                        );
+                        continue;
+                    }
+                };
+                match check_rss(
+                    &mut user,
+                    &prefs,
+                    recent,
+                    &client,
+                    "https://www.goodreads.com",
+                )
+                .await
+                {
+                    Ok(update) => {
+                        // Saved before anything is done with the books, so a failure part way
+                        // through can't record or announce them twice
                         user.update(pool)
                             .await
                             .context("unable to update timestamp in database")?;
+                        let mut years = BTreeSet::new();
+                        for book in update.hidden_books.iter() {
+                            match record_finished(pool, user, &settings, book, false).await {
+                                Ok(year) => {
+                                    years.insert(year);
+                                }
+                                Err(why) => {
+                                    tracing::error!(
+                                        error.cause_chain = ?why,
+                                        error.message = %why,
+                                        "Unable to record finished book because: {}",
+                                        why
+                                    );
+                                }
+                            }
+                        }
+                        for book in update.new_books.iter() {
+                            match record_finished(pool, user, &settings, book, true).await {
+                                Ok(year) => {
+                                    years.insert(year);
+                                }
+                                Err(why) => {
+                                    tracing::error!(
+                                        error.cause_chain = ?why,
+                                        error.message = %why,
+                                        "Unable to record finished book because: {}",
+                                        why
+                                    );
+                                }
+                            }
+                            if let Err(why) = announce_finished(
+                                &cache_and_http,
+                                pool,
+                                &follower_dms,
+                                user,
+                                &prefs,
+                                &settings,
+                                book,
+                            )
+                            .await
+                            {
+                                tracing::error!(
+                                    error.cause_chain = ?why,
+                                    error.message = %why,
+                                    "Unable to announce finished book because: {}",
+                                    why
+                                );
+                            }
+                        }
+                        // Hidden books count towards goals too, only the books themselves stay private
+                        for year in years {
+                            if let Err(why) = celebrate_milestone(
+                                &cache_and_http,
+                                pool,
+                                user,
+                                &prefs,
+                                &settings,
+                                year,
+                            )
+                            .await
+                            {
+                                tracing::error!(
+                                    error.cause_chain = ?why,
+                                    error.message = %why,
+                                    "Unable to announce goal milestone because: {}",
+                                    why
+                                );
+                            }
+                        }
+                        for change in update.changes {
+                            if let Err(why) = sync_announcement(
+                                cache_and_http.clone(),
+                                pool,
+                                change,
+                                user,
+                                &prefs,
+                                &settings,
+                            )
+                            .await
+                            {
+                                tracing::error!(
+                                    error.cause_chain = ?why,
+                                    error.message = %why,
+                                    "Unable to sync announcement because: {}",
+                                    why
+                                );
+                            }
+                        }
                     }
                     Err(why) => {
                         tracing::error!(
@@ -44,17 +180,152 @@ pub async fn crawl(cache_and_http: Arc<CacheAndHttp>, pool: Arc<SqlitePool>) ->
                         );
                     }
                 }
+                // Only used for recommendations, so a failure here shouldn't hold up the crawl
+                if shelf_checks
+                    .check_key(&(user.id, "currently-reading"))
+                    .is_ok()
+                {
+                    if let Err(why) =
+                        update_currently_reading(pool, user, &client, "https://www.goodreads.com")
+                            .await
+                    {
+                        tracing::warn!(
+                            error.cause_chain = ?why,
+                            error.message = %why,
+                            "Currently reading check failed because: {}",
+                            why
+                        );
+                    }
+                }
+                if prefs.sync_want_to_read && shelf_checks.check_key(&(user.id, "to-read")).is_ok()
+                {
+                    if let Err(why) =
+                        import_to_read(pool, user, &client, "https://www.goodreads.com").await
+                    {
+                        tracing::warn!(
+                            error.cause_chain = ?why,
+                            error.message = %why,
+                            "To-read check failed because: {}",
+                            why
+                        );
+                    }
+                }
             }
         }
         sleep(Duration::from_millis(1000 * 60)).await;
     }
 }
-#[tracing::instrument(name = "Checking user's RSS feed", skip(client, base_uri))]
+
+/// The user's preferences, their guild's settings and the announcements still kept in sync
+async fn crawl_settings(
+    pool: &SqlitePool,
+    user: &User,
+) -> anyhow::Result<(Preferences, GuildSettings, Vec<Announcement>)> {
+    let prefs = Preferences::get(pool, user.id).await?;
+    let settings = GuildSettings::get(pool, user.discord_guild_id).await?;
+    let recent = if settings.announcement_sync == SyncMode::Off {
+        Vec::new()
+    } else {
+        let since = Utc::now().timestamp() - SYNC_WINDOW_DAYS * 24 * 60 * 60;
+        Announcement::recent_for_user(pool, user.id, since).await?
+    };
+
+    Ok((prefs, settings, recent))
+}
+
+/// Add a book the user finished to their history and anything it counts towards. Books their
+/// preferences keep out of announcements count as finished, but stay private. Returns the year
+/// the book was read in.
+async fn record_finished(
+    pool: &SqlitePool,
+    user: &User,
+    settings: &GuildSettings,
+    book: &Book,
+    shared: bool,
+) -> anyhow::Result<i32> {
+    let entry = HistoryEntry::record(pool, user, book, settings.time_zone, shared).await?;
+    if let Err(why) = Badge::grant(pool, user, book.id()).await {
+        tracing::error!(
+            error.cause_chain = ?why,
+            error.message = %why,
+            "Unable to grant badges because: {}",
+            why
+        );
+    }
+    // The rating of a hidden book stays out of the wrap-up
+    let rating = if shared {
+        Some(book.rating() as i64)
+    } else {
+        None
+    };
+    GroupRead::mark_finished(
+        pool,
+        settings.guild_id,
+        user.discord_user_id,
+        book.id(),
+        rating,
+    )
+    .await?;
+    WantToRead::mark_finished(pool, user.discord_user_id, settings.guild_id, book.id()).await?;
+
+    Ok(entry.read_on.year())
+}
+
+/// Post a finished book, or hold it until quiet hours are over, and DM the user's followers
+async fn announce_finished(
+    cache_and_http: &Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    follower_dms: &FollowerDms,
+    user: &User,
+    prefs: &Preferences,
+    settings: &GuildSettings,
+    book: &Book,
+) -> anyhow::Result<()> {
+    // Digest guilds get the book in their next digest instead
+    if settings.digest_schedule == DigestSchedule::Off {
+        if let Some(release_at) = settings
+            .quiet_hours
+            .and_then(|quiet| quiet.release_at(settings.time_zone, Utc::now()))
+        {
+            // Followers hear about it when it's released
+            OutboxEntry::hold(pool, user, book, release_at.timestamp()).await?;
+            return Ok(());
+        }
+        post_book(
+            cache_and_http.clone(),
+            pool,
+            book,
+            user,
+            prefs,
+            settings,
+            user.get_channel_id(pool, book).await?,
+        )
+        .await
+        .context("Unable to post book to discord!")?;
+    }
+    if let Err(why) = follower_dms
+        .notify(cache_and_http.clone(), pool, book, user, prefs, settings)
+        .await
+    {
+        tracing::error!(
+            error.cause_chain = ?why,
+            error.message = %why,
+            "Unable to DM followers because: {}",
+            why
+        );
+    }
+
+    Ok(())
+}
+
+#[tracing::instrument(name = "Checking user's RSS feed", skip(recent, client, base_uri))]
 async fn check_rss(
     user: &mut User,
+    prefs: &Preferences,
+    recent: Vec<Announcement>,
     client: &GovernedClient,
     base_uri: &str,
-) -> anyhow::Result<Option<Vec<Book>>> {
+) -> anyhow::Result<ShelfUpdate> {
     let url = format!(
         "{}/review/list_rss/{}?shelf=read",
         base_uri, user.goodreads_user_id
@@ -62,21 +333,30 @@ async fn check_rss(
 
     let RssResult { rss, etag } = get_rss_feed(&client, &url, &user.last_etag).await?;
     user.set_last_etag(etag);
+    let mut update = ShelfUpdate {
+        changes: find_changes(recent, &rss),
+        ..ShelfUpdate::default()
+    };
     if let Some(last_book_id) = &user.last_book_id {
         // get items up to the last book id
-        let mut book_list: Vec<Book> = Vec::new();
+        let mut newest_id = None;
         for item in rss.channel.items.iter() {
             if &item.id != last_book_id {
+                newest_id.get_or_insert_with(|| item.id.to_string());
                 if let Ok(book) = item.try_into() {
-                    book_list.push(book);
+                    if prefs.should_announce(&book) {
+                        update.new_books.push(book);
+                    } else {
+                        update.hidden_books.push(book);
+                    }
                 }
             } else {
                 break;
             }
         }
-        if let Some(first) = book_list.first() {
-            user.set_last_book_id(Some(first.id().to_string()));
-            return Ok(Some(book_list));
+        if newest_id.is_some() {
+            // Books the user doesn't want announced still count as seen
+            user.set_last_book_id(newest_id);
         }
     } else {
         // Crawler has never run for this user
@@ -85,7 +365,117 @@ async fn check_rss(
         }
     }
 
-    Ok(None)
+    Ok(update)
+}
+
+/// The books on one of the user's shelves other than read. What's on them is taken as a whole
+/// each time, so unlike the read shelf there's no etag or last book to keep track of.
+#[tracing::instrument(name = "Checking user's shelf", skip(client, base_uri))]
+async fn check_shelf(
+    user: &User,
+    shelf: &str,
+    client: &GovernedClient,
+    base_uri: &str,
+) -> anyhow::Result<Vec<Book>> {
+    let url = format!(
+        "{}/review/list_rss/{}?shelf={}",
+        base_uri, user.goodreads_user_id, shelf
+    );
+
+    let RssResult { rss, .. } = get_rss_feed(client, &url, &None).await?;
+    Ok(rss
+        .channel
+        .items
+        .iter()
+        .filter_map(|item| item.try_into().ok())
+        .collect())
+}
+
+/// Swap the books kept from the user's currently-reading shelf for what's on it now
+async fn update_currently_reading(
+    pool: &SqlitePool,
+    user: &User,
+    client: &GovernedClient,
+    base_uri: &str,
+) -> anyhow::Result<()> {
+    let books = check_shelf(user, "currently-reading", client, base_uri).await?;
+    CurrentlyReading::replace(pool, user.id, &books).await
+}
+
+/// Add the books on the user's to-read shelf to their want to read list
+async fn import_to_read(
+    pool: &SqlitePool,
+    user: &User,
+    client: &GovernedClient,
+    base_uri: &str,
+) -> anyhow::Result<()> {
+    let books = check_shelf(user, "to-read", client, base_uri).await?;
+    WantToRead::import(pool, user.discord_user_id, user.discord_guild_id, &books).await?;
+    Ok(())
+}
+
+/// Compare recent announcements against the read shelf. A book missing from the feed only counts
+/// as removed when the feed reaches back past the announcement, since Goodreads only sends the
+/// most recently shelved books.
+fn find_changes(recent: Vec<Announcement>, rss: &Rss) -> Vec<AnnouncementChange> {
+    let mut books: Vec<Book> = rss
+        .channel
+        .items
+        .iter()
+        .filter_map(|item| item.try_into().ok())
+        .collect();
+    let oldest_shelved = books.iter().map(|book| book.completed().timestamp()).min();
+
+    let mut changes = Vec::new();
+    for announcement in recent {
+        match books
+            .iter()
+            .position(|book| book.id() == &announcement.book_id)
+        {
+            Some(index) => {
+                if books[index].rating() as i64 != announcement.rating {
+                    let book = books.swap_remove(index);
+                    changes.push(AnnouncementChange::Rerated(announcement, Box::new(book)));
+                }
+            }
+            None => {
+                if matches!(oldest_shelved, Some(oldest) if oldest < announcement.announced_at) {
+                    changes.push(AnnouncementChange::Removed(announcement));
+                }
+            }
+        }
+    }
+    changes
+}
+
+async fn sync_announcement(
+    cache_and_http: Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    change: AnnouncementChange,
+    user: &User,
+    prefs: &Preferences,
+    settings: &GuildSettings,
+) -> anyhow::Result<()> {
+    match change {
+        AnnouncementChange::Rerated(mut announcement, book) => {
+            HistoryEntry::set_rating(pool, user.id, book.id(), book.rating() as i64).await?;
+            update_announcement(
+                cache_and_http,
+                pool,
+                &mut announcement,
+                &book,
+                user,
+                prefs,
+                settings,
+            )
+            .await
+        }
+        AnnouncementChange::Removed(mut announcement) => {
+            retract_announcement(&cache_and_http.http, pool, &mut announcement, settings).await?;
+            // Only once the announcement is retracted, so a retry can't remove an earlier read
+            HistoryEntry::remove(pool, user.id, &announcement.book_id).await
+        }
+    }
 }
 
 #[tracing::instrument(name = "Retrieving RSS feed", skip(client))]
@@ -126,12 +516,16 @@ async fn get_rss_feed(
 
 #[cfg(test)]
 mod tests {
-    use crate::crawler::crawler::{check_rss, get_rss_feed};
-    use crate::crawler::{GovernedClient, RssResult};
-    use crate::model::User;
+    use crate::crawler::crawler::{
+        check_rss, check_shelf, find_changes, get_rss_feed, AnnouncementChange,
+    };
+    use crate::crawler::{GovernedClient, Rss, RssResult};
+    use crate::model::{Announcement, Preferences, User};
+    use chrono_tz::Tz;
     use claim::{assert_err, assert_none, assert_ok, assert_some};
+    use quick_xml::de::from_str;
     use tokio::fs::read_to_string;
-    use wiremock::matchers::{any, method};
+    use wiremock::matchers::{any, method, query_param};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     async fn get_test_data() -> String {
@@ -140,6 +534,25 @@ mod tests {
             .expect("Unable to read in test data")
     }
 
+    fn announcement(book_id: &str, rating: i64, announced_at: i64) -> Announcement {
+        Announcement {
+            id: 0,
+            user_id: 0,
+            guild_id: 0,
+            book_id: book_id.to_string(),
+            channel_id: 0,
+            message_id: 0,
+            thread_id: None,
+            announced_at,
+            rating,
+            webhook_id: None,
+            retracted: false,
+            title: String::new(),
+            author: String::new(),
+            url: String::new(),
+        }
+    }
+
     #[tokio::test]
     async fn get_rss_feed_fails_on_unmodified_etag() {
         let client = GovernedClient::default();
@@ -231,9 +644,18 @@ mod tests {
 
         let client = GovernedClient::default();
         let mut user = User::new(0, 0, 0, 0, None, 0, None);
-        assert_none!(assert_ok!(
-            check_rss(&mut user, &client, &mock_server.uri()).await
-        ));
+        assert!(assert_ok!(
+            check_rss(
+                &mut user,
+                &Preferences::default_for(0),
+                Vec::new(),
+                &client,
+                &mock_server.uri()
+            )
+            .await
+        )
+        .new_books
+        .is_empty());
         assert_some!(user.last_book_id);
     }
 
@@ -265,9 +687,18 @@ mod tests {
             0,
             Some("4981".to_string()),
         );
-        assert_none!(assert_ok!(
-            check_rss(&mut user, &client, &mock_server.uri()).await
-        ));
+        assert!(assert_ok!(
+            check_rss(
+                &mut user,
+                &Preferences::default_for(0),
+                Vec::new(),
+                &client,
+                &mock_server.uri()
+            )
+            .await
+        )
+        .new_books
+        .is_empty());
         assert_eq!(assert_some!(user.last_etag), "new-etag");
     }
 
@@ -287,11 +718,137 @@ mod tests {
 
         let client = GovernedClient::default();
         let mut user = User::new(0, 0, 0, 0, None, 0, Some("43848929".to_string()));
-        let book_list = assert_some!(assert_ok!(
-            check_rss(&mut user, &client, &mock_server.uri()).await
-        ));
+        let book_list = assert_ok!(
+            check_rss(
+                &mut user,
+                &Preferences::default_for(0),
+                Vec::new(),
+                &client,
+                &mock_server.uri()
+            )
+            .await
+        )
+        .new_books;
         assert_eq!(book_list.len(), 2);
         assert_eq!(assert_some!(user.last_etag), "new-etag");
         assert_eq!(assert_some!(user.last_book_id), "4981");
+        // Slaughterhouse-Five went on the shelves on the 8th and was finished on the 12th
+        assert_eq!(book_list[0].reading_days(Tz::UTC), Some(4));
+        assert_none!(book_list[1].reading_days(Tz::UTC));
+    }
+
+    #[tokio::test]
+    async fn check_rss_skips_books_filtered_by_preferences() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(get_test_data().await))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = GovernedClient::default();
+        let mut prefs = Preferences::default_for(0);
+        prefs.min_rating = 4;
+        let mut user = User::new(0, 0, 0, 0, None, 0, Some("43848929".to_string()));
+        let book_list =
+            assert_ok!(check_rss(&mut user, &prefs, Vec::new(), &client, &mock_server.uri()).await)
+                .new_books;
+        assert_eq!(book_list.len(), 1);
+        assert_eq!(book_list[0].title(), "Meditations");
+        assert_eq!(assert_some!(user.last_book_id), "4981");
+    }
+
+    #[tokio::test]
+    async fn check_rss_advances_last_book_id_when_every_book_is_filtered() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(get_test_data().await))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = GovernedClient::default();
+        let mut prefs = Preferences::default_for(0);
+        prefs.set_skip_shelves("classics, philosophy");
+        let mut user = User::new(0, 0, 0, 0, None, 0, Some("43848929".to_string()));
+        assert!(assert_ok!(
+            check_rss(&mut user, &prefs, Vec::new(), &client, &mock_server.uri()).await
+        )
+        .new_books
+        .is_empty());
+        assert_eq!(assert_some!(user.last_book_id), "4981");
+    }
+
+    #[tokio::test]
+    async fn check_currently_reading_returns_every_book_on_the_shelf() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(query_param("shelf", "currently-reading"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(get_test_data().await))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = GovernedClient::default();
+        // The read shelf's last book doesn't matter here
+        let user = User::new(0, 0, 0, 0, None, 0, Some("43848929".to_string()));
+        let books =
+            assert_ok!(check_shelf(&user, "currently-reading", &client, &mock_server.uri()).await);
+        assert_eq!(books.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn check_shelf_asks_for_the_shelf_it_was_given() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(query_param("shelf", "to-read"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(get_test_data().await))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = GovernedClient::default();
+        let user = User::new(0, 0, 0, 0, None, 0, None);
+        assert_ok!(check_shelf(&user, "to-read", &client, &mock_server.uri()).await);
+    }
+
+    #[tokio::test]
+    async fn find_changes_notices_new_ratings() {
+        let rss: Rss = assert_ok!(from_str(&get_test_data().await));
+        let recent = vec![
+            announcement("4981", 5, 1657728000),
+            announcement("30659", 4, 1657728000),
+        ];
+
+        let changes = find_changes(recent, &rss);
+        assert_eq!(changes.len(), 1);
+        match &changes[0] {
+            AnnouncementChange::Rerated(announcement, book) => {
+                assert_eq!(announcement.book_id, "4981");
+                assert_eq!(book.rating(), 3);
+            }
+            change => panic!("Expected a new rating, got {:?}", change),
+        }
+    }
+
+    #[tokio::test]
+    async fn find_changes_only_removes_books_the_feed_reaches_back_to() {
+        let rss: Rss = assert_ok!(from_str(&get_test_data().await));
+        // The oldest book in the test feed was shelved on June 22nd 2022
+        let recent = vec![
+            announcement("1", 4, 1656633600),
+            announcement("2", 4, 1654041600),
+        ];
+
+        let changes = find_changes(recent, &rss);
+        assert_eq!(changes.len(), 1);
+        match &changes[0] {
+            AnnouncementChange::Removed(announcement) => assert_eq!(announcement.book_id, "1"),
+            change => panic!("Expected a removal, got {:?}", change),
+        }
     }
 }
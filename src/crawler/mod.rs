@@ -1,7 +1,11 @@
+mod authors;
+mod book_info;
 mod crawler;
 mod governed_client;
 mod rss;
 
+pub use authors::watch_authors;
+pub(crate) use book_info::{book_id_from_url, book_info, search_books, BookInfo};
 pub use crawler::crawl;
 pub(crate) use governed_client::GovernedClient;
 pub(crate) use rss::*;
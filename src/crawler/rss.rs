@@ -15,6 +15,22 @@ pub struct Item {
     pub author: String,
     #[serde(rename = "book_medium_image_url", default)]
     pub image_url: String,
+    #[serde(default)]
+    pub user_review: String,
+    #[serde(default)]
+    pub user_shelves: String,
+    #[serde(default)]
+    pub user_read_at: String,
+    #[serde(default)]
+    pub user_date_added: String,
+    #[serde(default)]
+    pub book: BookDetails,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Default)]
+pub struct BookDetails {
+    #[serde(default)]
+    pub num_pages: String,
 }
 
 impl TryInto<Book> for &Item {
@@ -30,6 +46,13 @@ impl TryInto<Book> for &Item {
             &self.author,
             &self.image_url,
         )
+        .map(|book| {
+            book.with_review(&self.user_review)
+                .with_shelves(&self.user_shelves)
+                .with_pages(self.book.num_pages.trim().parse().ok())
+                .with_read_at(&self.user_read_at)
+                .with_date_added(&self.user_date_added)
+        })
         .ok_or("Unable to create Book from Item".to_string())
     }
 }
@@ -0,0 +1,92 @@
+use crate::discord::common::{badge_line, DatabaseContainer};
+use crate::model::{Badge, EarnedBadge, GuildSettings, User};
+use anyhow::anyhow;
+use chrono::TimeZone;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::utils::{parse_username, Colour};
+
+const BADGES_USAGE: &str = "Usage: `~badges [@member]`";
+
+#[command]
+pub async fn badges(ctx: &serenity::prelude::Context, msg: &Message, args: Args) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?;
+        let settings = GuildSettings::get(pool, guild_id.0 as i64).await?;
+
+        let member = match args.current() {
+            Some(mention) => match parse_username(mention) {
+                Some(member) => UserId(member),
+                None => {
+                    msg.reply(ctx, BADGES_USAGE).await?;
+                    return Ok(());
+                }
+            },
+            None => msg.author.id,
+        };
+        let user = match User::find(pool, member.0 as i64, settings.guild_id).await? {
+            Some(user) => user,
+            None => {
+                let reply = if member == msg.author.id {
+                    "You aren't on the _lurk list_ yet! Type `~lurk <good reads id>` first."
+                } else {
+                    "They aren't on the _lurk list_, so I don't know what they've read."
+                };
+                msg.reply(ctx, reply).await?;
+                return Ok(());
+            }
+        };
+
+        let earned = EarnedBadge::for_user(pool, user.id).await?;
+        let discord_user = member.to_user(ctx).await?;
+        let display_name = discord_user
+            .nick_in(ctx, guild_id)
+            .await
+            .unwrap_or_else(|| discord_user.name.clone());
+
+        let earned_lines: Vec<String> = earned
+            .iter()
+            .map(|earned| {
+                format!(
+                    "{} · {}",
+                    badge_line(&earned.badge),
+                    settings
+                        .time_zone
+                        .timestamp(earned.earned_at, 0)
+                        .format("%B %-d, %Y")
+                )
+            })
+            .collect();
+        let to_earn: Vec<String> = Badge::ALL
+            .iter()
+            .filter(|badge| !earned.iter().any(|earned| earned.badge == **badge))
+            .map(badge_line)
+            .collect();
+
+        msg.channel_id
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title(format!("🏅 {}'s badges", display_name))
+                        .colour(Colour::GOLD);
+                    if earned_lines.is_empty() {
+                        e.description("No badges yet. They come with finishing books!");
+                    } else {
+                        e.description(earned_lines.join("\n"));
+                    }
+                    if !to_earn.is_empty() {
+                        e.field("Still to earn", to_earn.join("\n"), false);
+                    }
+                    e
+                })
+            })
+            .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use crate::crawler::{book_id_from_url, book_info};
+use crate::discord::common::{DatabaseContainer, GoodreadsContainer};
+use crate::model::{GroupRead, GroupReadMember, GuildSettings, User};
+use anyhow::anyhow;
+use chrono::Utc;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::utils::Colour;
+
+const CLUB_USAGE: &str = r#"Usage:
+`~club` - show how the group read is going
+`~club start <goodreads book url> <YYYY-MM-DD>` - start a group read, finishing by the end of that day
+`~club join` - take part in the group read
+`~club leave` - stop taking part
+`~club cancel` - call the group read off, if you started it"#;
+
+/// Embed fields only hold 1024 characters, and each member is a mention
+const MEMBERS_SHOWN: usize = 30;
+
+#[command]
+pub async fn club(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let (Some(database), Some(goodreads)) = (
+        data.get::<DatabaseContainer>(),
+        data.get::<GoodreadsContainer>(),
+    ) {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let discord_user_id = msg.author.id.0 as i64;
+        let settings = GuildSettings::get(pool, guild_id).await?;
+        let running = GroupRead::running(pool, guild_id).await?;
+
+        let action = args.single::<String>().unwrap_or_default().to_lowercase();
+        if matches!(action.as_str(), "start" | "join")
+            && User::find(pool, discord_user_id, guild_id).await?.is_none()
+        {
+            msg.reply(
+                ctx,
+                "You aren't on the _lurk list_ yet! Type `~lurk <good reads id>` first, so I can tell when you've finished.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let reply = match (action.as_str(), running) {
+            ("", Some(group_read)) => {
+                let members = group_read.members(pool).await?;
+                msg.channel_id
+                    .send_message(ctx, |m| {
+                        m.embed(|e| {
+                            e.title(format!("📚 Group read: {}", group_read.title))
+                                .url(&group_read.url)
+                                .colour(Colour::TEAL)
+                                .description(format!(
+                                    "by {}\nFinish by {}",
+                                    group_read.author,
+                                    group_read
+                                        .last_day(settings.time_zone)
+                                        .format("%A %B %-d")
+                                ))
+                                .field(
+                                    format!(
+                                        "{} of {} finished",
+                                        members.iter().filter(|member| member.finished()).count(),
+                                        members.len()
+                                    ),
+                                    member_lines(&members),
+                                    false,
+                                )
+                                .footer(|f| f.text("Type ~club join to take part"))
+                        })
+                    })
+                    .await?;
+                return Ok(());
+            }
+            ("start", Some(group_read)) => format!(
+                "Hmm, the server is already reading {}. It has to finish or be cancelled before the next one starts.",
+                group_read.title
+            ),
+            ("start", None) => {
+                let book_id = args.single::<String>().ok().and_then(|url| book_id_from_url(&url));
+                let deadline = args.single::<String>().ok().and_then(|deadline| {
+                    GroupRead::parse_deadline(&deadline, settings.time_zone, Utc::now())
+                });
+                match (book_id, deadline) {
+                    (None, _) => format!(
+                        "Hmm, that doesn't look like a Goodreads book link.\n{}",
+                        CLUB_USAGE
+                    ),
+                    (_, None) => format!(
+                        "Hmm, the deadline needs to be a day still to come, like `2022-10-31`.\n{}",
+                        CLUB_USAGE
+                    ),
+                    (Some(book_id), Some(deadline)) => {
+                        match book_info(goodreads, "https://www.goodreads.com", &book_id).await {
+                            Err(why) => {
+                                tracing::warn!("Unable to look up book on Goodreads: {}", why);
+                                "Hmm, Goodreads isn't answering right now. Try again in a bit.".to_string()
+                            }
+                            Ok(None) => "Hmm, I couldn't find that book on Goodreads.".to_string(),
+                            Ok(Some(book)) => {
+                                let group_read = GroupRead::new(
+                                    guild_id,
+                                    discord_user_id,
+                                    &book.book_id,
+                                    &book.title,
+                                    &book.author.name,
+                                    &book.url(),
+                                    deadline,
+                                );
+                                match group_read.start(pool).await? {
+                                    Some(group_read) => format!(
+                                        "📚 The server is reading **{}** by {}, finishing by {}! Type `~club join` to take part. I'll post progress in <#{}> as members finish it, and a wrap-up when time's up.",
+                                        group_read.title,
+                                        group_read.author,
+                                        group_read.last_day(settings.time_zone).format("%A %B %-d"),
+                                        settings.notify_channel_id
+                                    ),
+                                    None => "Hmm, someone beat you to it, the server already has a group read going.".to_string(),
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            ("join", Some(group_read)) => {
+                if group_read.join(pool, discord_user_id).await? {
+                    format!(
+                        "You're in! Finish **{}** by {} and I'll notice when it's on your read shelf.",
+                        group_read.title,
+                        group_read.last_day(settings.time_zone).format("%A %B %-d")
+                    )
+                } else {
+                    "You've already joined.".to_string()
+                }
+            }
+            ("leave", Some(group_read)) => {
+                if group_read.leave(pool, discord_user_id).await? {
+                    format!("You're no longer reading **{}** with the server.", group_read.title)
+                } else {
+                    "You weren't taking part.".to_string()
+                }
+            }
+            ("cancel", Some(group_read)) => {
+                if group_read.started_by == discord_user_id {
+                    group_read.cancel(pool).await?;
+                    format!("The group read of **{}** is off.", group_read.title)
+                } else {
+                    format!(
+                        "Hmm, only <@{}> can cancel it, since they started it.",
+                        group_read.started_by
+                    )
+                }
+            }
+            ("" | "join" | "leave" | "cancel", None) => {
+                "There's no group read going. Type `~club start <goodreads book url> <YYYY-MM-DD>` to start one.".to_string()
+            }
+            _ => CLUB_USAGE.to_string(),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
+
+fn member_lines(members: &[GroupReadMember]) -> String {
+    let mut lines: Vec<String> = members
+        .iter()
+        .take(MEMBERS_SHOWN)
+        .map(|member| {
+            if member.finished() {
+                format!("✅ <@{}>", member.discord_user_id)
+            } else {
+                format!("📖 <@{}>", member.discord_user_id)
+            }
+        })
+        .collect();
+    if members.len() > MEMBERS_SHOWN {
+        lines.push(format!("…and {} more", members.len() - MEMBERS_SHOWN));
+    }
+    lines.join("\n")
+}
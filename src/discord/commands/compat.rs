@@ -0,0 +1,140 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{Compatibility, HistoryEntry, SharedBook, User};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::utils::Colour;
+
+const COMPAT_USAGE: &str = "Usage: `~compat @member [@member]`";
+
+/// How many books in common the reply lists
+const SHARED_BOOKS_SHOWN: usize = 10;
+
+#[command]
+pub async fn compat(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+
+        // With one member it's them and whoever asked
+        let (first, second) = match (args.single::<UserId>(), args.single::<UserId>()) {
+            (Ok(first), Ok(second)) => (first, second),
+            (Ok(other), Err(_)) => (msg.author.id, other),
+            _ => {
+                msg.reply(ctx, COMPAT_USAGE).await?;
+                return Ok(());
+            }
+        };
+        if first == second {
+            msg.reply(ctx, "Hmm, that's the same member twice.").await?;
+            return Ok(());
+        }
+
+        for member in [first, second] {
+            if User::find(pool, member.0 as i64, guild_id).await?.is_none() {
+                msg.reply(
+                    ctx,
+                    format!(
+                        "<@{}> isn't on the _lurk list_, so I don't know what they've read.",
+                        member
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        }
+
+        // Only what their preferences let us announce, since the reply is public
+        let mut entries = HistoryEntry::for_member(pool, first.0 as i64, guild_id, false).await?;
+        entries.extend(HistoryEntry::for_member(pool, second.0 as i64, guild_id, false).await?);
+        let compatibility = Compatibility::between(first.0 as i64, second.0 as i64, &entries);
+
+        if compatibility.shared.is_empty() {
+            msg.reply(
+                ctx,
+                format!(
+                    "<@{}> and <@{}> haven't read any of the same books yet.",
+                    first, second
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let agreement = match compatibility.agreement {
+            Some(agreement) => format!(
+                "{:.0}% over {} rated by both",
+                agreement * 100.0,
+                books(compatibility.rated)
+            ),
+            None => "No book rated by both yet".to_string(),
+        };
+        let mut shared: Vec<String> = compatibility
+            .shared
+            .iter()
+            .take(SHARED_BOOKS_SHOWN)
+            .map(shared_book_line)
+            .collect();
+        if compatibility.shared.len() > SHARED_BOOKS_SHOWN {
+            shared.push(format!(
+                "…and {} more",
+                compatibility.shared.len() - SHARED_BOOKS_SHOWN
+            ));
+        }
+
+        msg.channel_id
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title("💞 Reading compatibility")
+                        .colour(Colour::FABLED_PINK)
+                        .description(format!(
+                            "<@{}> and <@{}> are **{}%** compatible.",
+                            first, second, compatibility.score
+                        ))
+                        .field("Books in common", compatibility.shared.len(), true)
+                        .field("Rating agreement", agreement, true)
+                        .field("Read by both", shared.join("\n"), false)
+                })
+                .reference_message(msg)
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn shared_book_line(book: &SharedBook) -> String {
+    format!(
+        "[{}]({}) · {} / {}",
+        book.entry.title,
+        book.entry.url,
+        stars(book.first_rating),
+        stars(book.second_rating)
+    )
+}
+
+fn stars(rating: i64) -> String {
+    if rating > 0 {
+        format!("{}⭐", rating)
+    } else {
+        "unrated".to_string()
+    }
+}
+
+fn books(count: usize) -> String {
+    if count == 1 {
+        "1 book".to_string()
+    } else {
+        format!("{} books", count)
+    }
+}
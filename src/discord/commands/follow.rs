@@ -0,0 +1,72 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{Follow, Preferences, User};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+
+#[command]
+pub async fn follow(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let follower_id = msg.author.id.0 as i64;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("expected a guild attached to message"))?
+            .0 as i64;
+
+        if args.is_empty() {
+            let following = Follow::following(pool, follower_id, guild_id).await?;
+            let reply = if following.is_empty() {
+                "You aren't following anyone. Type `~follow @member` to get a DM when they finish a book.".to_string()
+            } else {
+                let members: Vec<String> = following
+                    .iter()
+                    .map(|followee_id| format!("<@{}>", followee_id))
+                    .collect();
+                format!("You're following {}.", members.join(", "))
+            };
+            msg.reply(ctx, reply).await?;
+            return Ok(());
+        }
+
+        let followee = match args.single::<UserId>() {
+            Ok(followee) => followee,
+            Err(_) => {
+                msg.reply(ctx, "Usage: `~follow @member`").await?;
+                return Ok(());
+            }
+        };
+        let followee_id = followee.0 as i64;
+
+        let outcome = if followee_id == follower_id {
+            Err("you can't follow yourself")
+        } else {
+            match User::find(pool, followee_id, guild_id).await? {
+                None => Err("they aren't lurking in this server"),
+                Some(user) if !Preferences::get(pool, user.id).await?.allow_followers => {
+                    Err("they've turned followers off")
+                }
+                Some(_) => Ok(Follow::add(pool, follower_id, followee_id, guild_id).await?),
+            }
+        };
+
+        let reply = match outcome {
+            Ok(true) => format!(
+                "You're now following <@{}>. I'll DM you when they finish a book.",
+                followee_id
+            ),
+            Ok(false) => format!("You're already following <@{}>.", followee_id),
+            Err(why) => format!("Hmm, {}.", why),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
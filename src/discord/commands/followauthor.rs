@@ -0,0 +1,84 @@
+use crate::crawler::search_books;
+use crate::discord::common::{DatabaseContainer, GoodreadsContainer};
+use crate::model::AuthorFollow;
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+#[command]
+pub async fn followauthor(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let (Some(database), Some(goodreads)) = (
+        data.get::<DatabaseContainer>(),
+        data.get::<GoodreadsContainer>(),
+    ) {
+        let pool = &**database;
+        let discord_user_id = msg.author.id.0 as i64;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("expected a guild attached to message"))?
+            .0 as i64;
+
+        let name = args.rest().trim();
+        if name.is_empty() {
+            let following = AuthorFollow::following(pool, discord_user_id, guild_id).await?;
+            let reply = if following.is_empty() {
+                "You aren't following any authors. Type `~followauthor <name>` to get a DM when they have a new book out.".to_string()
+            } else {
+                let authors: Vec<String> = following
+                    .iter()
+                    .map(|author| format!("**{}**", author.name))
+                    .collect();
+                format!("You're following {}.", authors.join(", "))
+            };
+            msg.reply(ctx, reply).await?;
+            return Ok(());
+        }
+
+        let books = match search_books(goodreads, "https://www.goodreads.com", name).await {
+            Ok(books) => books,
+            Err(why) => {
+                tracing::warn!("Unable to search Goodreads for author: {}", why);
+                msg.reply(
+                    ctx,
+                    "Hmm, Goodreads isn't answering right now. Try again in a bit.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        // A search for an author turns up books about them too, so an exact name wins
+        let author = books
+            .iter()
+            .map(|book| &book.author)
+            .find(|author| author.name.to_lowercase() == name.to_lowercase())
+            .or_else(|| books.first().map(|book| &book.author));
+
+        let reply = match author {
+            None => format!(
+                "Hmm, I couldn't find an author called {} on Goodreads.",
+                name
+            ),
+            Some(author) => {
+                if AuthorFollow::add(pool, discord_user_id, guild_id, author.id, &author.name)
+                    .await?
+                {
+                    format!(
+                        "You're now following **{}**. I'll DM you when they have a new book out.",
+                        author.name
+                    )
+                } else {
+                    format!("You're already following **{}**.", author.name)
+                }
+            }
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{GuildSettings, HistoryEntry, ReadingGoal, User};
+use anyhow::anyhow;
+use chrono::{Datelike, Utc};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+const GOAL_USAGE: &str = r#"Usage:
+`~goal` - see how your goal for this year is going
+`~goal <number>` - aim to finish this many books this year
+`~goal off` - drop this year's goal"#;
+
+/// Anything bigger is almost certainly a typo
+const MAX_GOAL: i64 = 1000;
+
+#[command]
+pub async fn goal(ctx: &serenity::prelude::Context, msg: &Message, args: Args) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let discord_guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+
+        let user = match User::find(pool, msg.author.id.0 as i64, discord_guild_id).await? {
+            Some(user) => user,
+            None => {
+                msg.reply(
+                    ctx,
+                    "You aren't on the _lurk list_ yet! Type `~lurk <good reads id>` first.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        let settings = GuildSettings::get(pool, discord_guild_id).await?;
+        let year = Utc::now().with_timezone(&settings.time_zone).year();
+
+        let value = args.rest().trim().to_lowercase();
+        let reply = if value.is_empty() {
+            match ReadingGoal::get(pool, user.id, year).await? {
+                Some(goal) => {
+                    let books = HistoryEntry::count_in_year(pool, user.id, year).await?;
+                    progress(&goal, books)
+                }
+                None => format!(
+                    "You haven't set a goal for {}. Type `~goal <number>` to set one.",
+                    year
+                ),
+            }
+        } else if value == "off" {
+            ReadingGoal::clear(pool, user.id, year).await?;
+            format!("Dropped your goal for {}.", year)
+        } else {
+            match value
+                .parse::<i64>()
+                .ok()
+                .filter(|target| (1..=MAX_GOAL).contains(target))
+            {
+                Some(target) => {
+                    let goal = ReadingGoal::set(pool, user.id, year, target).await?;
+                    let books = HistoryEntry::count_in_year(pool, user.id, year).await?;
+                    format!("Saved! {}", progress(&goal, books))
+                }
+                None => format!(
+                    "Hmm, goals need to be a number of books from 1 to {}.\n{}",
+                    MAX_GOAL, GOAL_USAGE
+                ),
+            }
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
+
+fn progress(goal: &ReadingGoal, books: i64) -> String {
+    format!(
+        "Your goal is {}, and you've read {} so far ({}%).",
+        goal,
+        books,
+        books * 100 / goal.target
+    )
+}
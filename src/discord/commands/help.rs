@@ -1,11 +1,12 @@
-use crate::discord::common::HELP_STR;
+use crate::discord::help::{help_topic, HELP_STR};
 use serenity::framework::standard::macros::command;
-use serenity::framework::standard::CommandResult;
+use serenity::framework::standard::{Args, CommandResult};
 use serenity::model::channel::Message;
 
 #[command]
-pub async fn help(ctx: &serenity::prelude::Context, msg: &Message) -> CommandResult {
-    msg.reply(ctx, HELP_STR).await?;
+pub async fn help(ctx: &serenity::prelude::Context, msg: &Message, args: Args) -> CommandResult {
+    let reply = help_topic(args.rest()).unwrap_or(HELP_STR);
+    msg.reply(ctx, reply).await?;
 
     Ok(())
 }
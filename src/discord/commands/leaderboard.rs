@@ -0,0 +1,128 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{GuildSettings, HistoryEntry, LeaderboardMetric, ReadingGoal, Standing};
+use anyhow::anyhow;
+use chrono::{Datelike, NaiveDate, Utc};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::utils::Colour;
+use std::collections::HashMap;
+
+const LEADERBOARD_USAGE: &str = "Usage: `~leaderboard [year|month] [books|pages]`";
+
+/// How many members make it onto the board
+const LEADERBOARD_SIZE: usize = 10;
+
+const MEDALS: [&str; 3] = ["🥇", "🥈", "🥉"];
+
+#[command]
+pub async fn leaderboard(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+
+        let mut monthly = false;
+        let mut metric = LeaderboardMetric::Books;
+        for arg in args.iter::<String>() {
+            let arg = arg?.to_lowercase();
+            match arg.as_str() {
+                "year" => monthly = false,
+                "month" => monthly = true,
+                _ => match LeaderboardMetric::parse(&arg) {
+                    Some(parsed) => metric = parsed,
+                    None => {
+                        msg.reply(ctx, LEADERBOARD_USAGE).await?;
+                        return Ok(());
+                    }
+                },
+            }
+        }
+
+        let settings = GuildSettings::get(pool, guild_id).await?;
+        let today = Utc::now()
+            .with_timezone(&settings.time_zone)
+            .date()
+            .naive_local();
+        let (from, until, period) = if monthly {
+            let from = NaiveDate::from_ymd(today.year(), today.month(), 1);
+            (from, today, today.format("%B %Y").to_string())
+        } else {
+            let from = NaiveDate::from_ymd(today.year(), 1, 1);
+            (from, today, today.year().to_string())
+        };
+
+        let history = HistoryEntry::shared_read_between(pool, guild_id, from, until).await?;
+        let standings = Standing::rank(&history, metric);
+        if standings.is_empty() {
+            msg.reply(
+                ctx,
+                format!("Nobody has finished a book in {} yet.", period),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // Goals are yearly, so they only make sense next to a yearly board
+        let goals: HashMap<i64, i64> = if monthly {
+            HashMap::new()
+        } else {
+            ReadingGoal::for_guild(pool, guild_id, today.year())
+                .await?
+                .into_iter()
+                .map(|goal| (goal.user_id, goal.target))
+                .collect()
+        };
+
+        let lines: Vec<String> = standings
+            .iter()
+            .take(LEADERBOARD_SIZE)
+            .enumerate()
+            .map(|(place, standing)| {
+                standing_line(place, standing, goals.get(&standing.user_id).copied())
+            })
+            .collect();
+        let title = match metric {
+            LeaderboardMetric::Books => format!("🏆 Most books finished in {}", period),
+            LeaderboardMetric::Pages => format!("🏆 Most pages read in {}", period),
+        };
+        msg.channel_id
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title(title)
+                        .colour(Colour::GOLD)
+                        .description(lines.join("\n"))
+                })
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn standing_line(place: usize, standing: &Standing, goal: Option<i64>) -> String {
+    let rank = MEDALS
+        .get(place)
+        .map(|medal| medal.to_string())
+        .unwrap_or_else(|| format!("{}.", place + 1));
+    let books = if standing.books == 1 {
+        "1 book".to_string()
+    } else {
+        format!("{} books", standing.books)
+    };
+    let mut line = format!(
+        "{} <@{}> · {} · {} pages",
+        rank, standing.discord_user_id, books, standing.pages
+    );
+    if let Some(target) = goal {
+        line.push_str(&format!(" · 🎯 {}/{}", standing.books, target));
+    }
+    line
+}
@@ -1,9 +1,72 @@
+mod badges;
+mod club;
+mod compat;
+mod follow;
+mod followauthor;
+mod goal;
 mod help;
+mod leaderboard;
 mod lurk;
+mod nominate;
+mod prefs;
+mod recommend;
+mod route;
+mod set_announcement_sync;
+mod set_club_favourite;
+mod set_digest;
 mod set_notify_channel;
+mod set_quiet_hours;
+mod set_recap;
+mod set_template;
+mod set_threads;
+mod set_timezone;
+mod set_webhook_mode;
+mod stats;
+mod twins;
+mod unfollow;
+mod unfollowauthor;
 mod unlurk;
+mod vote;
+mod whohasread;
+mod wtr;
 
+pub use badges::*;
+pub use club::*;
+pub use compat::*;
+pub use follow::*;
+pub use followauthor::*;
+pub use goal::*;
 pub use help::*;
+pub use leaderboard::*;
 pub use lurk::*;
+pub use nominate::*;
+pub use prefs::*;
+pub use recommend::*;
+pub use route::*;
+pub use set_announcement_sync::*;
+pub use set_club_favourite::*;
+pub use set_digest::*;
 pub use set_notify_channel::*;
+pub use set_quiet_hours::*;
+pub use set_recap::*;
+pub use set_template::*;
+pub use set_threads::*;
+pub use set_timezone::*;
+pub use set_webhook_mode::*;
+pub use stats::*;
+pub use twins::*;
+pub use unfollow::*;
+pub use unfollowauthor::*;
 pub use unlurk::*;
+pub use vote::*;
+pub use whohasread::*;
+pub use wtr::*;
+
+/// Parse the `on`/`off` value taken by settings commands
+fn parse_toggle(value: &str) -> Result<bool, &'static str> {
+    match value.to_lowercase().as_str() {
+        "on" | "yes" | "true" => Ok(true),
+        "off" | "no" | "false" => Ok(false),
+        _ => Err("that setting needs to be `on` or `off`"),
+    }
+}
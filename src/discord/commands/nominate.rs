@@ -0,0 +1,174 @@
+use crate::crawler::{book_id_from_url, book_info};
+use crate::discord::common::{DatabaseContainer, GoodreadsContainer};
+use crate::discord::polls::nomination_lines;
+use crate::model::{Nomination, Poll, MAX_NOMINATIONS};
+use anyhow::anyhow;
+use chrono::Utc;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::utils::Colour;
+
+const NOMINATE_USAGE: &str = r#"Usage:
+`~nominate` - see what's been nominated for the next book
+`~nominate <goodreads book url>` - put a book forward"#;
+
+#[command]
+pub async fn nominate(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let (Some(database), Some(goodreads)) = (
+        data.get::<DatabaseContainer>(),
+        data.get::<GoodreadsContainer>(),
+    ) {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let discord_user_id = msg.author.id.0 as i64;
+        let poll = Poll::current(pool, guild_id).await?;
+        let nominations = match &poll {
+            Some(poll) => poll.nominations(pool).await?,
+            None => Vec::new(),
+        };
+
+        let url = args.single::<String>().unwrap_or_default();
+        if url.is_empty() {
+            match poll {
+                Some(poll) if !nominations.is_empty() => {
+                    let next = if poll.closes_at.is_some() {
+                        "Voting's open · type ~vote to cast yours".to_string()
+                    } else {
+                        "Type ~nominate <goodreads book url> to put another book forward"
+                            .to_string()
+                    };
+                    msg.channel_id
+                        .send_message(ctx, |m| {
+                            m.embed(|e| {
+                                e.title("📥 Nominated for the next book")
+                                    .colour(Colour::PURPLE)
+                                    .description(nomination_lines(&nominations))
+                                    .footer(|f| f.text(next))
+                            })
+                        })
+                        .await?;
+                }
+                _ => {
+                    msg.reply(
+                        ctx,
+                        "Nobody's nominated anything yet. Type `~nominate <goodreads book url>` to put a book forward.",
+                    )
+                    .await?;
+                }
+            }
+            return Ok(());
+        }
+
+        let book_id = match book_id_from_url(&url) {
+            Some(book_id) => book_id,
+            None => {
+                msg.reply(
+                    ctx,
+                    format!(
+                        "Hmm, that doesn't look like a Goodreads book link.\n{}",
+                        NOMINATE_USAGE
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        if matches!(&poll, Some(poll) if poll.closes_at.is_some()) {
+            msg.reply(
+                ctx,
+                "Voting's already started, so nominations are closed. Type `~vote` to cast yours.",
+            )
+            .await?;
+            return Ok(());
+        }
+        if nominations.len() >= MAX_NOMINATIONS {
+            msg.reply(
+                ctx,
+                format!(
+                    "Hmm, the poll is full: {} books is as many as a ballot can hold.",
+                    MAX_NOMINATIONS
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let book = match book_info(goodreads, "https://www.goodreads.com", &book_id).await {
+            Err(why) => {
+                tracing::warn!("Unable to look up book on Goodreads: {}", why);
+                msg.reply(
+                    ctx,
+                    "Hmm, Goodreads isn't answering right now. Try again in a bit.",
+                )
+                .await?;
+                return Ok(());
+            }
+            Ok(None) => {
+                msg.reply(ctx, "Hmm, I couldn't find that book on Goodreads.")
+                    .await?;
+                return Ok(());
+            }
+            Ok(Some(book)) => book,
+        };
+        let poll = match poll {
+            Some(poll) => poll,
+            None => Poll::open(pool, guild_id, discord_user_id).await?,
+        };
+        let nomination = Nomination {
+            book_id: book.book_id.clone(),
+            title: book.title.clone(),
+            author: book.author.name.clone(),
+            url: book.url(),
+            image_url: book.image_url.clone(),
+            nominated_by: discord_user_id,
+            nominated_at: Utc::now().timestamp(),
+        };
+        if !poll.nominate(pool, &nomination).await? {
+            msg.reply(ctx, format!("_{}_ is already nominated.", nomination.title))
+                .await?;
+            return Ok(());
+        }
+
+        let nominated = nominations.len() + 1;
+        let mut description = format!(
+            "by {}\nput forward by <@{}>",
+            nomination.author, discord_user_id
+        );
+        if nominated >= 2 {
+            description.push_str(&format!(
+                "\n\n{} books nominated so far. <@{}> can type `~vote start <YYYY-MM-DD>` to open the voting.",
+                nominated, poll.opened_by
+            ));
+        }
+        msg.channel_id
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title(format!("📥 Nominated: {}", nomination.title))
+                        .url(&nomination.url)
+                        .colour(Colour::PURPLE)
+                        .description(description)
+                        .footer(|f| {
+                            f.text(
+                                "Type ~nominate <goodreads book url> to put another book forward",
+                            )
+                        });
+                    if !nomination.image_url.is_empty() {
+                        e.thumbnail(&nomination.image_url);
+                    }
+                    e
+                })
+            })
+            .await?;
+    }
+
+    Ok(())
+}
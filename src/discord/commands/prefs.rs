@@ -0,0 +1,137 @@
+use super::parse_toggle;
+use crate::discord::common::DatabaseContainer;
+use crate::model::{Preferences, User};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+const PREFS_USAGE: &str = r#"Usage:
+`~prefs` - show your current settings
+`~prefs min_rating <0-5>` - only announce books rated at least this many stars
+`~prefs review <on|off>` - include your review text in announcements
+`~prefs mention <on|off>` - @mention you instead of using your name
+`~prefs hide_unrated <on|off>` - don't announce books you haven't rated
+`~prefs skip_shelves <shelf, shelf, ...|none>` - never announce books on these shelves
+`~prefs followers <on|off>` - let other members follow your reading by DM
+`~prefs recap <on|off>` - get your own year-in-review by DM
+`~prefs pace <on|off>` - say how long each book took, and nudge you before a weekly streak ends"#;
+
+#[command]
+pub async fn prefs(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let discord_user_id = msg.author.id.0 as i64;
+        let discord_guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+
+        let user = match User::find(pool, discord_user_id, discord_guild_id).await? {
+            Some(user) => user,
+            None => {
+                msg.reply(
+                    ctx,
+                    "You aren't on the _lurk list_ yet! Type `~lurk <good reads id>` first.",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+        let mut prefs = Preferences::get(pool, user.id).await?;
+
+        if args.is_empty() {
+            msg.reply(ctx, describe(&prefs)).await?;
+            return Ok(());
+        }
+
+        let setting = args.single::<String>()?.to_lowercase();
+        let value = args.rest().trim();
+        let outcome = match setting.as_str() {
+            "min_rating" => value
+                .parse::<i64>()
+                .ok()
+                .filter(|rating| (0..=5).contains(rating))
+                .map(|rating| prefs.min_rating = rating)
+                .ok_or("the minimum rating must be a number from 0 to 5"),
+            "review" => parse_toggle(value).map(|on| prefs.include_review = on),
+            "mention" => parse_toggle(value).map(|on| prefs.mention = on),
+            "hide_unrated" => parse_toggle(value).map(|on| prefs.hide_unrated = on),
+            "followers" => parse_toggle(value).map(|on| prefs.allow_followers = on),
+            "recap" => parse_toggle(value).map(|on| prefs.recap = on),
+            "pace" => parse_toggle(value).map(|on| prefs.pace = on),
+            "skip_shelves" => match value {
+                "" => Err("tell me which shelves to skip, or `none`"),
+                "none" => {
+                    prefs.set_skip_shelves("");
+                    Ok(())
+                }
+                shelves => {
+                    prefs.set_skip_shelves(shelves);
+                    Ok(())
+                }
+            },
+            _ => Err("I don't know that setting"),
+        };
+
+        match outcome {
+            Ok(()) => match prefs.save(pool).await {
+                Ok(()) => {
+                    msg.reply(ctx, format!("Saved!\n{}", describe(&prefs)))
+                        .await?;
+                }
+                Err(why) => {
+                    msg.reply(
+                        ctx,
+                        format!("Ooopsie! I was unable to save your settings :(\n{}", why),
+                    )
+                    .await?;
+                    tracing::error!(
+                        "Unable to save preferences for user ({}) in guild ({}) because: {}",
+                        discord_user_id,
+                        discord_guild_id,
+                        why
+                    );
+                }
+            },
+            Err(why) => {
+                msg.reply(ctx, format!("Hmm, {}.\n{}", why, PREFS_USAGE))
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn describe(prefs: &Preferences) -> String {
+    let skip_shelves = if prefs.skip_shelves.is_empty() {
+        "none".to_string()
+    } else {
+        prefs.skip_shelves.join(", ")
+    };
+    format!(
+        "Your announcement settings:\n• min_rating: {}\n• review: {}\n• mention: {}\n• hide_unrated: {}\n• skip_shelves: {}\n• followers: {}\n• recap: {}\n• pace: {}",
+        prefs.min_rating,
+        on_off(prefs.include_review),
+        on_off(prefs.mention),
+        on_off(prefs.hide_unrated),
+        skip_shelves,
+        on_off(prefs.allow_followers),
+        on_off(prefs.recap),
+        on_off(prefs.pace)
+    )
+}
@@ -0,0 +1,121 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{CurrentlyReading, HistoryEntry, Recommendation, User};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::utils::Colour;
+use std::collections::HashSet;
+
+/// How many books a reply suggests
+const RECOMMENDATIONS_SHOWN: usize = 5;
+
+/// How many recommenders are named for each book before the rest are counted
+const RECOMMENDERS_SHOWN: usize = 3;
+
+#[command]
+pub async fn recommend(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+
+        let member = if args.is_empty() {
+            msg.author.id
+        } else {
+            match args.single::<UserId>() {
+                Ok(member) => member,
+                Err(_) => {
+                    msg.reply(ctx, "Usage: `~recommend [@member]`").await?;
+                    return Ok(());
+                }
+            }
+        };
+        let member_id = member.0 as i64;
+
+        if User::find(pool, member_id, guild_id).await?.is_none() {
+            let reply = if member == msg.author.id {
+                "You aren't on the _lurk list_ yet! Type `~lurk <good reads id>` first."
+            } else {
+                "They aren't on the _lurk list_, so I don't know what they've read."
+            };
+            msg.reply(ctx, reply).await?;
+            return Ok(());
+        }
+
+        // Tastes are matched on what everyone shares, but nothing they've read privately or are
+        // reading now is worth suggesting
+        let history = HistoryEntry::shared_in_guild(pool, guild_id).await?;
+        let own = HistoryEntry::for_member(pool, member_id, guild_id, true).await?;
+        let reading = CurrentlyReading::book_ids(pool, member_id, guild_id).await?;
+        let exclude: HashSet<&str> = own
+            .iter()
+            .map(|entry| entry.book_id.as_str())
+            .chain(reading.iter().map(|book_id| book_id.as_str()))
+            .collect();
+
+        let recommendations = Recommendation::for_member(member_id, &history, &exclude);
+        if recommendations.is_empty() {
+            msg.reply(
+                ctx,
+                "I don't have anything to suggest yet. Suggestions come from members who've read some of the same books, so check back once there's a bit more overlap.",
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let lines: Vec<String> = recommendations
+            .iter()
+            .take(RECOMMENDATIONS_SHOWN)
+            .enumerate()
+            .map(|(place, recommendation)| recommendation_line(place, recommendation))
+            .collect();
+        msg.channel_id
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title("📖 You might like")
+                        .colour(Colour::DARK_GREEN)
+                        .description(format!(
+                        "Suggestions for <@{}> from the members with the most similar taste:\n\n{}",
+                        member,
+                        lines.join("\n\n")
+                    ))
+                })
+                .reference_message(msg)
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn recommendation_line(place: usize, recommendation: &Recommendation) -> String {
+    let mut loved_by: Vec<String> = recommendation
+        .recommenders
+        .iter()
+        .take(RECOMMENDERS_SHOWN)
+        .map(|discord_user_id| format!("<@{}>", discord_user_id))
+        .collect();
+    if recommendation.recommenders.len() > RECOMMENDERS_SHOWN {
+        loved_by.push(format!(
+            "{} more",
+            recommendation.recommenders.len() - RECOMMENDERS_SHOWN
+        ));
+    }
+    format!(
+        "{}. [{}]({}) by {}\nLoved by {}",
+        place + 1,
+        recommendation.entry.title,
+        recommendation.entry.url,
+        recommendation.entry.author,
+        loved_by.join(", ")
+    )
+}
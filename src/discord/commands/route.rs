@@ -0,0 +1,121 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{GuildSettings, RouteCondition, RoutingRule};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+
+const ROUTE_USAGE: &str = r#"Usage:
+`~route` - show where announcements go
+`~route shelf <shelf> <#channel>` - announce books on this shelf in another channel
+`~route rating <1-5> <#channel>` - announce books rated at least this many stars in another channel
+`~route remove <number>` - remove a rule
+`~route clear` - send everything to the notification channel again"#;
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn route(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?;
+        let settings = GuildSettings::get(pool, guild_id.0 as i64).await?;
+        let rules = RoutingRule::list(pool, settings.guild_id).await?;
+
+        if args.is_empty() {
+            msg.reply(ctx, describe(&settings, &rules)).await?;
+            return Ok(());
+        }
+
+        let action = args.single::<String>()?.to_lowercase();
+        let outcome = match action.as_str() {
+            "remove" => match args
+                .single::<usize>()
+                .ok()
+                .and_then(|number| number.checked_sub(1))
+                .and_then(|index| rules.get(index))
+            {
+                Some(rule) => {
+                    rule.remove(pool).await?;
+                    Ok(())
+                }
+                None => Err("there's no rule with that number"),
+            },
+            "clear" => {
+                RoutingRule::clear(pool, settings.guild_id).await?;
+                Ok(())
+            }
+            kind => {
+                let value = args.single::<String>().unwrap_or_default();
+                match (
+                    RouteCondition::parse(kind, &value),
+                    args.single::<ChannelId>(),
+                ) {
+                    (Some(condition), _) if !condition.can_match() => Err(
+                        "only finished books are announced, so books on the `currently-reading` and `to-read` shelves never reach a rule",
+                    ),
+                    (Some(condition), Ok(channel)) => {
+                        let in_guild = matches!(
+                            channel.to_channel(ctx).await.ok().and_then(|c| c.guild()),
+                            Some(channel) if channel.guild_id == guild_id
+                        );
+                        if in_guild {
+                            RoutingRule::add(pool, settings.guild_id, condition, channel.0 as i64)
+                                .await?;
+                            Ok(())
+                        } else {
+                            Err("that channel isn't in this server")
+                        }
+                    }
+                    (None, _) => Err("rules need a shelf name or a rating from 1 to 5"),
+                    (_, Err(_)) => Err("tell me which channel to use, like `#club`"),
+                }
+            }
+        };
+
+        match outcome {
+            Ok(()) => {
+                let rules = RoutingRule::list(pool, settings.guild_id).await?;
+                msg.reply(ctx, format!("Saved!\n{}", describe(&settings, &rules)))
+                    .await?;
+            }
+            Err(why) => {
+                msg.reply(ctx, format!("Hmm, {}.\n{}", why, ROUTE_USAGE))
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn describe(settings: &GuildSettings, rules: &[RoutingRule]) -> String {
+    if rules.is_empty() {
+        return format!("All announcements go to <#{}>.", settings.notify_channel_id);
+    }
+    let mut lines = vec!["Rules are checked in order:".to_string()];
+    for (number, rule) in rules.iter().enumerate() {
+        let mut line = format!(
+            "{}. Books {} go to <#{}>",
+            number + 1,
+            rule.condition,
+            rule.channel_id
+        );
+        if !rule.condition.can_match() {
+            line.push_str(" (never used, since only finished books are announced)");
+        }
+        lines.push(line);
+    }
+    lines.push(format!(
+        "Everything else goes to <#{}>.",
+        settings.notify_channel_id
+    ));
+    lines.join("\n")
+}
@@ -0,0 +1,44 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{GuildSettings, SyncMode};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_announcement_sync(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let mode = match SyncMode::parse(args.rest()) {
+            Some(mode) => mode,
+            None => {
+                msg.reply(
+                    ctx,
+                    "Hmm, that setting needs to be `off`, `edit` or `delete`: `~set_announcement_sync <off|edit|delete>`",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        GuildSettings::set_announcement_sync(pool, guild_id, mode).await?;
+        let reply = match mode {
+            SyncMode::Off => "Announcements will stay as they were posted.",
+            SyncMode::Edit => "When someone changes their rating I'll update the announcement, and I'll strike it through if the book comes off their read shelf.",
+            SyncMode::Delete => "When someone changes their rating I'll update the announcement, and I'll delete it if the book comes off their read shelf.",
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::GuildSettings;
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+const SET_CLUB_FAVOURITE_USAGE: &str = "`~set_club_favourite <readers|off>`";
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_club_favourite(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let value = args.rest().trim();
+        let readers = if value.eq_ignore_ascii_case("off") {
+            None
+        } else {
+            // A book read by one member is everyone's favourite
+            match value.parse::<i64>() {
+                Ok(readers) if readers >= 2 => Some(readers),
+                _ => {
+                    msg.reply(
+                        ctx,
+                        format!(
+                            "Hmm, a club favourite needs at least 2 readers: {}",
+                            SET_CLUB_FAVOURITE_USAGE
+                        ),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            }
+        };
+
+        GuildSettings::set_club_favourite_readers(pool, guild_id, readers).await?;
+        let reply = match readers {
+            Some(readers) => format!(
+                "Books finished by {} or more members will be flagged as club favourites.",
+                readers
+            ),
+            None => "I'll stop flagging club favourites.".to_string(),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
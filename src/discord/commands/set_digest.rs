@@ -0,0 +1,87 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{DigestSchedule, GuildSettings, DIGEST_TIME_FORMAT};
+use anyhow::anyhow;
+use chrono::NaiveTime;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+const SET_DIGEST_USAGE: &str = "`~set_digest <off|daily|weekly|monthly> [HH:MM]`";
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_digest(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let schedule = match DigestSchedule::parse(
+            &args.single::<String>().unwrap_or_default().to_lowercase(),
+        ) {
+            Some(schedule) => schedule,
+            None => {
+                msg.reply(
+                    ctx,
+                    format!(
+                        "Hmm, the schedule needs to be `off`, `daily`, `weekly` or `monthly`: {}",
+                        SET_DIGEST_USAGE
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        let settings = GuildSettings::get(pool, guild_id).await?;
+        let time = match args.single::<String>() {
+            Ok(time) => match NaiveTime::parse_from_str(&time, DIGEST_TIME_FORMAT) {
+                Ok(time) => time,
+                Err(_) => {
+                    msg.reply(
+                        ctx,
+                        format!(
+                            "Hmm, the time needs to look like `09:00` or `18:30`: {}",
+                            SET_DIGEST_USAGE
+                        ),
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            },
+            Err(_) => settings.digest_time,
+        };
+        GuildSettings::set_digest(pool, guild_id, schedule, time).await?;
+        let when = format!(
+            "at {} {}",
+            time.format(DIGEST_TIME_FORMAT),
+            settings.time_zone.name()
+        );
+        let reply = match schedule {
+            DigestSchedule::Off => {
+                "I'll go back to announcing each book as it's finished.".to_string()
+            }
+            DigestSchedule::Daily => format!(
+                "Finished books will be rounded up in <#{}> every day {}.",
+                settings.notify_channel_id, when
+            ),
+            DigestSchedule::Weekly => format!(
+                "Finished books will be rounded up in <#{}> every Monday {}.",
+                settings.notify_channel_id, when
+            ),
+            DigestSchedule::Monthly => format!(
+                "Finished books will be rounded up in <#{}> on the 1st of every month {}.",
+                settings.notify_channel_id, when
+            ),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
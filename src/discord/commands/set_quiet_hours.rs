@@ -0,0 +1,53 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{GuildSettings, QuietHours};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_quiet_hours(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let value = args.rest().trim();
+        let quiet_hours = if value.eq_ignore_ascii_case("off") {
+            None
+        } else {
+            match QuietHours::parse(value) {
+                Some(quiet_hours) => Some(quiet_hours),
+                None => {
+                    msg.reply(
+                        ctx,
+                        "Hmm, quiet hours need a start and an end like `22:00-07:00`: `~set_quiet_hours <HH:MM-HH:MM|off>`",
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            }
+        };
+
+        GuildSettings::set_quiet_hours(pool, guild_id, quiet_hours).await?;
+        let settings = GuildSettings::get(pool, guild_id).await?;
+        let reply = match quiet_hours {
+            Some(quiet_hours) => format!(
+                "Books finished between {} {} will be announced once quiet hours are over.",
+                quiet_hours,
+                settings.time_zone.name()
+            ),
+            None => "I'll announce books whatever the time.".to_string(),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{GuildSettings, RecapDate, DIGEST_TIME_FORMAT};
+use anyhow::anyhow;
+use chrono::{Datelike, Utc};
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_recap(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let value = args.rest().trim();
+        let date = if value.eq_ignore_ascii_case("off") {
+            None
+        } else {
+            match RecapDate::parse(value) {
+                Some(date) => Some(date),
+                None => {
+                    msg.reply(
+                        ctx,
+                        "Hmm, the year-in-review needs a month and day like `01-05`: `~set_recap <MM-DD|off>`",
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            }
+        };
+
+        let settings = GuildSettings::get(pool, guild_id).await?;
+        let now = Utc::now();
+        // A date that has already gone by this year waits for next year, rather than posting now
+        let last_recap_year = date
+            .and_then(|date| {
+                date.due(
+                    settings.digest_time,
+                    settings.time_zone,
+                    now,
+                    settings.last_recap_year,
+                )
+            })
+            .or(settings.last_recap_year);
+        GuildSettings::set_recap(pool, guild_id, date, last_recap_year).await?;
+
+        let reply = match date {
+            Some(date) => {
+                let this_year = now.with_timezone(&settings.time_zone).year();
+                let year = if matches!(last_recap_year, Some(last) if last >= this_year) {
+                    this_year + 1
+                } else {
+                    this_year
+                };
+                format!(
+                    "The next year-in-review goes out on {} at {} {}, looking back on {}.",
+                    date.in_year(year).format("%B %-d %Y"),
+                    settings.digest_time.format(DIGEST_TIME_FORMAT),
+                    settings.time_zone.name(),
+                    date.covers(year)
+                )
+            }
+            None => "No more year-in-review posts.".to_string(),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
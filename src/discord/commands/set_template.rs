@@ -0,0 +1,88 @@
+use crate::discord::common::DatabaseContainer;
+use crate::discord::template::{sample_book, Template, DEFAULT_TEMPLATE};
+use crate::model::GuildSettings;
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_template(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let source = args.rest().trim();
+
+        if source.is_empty() {
+            let settings = GuildSettings::get(pool, guild_id).await?;
+            let current = settings
+                .announcement_template
+                .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+            // Templates can fill a whole message, so they're shown in an embed
+            msg.channel_id
+                .send_message(ctx, |m| {
+                    m.content(format!(
+                        "Type `~set_template <template>` to change it, or `~set_template reset` to go back to the default. You can use {}.",
+                        Template::placeholders()
+                    ))
+                    .embed(|e| {
+                        e.title("The current template")
+                            .description(format!("```\n{}\n```", current))
+                    })
+                    .reference_message(msg)
+                })
+                .await?;
+            return Ok(());
+        }
+
+        let (template, stored) = if source.eq_ignore_ascii_case("reset") {
+            (Template::default(), None)
+        } else {
+            match Template::parse(source) {
+                Ok(template) => (template, Some(source)),
+                Err(why) => {
+                    msg.reply(ctx, format!("That template won't work: {}", why))
+                        .await?;
+                    return Ok(());
+                }
+            }
+        };
+
+        match GuildSettings::set_announcement_template(pool, guild_id, stored).await {
+            Ok(_) => {
+                let book = sample_book();
+                let preview = template.render(&msg.author.name, &book, Some(book.review()));
+                msg.channel_id
+                    .send_message(ctx, |m| {
+                        m.content("Saved! Announcements will look like this:")
+                            .embed(|e| e.description(preview))
+                            .reference_message(msg)
+                    })
+                    .await?;
+            }
+            Err(why) => {
+                msg.reply(
+                    ctx,
+                    format!("Ooopsie! I was unable to save the template :(\n{}", why),
+                )
+                .await?;
+                tracing::error!(
+                    "Unable to set the announcement template for guild ({}) because: {}",
+                    guild_id,
+                    why
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use super::parse_toggle;
+use crate::discord::common::DatabaseContainer;
+use crate::discord::thread::ARCHIVE_DURATIONS;
+use crate::model::GuildSettings;
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+const SET_THREADS_USAGE: &str = "`~set_threads <on|off> [1h|24h|3d|1w]`";
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_threads(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let enabled = match parse_toggle(args.single::<String>().unwrap_or_default().trim()) {
+            Ok(enabled) => enabled,
+            Err(why) => {
+                msg.reply(ctx, format!("Hmm, {}: {}", why, SET_THREADS_USAGE))
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let settings = GuildSettings::get(pool, guild_id).await?;
+        let archive_minutes = match args.single::<String>() {
+            Ok(duration) => {
+                let duration = duration.to_lowercase();
+                match ARCHIVE_DURATIONS.iter().find(|(name, _)| *name == duration) {
+                    Some((_, minutes)) => i64::from(*minutes),
+                    None => {
+                        msg.reply(
+                            ctx,
+                            format!(
+                                "Hmm, threads can only auto archive after 1h, 24h, 3d or 1w: {}",
+                                SET_THREADS_USAGE
+                            ),
+                        )
+                        .await?;
+                        return Ok(());
+                    }
+                }
+            }
+            Err(_) => settings.thread_archive_minutes,
+        };
+
+        GuildSettings::set_threads(pool, guild_id, enabled, archive_minutes).await?;
+        if enabled {
+            let archive_after = ARCHIVE_DURATIONS
+                .iter()
+                .find(|(_, minutes)| i64::from(*minutes) == archive_minutes)
+                .map(|(name, _)| *name)
+                .unwrap_or("24h");
+            msg.reply(
+                ctx,
+                format!(
+                    "Each announcement will now get its own discussion thread, archived after {} of quiet. I'll need the _Create Public Threads_ permission in <#{}>.",
+                    archive_after, settings.notify_channel_id
+                ),
+            )
+            .await?;
+        } else {
+            msg.reply(ctx, "I'll stop opening discussion threads.")
+                .await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::GuildSettings;
+use anyhow::anyhow;
+use chrono_tz::Tz;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_timezone(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let time_zone: Tz = match args.rest().trim().parse() {
+            Ok(time_zone) => time_zone,
+            Err(_) => {
+                msg.reply(
+                    ctx,
+                    "Hmm, I don't know that time zone. Try a name like `Europe/London` or `America/New_York`: `~set_timezone <zone>`",
+                )
+                .await?;
+                return Ok(());
+            }
+        };
+
+        GuildSettings::set_time_zone(pool, guild_id, time_zone).await?;
+        msg.reply(
+            ctx,
+            format!(
+                "Digests, quiet hours and reading dates will now follow {} time.",
+                time_zone.name()
+            ),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
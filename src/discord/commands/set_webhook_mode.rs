@@ -0,0 +1,79 @@
+use super::parse_toggle;
+use crate::discord::common::DatabaseContainer;
+use crate::discord::webhook::{get_or_create_webhook, remove_webhook};
+use crate::model::GuildSettings;
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+#[command]
+#[required_permissions(ADMINISTRATOR)]
+pub async fn set_webhook_mode(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let enabled = match parse_toggle(args.rest().trim()) {
+            Ok(enabled) => enabled,
+            Err(why) => {
+                msg.reply(ctx, format!("Hmm, {}: `~set_webhook_mode <on|off>`", why))
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        GuildSettings::set_webhook_enabled(pool, guild_id, enabled).await?;
+        let settings = GuildSettings::get(pool, guild_id).await?;
+        if enabled {
+            match get_or_create_webhook(&ctx.http, pool, &settings, settings.notify_channel()).await
+            {
+                Ok(_) => {
+                    msg.reply(
+                        ctx,
+                        format!(
+                            "Announcements in <#{}> will now be posted under each member's name and avatar.",
+                            settings.notify_channel_id
+                        ),
+                    )
+                    .await?;
+                }
+                Err(why) => {
+                    GuildSettings::set_webhook_enabled(pool, guild_id, false).await?;
+                    msg.reply(
+                        ctx,
+                        format!(
+                            "I couldn't set up a webhook in <#{}>. Make sure I have the _Manage Webhooks_ permission there.",
+                            settings.notify_channel_id
+                        ),
+                    )
+                    .await?;
+                    tracing::error!(
+                        "Unable to create a webhook for guild ({}) because: {}",
+                        guild_id,
+                        why
+                    );
+                }
+            }
+        } else {
+            if let Err(why) = remove_webhook(&ctx.http, pool, &settings).await {
+                tracing::error!(
+                    "Unable to remove the webhook for guild ({}) because: {}",
+                    guild_id,
+                    why
+                );
+            }
+            msg.reply(ctx, "Announcements will be posted by me again.")
+                .await?;
+        }
+    }
+
+    Ok(())
+}
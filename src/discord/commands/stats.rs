@@ -0,0 +1,175 @@
+use crate::discord::common::{attach_reading_chart, streak_line, DatabaseContainer};
+use crate::model::{GuildSettings, HistoryEntry, ReadingStats, User, WeeklyStreak};
+use anyhow::anyhow;
+use chrono::{Datelike, Utc};
+use serenity::builder::CreateEmbed;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+use serenity::utils::{parse_username, Colour};
+
+const STATS_USAGE: &str = "Usage: `~stats [@member] [year]`";
+
+#[command]
+pub async fn stats(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?;
+        let settings = GuildSettings::get(pool, guild_id.0 as i64).await?;
+
+        // A bare number is a year, so only mentions pick out a member
+        let member = match args.current().and_then(parse_username) {
+            Some(member) => {
+                args.advance();
+                UserId(member)
+            }
+            None => msg.author.id,
+        };
+        let today = Utc::now()
+            .with_timezone(&settings.time_zone)
+            .date()
+            .naive_local();
+        let year = match args.current() {
+            Some(year) => match year.parse::<i32>() {
+                Ok(year) => year,
+                Err(_) => {
+                    msg.reply(ctx, STATS_USAGE).await?;
+                    return Ok(());
+                }
+            },
+            None => today.year(),
+        };
+
+        if User::find(pool, member.0 as i64, settings.guild_id)
+            .await?
+            .is_none()
+        {
+            let reply = if member == msg.author.id {
+                "You aren't on the _lurk list_ yet! Type `~lurk <good reads id>` first."
+            } else {
+                "They aren't on the _lurk list_, so I don't know what they've read."
+            };
+            msg.reply(ctx, reply).await?;
+            return Ok(());
+        }
+
+        // Other members only see what the member's preferences let us announce
+        let history = HistoryEntry::for_member(
+            pool,
+            member.0 as i64,
+            settings.guild_id,
+            member == msg.author.id,
+        )
+        .await?;
+        let stats = ReadingStats::for_year(&history, year);
+        // Streaks only mean something for the year still going
+        let streak = if year == today.year() {
+            Some(WeeklyStreak::current(
+                history.iter().map(|entry| entry.read_on),
+                today,
+            ))
+        } else {
+            None
+        };
+
+        let user = member.to_user(ctx).await?;
+        let display_name = user
+            .nick_in(ctx, guild_id)
+            .await
+            .unwrap_or_else(|| user.name.clone());
+
+        if stats.books == 0 {
+            msg.reply(
+                ctx,
+                format!(
+                    "{} hasn't finished any books in {} yet.",
+                    display_name, year
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let mut embed = stats_embed(&display_name, &user.face(), year, &stats, streak);
+        let chart = attach_reading_chart(&mut embed, &stats)?;
+        msg.channel_id
+            .send_message(ctx, |m| {
+                m.set_embed(embed).add_file(chart).reference_message(msg)
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn stats_embed(
+    display_name: &str,
+    avatar_url: &str,
+    year: i32,
+    stats: &ReadingStats,
+    streak: Option<WeeklyStreak>,
+) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .author(|a| a.name(display_name).icon_url(avatar_url))
+        .title(format!("📊 {} in books", year))
+        .colour(Colour::BLURPLE)
+        .field("Books", stats.books, true)
+        .field("Pages", stats.pages, true)
+        .field(
+            "Average rating",
+            match stats.average_rating {
+                Some(average) => format!("{:.1} ⭐", average),
+                None => "—".to_string(),
+            },
+            true,
+        );
+
+    if !stats.favourite_authors.is_empty() {
+        let authors: Vec<String> = stats
+            .favourite_authors
+            .iter()
+            .map(|(author, books)| format!("{} ({})", author, books))
+            .collect();
+        embed.field("Favourite authors", authors.join("\n"), false);
+    }
+    if let Some(longest) = &stats.longest {
+        embed.field("Longest", book_line(longest), true);
+    }
+    if let Some(shortest) = &stats.shortest {
+        embed.field("Shortest", book_line(shortest), true);
+    }
+    if let Some(pace) = &stats.pace {
+        let mut lines = vec![format!("{:.1} days a book", pace.days_per_book)];
+        if let Some(pages_per_day) = pace.pages_per_day {
+            lines.push(format!("{:.0} pages/day", pages_per_day));
+        }
+        embed.field("Pace", lines.join("\n"), true);
+    }
+    if let Some(line) = streak.as_ref().and_then(streak_line) {
+        let line = if matches!(streak, Some(streak) if streak.at_risk) {
+            format!("{}\nFinish a book by Sunday to keep it going", line)
+        } else {
+            line
+        };
+        embed.field("Streak", line, true);
+    }
+    embed
+}
+
+fn book_line(entry: &HistoryEntry) -> String {
+    format!(
+        "[{}]({}) · {} pages",
+        entry.title,
+        entry.url,
+        entry.pages.unwrap_or_default()
+    )
+}
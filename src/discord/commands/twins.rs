@@ -0,0 +1,64 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{Compatibility, HistoryEntry, MIN_TWIN_BOOKS};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::CommandResult;
+use serenity::model::channel::Message;
+use serenity::utils::Colour;
+
+/// How many pairs make the list
+const TWINS_SHOWN: usize = 5;
+
+#[command]
+pub async fn twins(ctx: &serenity::prelude::Context, msg: &Message) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+
+        let history = HistoryEntry::shared_in_guild(pool, guild_id).await?;
+        let twins = Compatibility::twins(&history);
+        if twins.is_empty() {
+            msg.reply(
+                ctx,
+                format!(
+                    "No two members have read {} of the same books yet.",
+                    MIN_TWIN_BOOKS
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let lines: Vec<String> = twins
+            .iter()
+            .take(TWINS_SHOWN)
+            .enumerate()
+            .map(|(place, pair)| {
+                format!(
+                    "{}. <@{}> & <@{}> · **{}%** · {} books in common",
+                    place + 1,
+                    pair.first,
+                    pair.second,
+                    pair.score,
+                    pair.shared.len()
+                )
+            })
+            .collect();
+        msg.channel_id
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title("👯 Reading twins")
+                        .colour(Colour::FABLED_PINK)
+                        .description(lines.join("\n"))
+                        .footer(|f| f.text("Type ~compat @member @member for the details"))
+                })
+            })
+            .await?;
+    }
+
+    Ok(())
+}
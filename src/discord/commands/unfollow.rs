@@ -0,0 +1,42 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::Follow;
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::model::id::UserId;
+
+#[command]
+pub async fn unfollow(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("expected a guild attached to message"))?
+            .0 as i64;
+
+        let followee = match args.single::<UserId>() {
+            Ok(followee) => followee,
+            Err(_) => {
+                msg.reply(ctx, "Usage: `~unfollow @member`").await?;
+                return Ok(());
+            }
+        };
+
+        let removed =
+            Follow::remove(pool, msg.author.id.0 as i64, followee.0 as i64, guild_id).await?;
+        let reply = if removed {
+            format!("You've stopped following <@{}>.", followee.0)
+        } else {
+            format!("You weren't following <@{}>.", followee.0)
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::AuthorFollow;
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+#[command]
+pub async fn unfollowauthor(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("expected a guild attached to message"))?
+            .0 as i64;
+
+        let name = args.rest().trim();
+        if name.is_empty() {
+            msg.reply(ctx, "Usage: `~unfollowauthor <name>`").await?;
+            return Ok(());
+        }
+
+        let reply = match AuthorFollow::remove(pool, msg.author.id.0 as i64, guild_id, name).await? {
+            Some(author) => format!("You've stopped following **{}**.", author.name),
+            None => format!(
+                "You weren't following an author called {}. Type `~followauthor` to see who you follow.",
+                name
+            ),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
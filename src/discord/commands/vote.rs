@@ -0,0 +1,142 @@
+use crate::discord::common::DatabaseContainer;
+use crate::discord::polls::{ballot_components, ballot_embed};
+use crate::model::{GroupRead, GuildSettings, Poll, PollMethod};
+use anyhow::anyhow;
+use chrono::Utc;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+
+const VOTE_USAGE: &str = r#"Usage:
+`~vote` - show the ballot
+`~vote start <YYYY-MM-DD>` - close nominations and vote until the end of that day
+`~vote start <YYYY-MM-DD> ranked` - rank favourites instead of picking every book you'd read
+`~vote start <YYYY-MM-DD> club <YYYY-MM-DD>` - start a group read of the winner, finishing by the second day
+`~vote cancel` - call the poll off, if you opened it"#;
+
+#[command]
+pub async fn vote(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    mut args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+        let discord_user_id = msg.author.id.0 as i64;
+        let settings = GuildSettings::get(pool, guild_id).await?;
+        let poll = Poll::current(pool, guild_id).await?;
+
+        let action = args.single::<String>().unwrap_or_default().to_lowercase();
+        let reply = match (action.as_str(), poll) {
+            ("", Some(poll)) if poll.closes_at.is_some() => {
+                let nominations = poll.nominations(pool).await?;
+                msg.channel_id
+                    .send_message(ctx, |m| {
+                        m.set_embed(ballot_embed(&poll, &nominations, settings.time_zone))
+                            .set_components(ballot_components(&poll, &nominations))
+                    })
+                    .await?;
+                return Ok(());
+            }
+            ("", Some(poll)) => format!(
+                "Voting hasn't started yet. Type `~nominate` to see what's been put forward; <@{}> can type `~vote start <YYYY-MM-DD>` to open the voting.",
+                poll.opened_by
+            ),
+            ("start" | "cancel", Some(poll)) if poll.opened_by != discord_user_id => format!(
+                "Hmm, only <@{}> can do that, since they opened the poll.",
+                poll.opened_by
+            ),
+            ("start", Some(poll)) if poll.closes_at.is_some() => {
+                "Voting's already started. Type `~vote` to see the ballot.".to_string()
+            }
+            ("start", Some(poll)) => {
+                let now = Utc::now();
+                let closes_at = args.single::<String>().ok().and_then(|closes| {
+                    GroupRead::parse_deadline(&closes, settings.time_zone, now)
+                });
+                let mut method = PollMethod::Approval;
+                let mut club_deadline = None;
+                let mut understood = true;
+                while let Ok(option) = args.single::<String>() {
+                    match option.to_lowercase().as_str() {
+                        "club" => {
+                            club_deadline = args.single::<String>().ok().and_then(|deadline| {
+                                GroupRead::parse_deadline(&deadline, settings.time_zone, now)
+                            });
+                            understood = club_deadline.is_some();
+                        }
+                        option => match PollMethod::parse(option) {
+                            Some(parsed) => method = parsed,
+                            None => understood = false,
+                        },
+                    }
+                    if !understood {
+                        break;
+                    }
+                }
+                let nominations = poll.nominations(pool).await?;
+
+                match closes_at {
+                    _ if !understood => format!(
+                        "Hmm, I didn't follow that. Group read deadlines need to be a day still to come, like `2022-12-31`.\n{}",
+                        VOTE_USAGE
+                    ),
+                    None => format!(
+                        "Hmm, voting needs to close on a day still to come, like `2022-10-31`.\n{}",
+                        VOTE_USAGE
+                    ),
+                    Some(closes_at) if matches!(club_deadline, Some(deadline) if deadline <= closes_at) => {
+                        "Hmm, the group read has to end after the voting does.".to_string()
+                    }
+                    Some(_) if nominations.len() < 2 => {
+                        "A vote needs at least two books to choose from. Type `~nominate <goodreads book url>` to put some forward.".to_string()
+                    }
+                    Some(closes_at) => {
+                        let closes_at = closes_at.timestamp();
+                        let club_deadline = club_deadline.map(|deadline| deadline.timestamp());
+                        if poll
+                            .start_voting(pool, method, closes_at, club_deadline)
+                            .await?
+                        {
+                            let poll = Poll {
+                                method,
+                                closes_at: Some(closes_at),
+                                club_deadline,
+                                ..poll
+                            };
+                            msg.channel_id
+                                .send_message(ctx, |m| {
+                                    m.content("🗳️ Nominations are closed, voting is open!")
+                                        .set_embed(ballot_embed(
+                                            &poll,
+                                            &nominations,
+                                            settings.time_zone,
+                                        ))
+                                        .set_components(ballot_components(&poll, &nominations))
+                                })
+                                .await?;
+                            return Ok(());
+                        }
+                        "Voting's already started. Type `~vote` to see the ballot.".to_string()
+                    }
+                }
+            }
+            ("cancel", Some(poll)) => {
+                poll.cancel(pool).await?;
+                "The poll is off.".to_string()
+            }
+            ("" | "start" | "cancel", None) => {
+                "There's no poll going. Type `~nominate <goodreads book url>` to start one.".to_string()
+            }
+            _ => VOTE_USAGE.to_string(),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
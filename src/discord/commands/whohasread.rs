@@ -0,0 +1,119 @@
+use crate::discord::common::DatabaseContainer;
+use crate::model::{Announcement, HistoryEntry};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::utils::Colour;
+
+/// How many different books a reply lists
+const MAX_BOOKS: usize = 5;
+
+/// Discord refuses embed descriptions longer than this, so leave some room for the last line
+const MAX_DESCRIPTION_LENGTH: usize = 4000;
+
+#[command]
+pub async fn whohasread(
+    ctx: &serenity::prelude::Context,
+    msg: &Message,
+    args: Args,
+) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let Some(database) = data.get::<DatabaseContainer>() {
+        let pool = &**database;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+
+        let query = args.rest().trim();
+        if query.is_empty() {
+            msg.reply(ctx, "Usage: `~whohasread <title or author>`")
+                .await?;
+            return Ok(());
+        }
+
+        let entries = HistoryEntry::search(pool, guild_id, query).await?;
+        if entries.is_empty() {
+            msg.reply(
+                ctx,
+                format!("Nobody here has read anything matching \"{}\" yet.", query),
+            )
+            .await?;
+            return Ok(());
+        }
+
+        let mut books: Vec<Vec<HistoryEntry>> = Vec::new();
+        for entry in entries {
+            match books
+                .iter_mut()
+                .find(|readers| readers[0].book_id == entry.book_id)
+            {
+                // A re-read doesn't make them another reader
+                Some(readers) => {
+                    if !readers
+                        .iter()
+                        .any(|reader| reader.discord_user_id == entry.discord_user_id)
+                    {
+                        readers.push(entry);
+                    }
+                }
+                None => books.push(vec![entry]),
+            }
+        }
+
+        let mut description = String::new();
+        let mut shown = 0;
+        for readers in books.iter().take(MAX_BOOKS) {
+            let mut section = format!(
+                "**[{}]({})** by {}\n",
+                readers[0].title, readers[0].url, readers[0].author
+            );
+            for reader in readers {
+                let link = Announcement::latest_for_book(pool, reader.user_id, &reader.book_id)
+                    .await?
+                    .map(|announcement| announcement.link());
+                section.push_str(&reader_line(reader, link.as_deref()));
+                section.push('\n');
+            }
+
+            if description.chars().count() + section.chars().count() > MAX_DESCRIPTION_LENGTH {
+                break;
+            }
+            description.push_str(&section);
+            description.push('\n');
+            shown += 1;
+        }
+        if books.len() > shown {
+            description.push_str(&format!(
+                "…and {} more. Try a longer search to narrow it down.",
+                books.len() - shown
+            ));
+        }
+
+        msg.channel_id
+            .send_message(ctx, |m| {
+                m.embed(|e| {
+                    e.title(format!("📚 Who has read \"{}\"?", query))
+                        .colour(Colour::BLUE)
+                        .description(description.trim_end())
+                })
+                .reference_message(msg)
+            })
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn reader_line(reader: &HistoryEntry, announcement: Option<&str>) -> String {
+    let mut line = format!("• <@{}>", reader.discord_user_id);
+    if reader.rating > 0 {
+        line.push(' ');
+        line.push_str(&"⭐".repeat(reader.rating as usize));
+    }
+    if let Some(announcement) = announcement {
+        line.push_str(&format!(" · [announcement]({})", announcement));
+    }
+    line
+}
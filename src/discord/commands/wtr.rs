@@ -0,0 +1,203 @@
+use super::parse_toggle;
+use crate::crawler::{book_id_from_url, book_info};
+use crate::discord::common::{DatabaseContainer, GoodreadsContainer};
+use crate::model::{Preferences, User, WantToRead};
+use anyhow::anyhow;
+use serenity::framework::standard::macros::command;
+use serenity::framework::standard::{Args, CommandResult};
+use serenity::model::channel::Message;
+use serenity::utils::Colour;
+use std::cmp::Reverse;
+
+const WTR_USAGE: &str = r#"Usage:
+`~wtr` - see your want to read list
+`~wtr add <goodreads book url>` - put a book on it
+`~wtr remove <number|goodreads book url>` - take a book off it
+`~wtr sync <on|off>` - add the books on your Goodreads to-read shelf too"#;
+
+/// Keeps the list inside an embed description's 4096 characters
+const BOOKS_SHOWN: usize = 15;
+
+/// How many of the books read off the list are shown under it
+const FINISHED_SHOWN: usize = 5;
+
+#[command]
+pub async fn wtr(ctx: &serenity::prelude::Context, msg: &Message, mut args: Args) -> CommandResult {
+    let data = ctx.data.read().await;
+    if let (Some(database), Some(goodreads)) = (
+        data.get::<DatabaseContainer>(),
+        data.get::<GoodreadsContainer>(),
+    ) {
+        let pool = &**database;
+        let discord_user_id = msg.author.id.0 as i64;
+        let guild_id = msg
+            .guild_id
+            .ok_or(anyhow!("Expected a guild id on message"))?
+            .0 as i64;
+
+        let action = args.single::<String>().unwrap_or_default().to_lowercase();
+        let value = args.rest().trim();
+        let reply = match action.as_str() {
+            "" | "list" => {
+                let books = WantToRead::list(pool, discord_user_id, guild_id).await?;
+                let (finished, unread): (Vec<WantToRead>, Vec<WantToRead>) = books
+                    .into_iter()
+                    .partition(|book| book.finished_at.is_some());
+                if unread.is_empty() && finished.is_empty() {
+                    "Your want to read list is empty. Type `~wtr add <goodreads book url>`, or click 📚 _Want to read_ on an announcement.".to_string()
+                } else {
+                    msg.channel_id
+                        .send_message(ctx, |m| {
+                            m.embed(|e| {
+                                e.title("📚 Your want to read list")
+                                    .colour(Colour::DARK_GREEN)
+                                    .description(list_lines(&unread))
+                                    .footer(|f| {
+                                        f.text("Type ~wtr remove <number> to take a book off")
+                                    });
+                                if !finished.is_empty() {
+                                    e.field("✅ Read since", finished_lines(&finished), false);
+                                }
+                                e
+                            })
+                            .reference_message(msg)
+                        })
+                        .await?;
+                    return Ok(());
+                }
+            }
+            "add" => {
+                let book_id = match book_id_from_url(value) {
+                    Some(book_id) => book_id,
+                    None => {
+                        msg.reply(
+                            ctx,
+                            format!(
+                                "Hmm, that doesn't look like a Goodreads book link.\n{}",
+                                WTR_USAGE
+                            ),
+                        )
+                        .await?;
+                        return Ok(());
+                    }
+                };
+                match book_info(goodreads, "https://www.goodreads.com", &book_id).await {
+                    Err(why) => {
+                        tracing::warn!("Unable to look up book on Goodreads: {}", why);
+                        "Hmm, Goodreads isn't answering right now. Try again in a bit.".to_string()
+                    }
+                    Ok(None) => "Hmm, I couldn't find that book on Goodreads.".to_string(),
+                    Ok(Some(book)) => {
+                        let added = WantToRead::new(
+                            discord_user_id,
+                            guild_id,
+                            &book.book_id,
+                            &book.title,
+                            &book.author.name,
+                            &book.url(),
+                        )
+                        .add(pool)
+                        .await?;
+                        if added {
+                            format!("Added _{}_ to your want to read list 📚", book.title)
+                        } else {
+                            format!("_{}_ is already on your want to read list.", book.title)
+                        }
+                    }
+                }
+            }
+            "remove" => {
+                let unread: Vec<WantToRead> = WantToRead::list(pool, discord_user_id, guild_id)
+                    .await?
+                    .into_iter()
+                    .filter(|book| book.finished_at.is_none())
+                    .collect();
+                // Numbers are the ones `~wtr` shows, which only counts unread books
+                let book_id = match value.parse::<usize>() {
+                    Ok(number) => number
+                        .checked_sub(1)
+                        .and_then(|index| unread.get(index))
+                        .map(|book| book.book_id.clone()),
+                    Err(_) => book_id_from_url(value),
+                };
+                match book_id {
+                    None => format!(
+                        "Hmm, tell me which book by its number in `~wtr` or its Goodreads link.\n{}",
+                        WTR_USAGE
+                    ),
+                    Some(book_id) => {
+                        if WantToRead::remove(pool, discord_user_id, guild_id, &book_id).await? {
+                            let title = unread
+                                .iter()
+                                .find(|book| book.book_id == book_id)
+                                .map(|book| format!("_{}_", book.title))
+                                .unwrap_or_else(|| "That book".to_string());
+                            format!("Took {} off your want to read list.", title)
+                        } else {
+                            "That book isn't on your want to read list.".to_string()
+                        }
+                    }
+                }
+            }
+            "sync" => match User::find(pool, discord_user_id, guild_id).await? {
+                None => "You aren't on the _lurk list_ yet! Type `~lurk <good reads id>` first."
+                    .to_string(),
+                Some(user) => match parse_toggle(value) {
+                    Err(why) => format!("Hmm, {}.\n{}", why, WTR_USAGE),
+                    Ok(on) => {
+                        let mut prefs = Preferences::get(pool, user.id).await?;
+                        prefs.sync_want_to_read = on;
+                        prefs.save(pool).await?;
+                        if on {
+                            "Got it! Books on your Goodreads to-read shelf will be added to your list as I check your shelves. Taking them off the shelf won't take them off the list.".to_string()
+                        } else {
+                            "Got it, I'll leave your Goodreads to-read shelf alone. Books already added stay on your list.".to_string()
+                        }
+                    }
+                },
+            },
+            _ => WTR_USAGE.to_string(),
+        };
+        msg.reply(ctx, reply).await?;
+    }
+
+    Ok(())
+}
+
+fn list_lines(unread: &[WantToRead]) -> String {
+    if unread.is_empty() {
+        return "You've read everything on your list!".to_string();
+    }
+    let mut lines: Vec<String> = unread
+        .iter()
+        .take(BOOKS_SHOWN)
+        .enumerate()
+        .map(|(index, book)| {
+            format!(
+                "**{}.** [{}]({}) by {}",
+                index + 1,
+                book.title,
+                book.url,
+                book.author
+            )
+        })
+        .collect();
+    if unread.len() > BOOKS_SHOWN {
+        lines.push(format!("…and {} more", unread.len() - BOOKS_SHOWN));
+    }
+    lines.join("\n")
+}
+
+fn finished_lines(finished: &[WantToRead]) -> String {
+    let mut finished: Vec<&WantToRead> = finished.iter().collect();
+    finished.sort_by_key(|book| Reverse(book.finished_at));
+    let mut lines: Vec<String> = finished
+        .iter()
+        .take(FINISHED_SHOWN)
+        .map(|book| format!("~~{}~~ by {}", book.title, book.author))
+        .collect();
+    if finished.len() > FINISHED_SHOWN {
+        lines.push(format!("…and {} more", finished.len() - FINISHED_SHOWN));
+    }
+    lines.join("\n")
+}
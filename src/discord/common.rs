@@ -1,37 +1,86 @@
 use anyhow::{anyhow, Context};
+use chrono::Utc;
+use chrono_tz::Tz;
 use reqwest::Url;
+use serenity::builder::CreateEmbed;
 use serenity::framework::standard::macros::group;
 use serenity::framework::standard::StandardFramework;
-use serenity::model::channel::Message;
+use serenity::http::Http;
+use serenity::model::channel::{AttachmentType, Message};
 use serenity::model::event::ResumedEvent;
 use serenity::model::gateway::Ready;
 use serenity::model::id::ChannelId;
-use serenity::model::prelude::{AttachmentType, Guild, GuildId, UserId};
+use serenity::model::interactions::Interaction;
+use serenity::model::prelude::{Guild, GuildId, UserId};
 use serenity::prelude::*;
+use serenity::utils::Colour;
 use serenity::{async_trait, CacheAndHttp};
 use sqlx::SqlitePool;
+use std::borrow::Cow;
 use std::env;
 use std::sync::Arc;
 
+use crate::chart::reading_chart;
+use crate::crawler::GovernedClient;
 use crate::discord::commands::*;
-use crate::model::{Book, User};
+use crate::discord::help::HELP_STR;
+use crate::discord::polls::{handle_ballot, BALLOT_PREFIX};
+use crate::discord::reactions::{announcement_buttons, handle_button};
+use crate::discord::template::Template;
+use crate::discord::thread::{existing_thread, join_thread, open_thread};
+use crate::discord::webhook::{post_as_member, MemberPost};
+use crate::model::{
+    Announcement, AuthorFollow, Badge, Book, EarnedBadge, GuildSettings, HistoryEntry, Preferences,
+    ReactionCounts, ReadingStats, User, WeeklyStreak,
+};
 
 pub struct DatabaseContainer;
-pub const HELP_STR: &str = r#"👋
-To chose which channel is used for notifications, (1) be an admin and (2) type `~set_notify_channel` in the channel that should have it.
-
-To sign up for sending notifications about your book progress, type `~lurk <good reads id>` where <good reads id> is your integer id assigned by goodreads. Sign in to good reads, go to your profile, and look at the URL. You should see something like `https://www.goodreads.com/user/show/<good reads id>-herp-derplinson`
-
-To remove yourself from notifications, type `~unlurk`. You'll be excluded from further... _lurking_ 😏.
-
-To see this message again, type `~help`"#;
 
 impl TypeMapKey for DatabaseContainer {
     type Value = Arc<SqlitePool>;
 }
 
+/// Looks books up on Goodreads for commands, rate limited apart from the crawler
+pub struct GoodreadsContainer;
+
+impl TypeMapKey for GoodreadsContainer {
+    type Value = Arc<GovernedClient>;
+}
+
 #[group]
-#[commands(lurk, unlurk, set_notify_channel, help)]
+#[commands(
+    lurk,
+    unlurk,
+    prefs,
+    follow,
+    unfollow,
+    followauthor,
+    unfollowauthor,
+    stats,
+    goal,
+    badges,
+    leaderboard,
+    whohasread,
+    compat,
+    twins,
+    recommend,
+    club,
+    nominate,
+    vote,
+    wtr,
+    set_notify_channel,
+    set_template,
+    set_webhook_mode,
+    set_threads,
+    set_announcement_sync,
+    set_digest,
+    set_timezone,
+    set_quiet_hours,
+    set_recap,
+    set_club_favourite,
+    route,
+    help
+)]
 struct General;
 
 struct Handler;
@@ -86,6 +135,27 @@ impl EventHandler for Handler {
         }
     }
 
+    async fn interaction_create(&self, ctx: serenity::prelude::Context, interaction: Interaction) {
+        if let Interaction::MessageComponent(component) = interaction {
+            let data = ctx.data.read().await;
+            if let Some(database) = data.get::<DatabaseContainer>() {
+                let handled = if component.data.custom_id.starts_with(BALLOT_PREFIX) {
+                    handle_ballot(&ctx, database, &component).await
+                } else {
+                    handle_button(&ctx, database, &component).await
+                };
+                if let Err(why) = handled {
+                    tracing::error!(
+                        error.cause_chain = ?why,
+                        error.message = %why,
+                        "Unable to handle button click because: {}",
+                        why
+                    );
+                }
+            }
+        }
+    }
+
     async fn resume(&self, _: serenity::prelude::Context, _: ResumedEvent) {
         tracing::info!("Resumed");
     }
@@ -108,40 +178,459 @@ pub async fn get_discord_client(database: Arc<SqlitePool>) -> Client {
         .expect("Error creating client");
     {
         let mut data = client.data.write().await;
-        data.insert::<DatabaseContainer>(database)
+        data.insert::<DatabaseContainer>(database);
+        data.insert::<GoodreadsContainer>(Arc::new(GovernedClient::default()));
     }
     client
 }
 
-#[tracing::instrument(name = "Posting message to discord", skip(cache_and_http))]
+#[tracing::instrument(name = "Posting message to discord", skip(cache_and_http, pool))]
 pub async fn post_book(
     cache_and_http: Arc<CacheAndHttp>,
+    pool: &SqlitePool,
     book: &Book,
     user: &User,
+    prefs: &Preferences,
+    settings: &GuildSettings,
     channel: ChannelId,
 ) -> anyhow::Result<Message> {
-    let rating = "⭐".repeat(book.rating());
-    let user_id = UserId(user.discord_user_id as u64)
+    let http = &cache_and_http.http;
+    let mut rendered = render_announcement(&cache_and_http, book, user, prefs, settings).await?;
+    if let Err(why) = add_also_read_field(pool, &mut rendered.embed, user, book, settings).await {
+        tracing::error!("Unable to look up other readers of the book: {}", why);
+    }
+    if let Err(why) = add_badge_field(pool, &mut rendered.embed, user, book).await {
+        tracing::error!("Unable to look up badges earned with the book: {}", why);
+    }
+    if prefs.pace {
+        if let Err(why) = add_pace_field(pool, &mut rendered.embed, user, book, settings).await {
+            tracing::error!("Unable to work out the member's reading pace: {}", why);
+        }
+    }
+    if let Err(why) = add_author_suggestion(pool, &mut rendered.embed, user, book).await {
+        tracing::error!("Unable to look up the member's followed authors: {}", why);
+    }
+
+    let discussion = if settings.threads_enabled {
+        existing_thread(http, pool, settings.guild_id, book).await
+    } else {
+        None
+    };
+    let content = match discussion {
+        Some(thread) => with_discussion(&rendered.content, thread),
+        None => rendered.content.clone(),
+    };
+    let components = announcement_buttons(&ReactionCounts::default());
+    let post = MemberPost {
+        username: &rendered.display_name,
+        avatar_url: &rendered.avatar_url,
+        content: &content,
+        embed: &rendered.embed,
+        components: &components,
+    };
+    let message = send_announcement(http, pool, settings, channel, &post).await?;
+
+    let thread = if settings.threads_enabled {
+        match discussion {
+            Some(thread) => {
+                match join_thread(http, thread, &rendered.name, &message, settings.guild_id).await {
+                    Ok(()) => Some(thread),
+                    Err(why) => {
+                        tracing::error!("Unable to post in the discussion thread: {}", why);
+                        None
+                    }
+                }
+            }
+            None => match open_thread(http, settings, &message, book).await {
+                Ok(thread) => Some(thread),
+                Err(why) => {
+                    tracing::error!("Unable to open a discussion thread: {}", why);
+                    None
+                }
+            },
+        }
+    } else {
+        None
+    };
+    if let Err(why) = Announcement::record(pool, user, book, &message, thread).await {
+        tracing::error!("Unable to record announcement because: {}", why);
+    }
+
+    Ok(message)
+}
+
+/// An announcement of a finished book, ready to post or to edit over an earlier one
+pub struct RenderedAnnouncement {
+    /// How the template refers to the member, which is a mention when they asked for one
+    pub name: String,
+    pub display_name: String,
+    pub avatar_url: String,
+    pub content: String,
+    pub embed: CreateEmbed,
+}
+
+/// Fill in the guild's template and build the embed for `book`
+#[tracing::instrument(name = "Rendering announcement", skip(cache_and_http))]
+pub async fn render_announcement(
+    cache_and_http: &Arc<CacheAndHttp>,
+    book: &Book,
+    user: &User,
+    prefs: &Preferences,
+    settings: &GuildSettings,
+) -> anyhow::Result<RenderedAnnouncement> {
+    let template = match settings
+        .announcement_template
+        .as_deref()
+        .map(Template::parse)
+    {
+        Some(Ok(template)) => template,
+        Some(Err(why)) => {
+            tracing::warn!(
+                "Guild ({}) has an invalid announcement template, using the default: {}",
+                settings.guild_id,
+                why
+            );
+            Template::default()
+        }
+        None => Template::default(),
+    };
+    let review = if prefs.include_review && !book.review().is_empty() {
+        Some(review_text(book.review()))
+    } else {
+        None
+    };
+    let discord_user = UserId(user.discord_user_id as u64)
         .to_user(cache_and_http.clone())
         .await?;
-    let msg = format!(
-        "🎉\n {} finished {} by {}",
-        user_id.name,
-        book.title(),
-        book.author()
+    let display_name = discord_user
+        .nick_in(cache_and_http, GuildId(user.discord_guild_id as u64))
+        .await
+        .unwrap_or_else(|| discord_user.name.clone());
+    let name = if prefs.mention {
+        format!("<@{}>", user.discord_user_id)
+    } else {
+        display_name.clone()
+    };
+    let avatar_url = discord_user.face();
+
+    Ok(RenderedAnnouncement {
+        content: template.render(&name, book, review.as_deref()),
+        embed: book_embed(
+            book,
+            &display_name,
+            &avatar_url,
+            review.as_deref(),
+            settings.time_zone,
+        ),
+        name,
+        display_name,
+        avatar_url,
+    })
+}
+
+/// How many other readers an announcement names before counting the rest. Embed fields only hold
+/// 1024 characters, and each line has a mention and a link.
+const ALSO_READ_SHOWN: usize = 5;
+
+/// Name the other members who finished the book, with links to their announcements, and flag it
+/// as a club favourite once enough of the guild has read it
+pub async fn add_also_read_field(
+    pool: &SqlitePool,
+    embed: &mut CreateEmbed,
+    user: &User,
+    book: &Book,
+    settings: &GuildSettings,
+) -> anyhow::Result<()> {
+    let readers = HistoryEntry::other_readers(pool, settings.guild_id, user.id, book.id()).await?;
+    if readers.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = Vec::new();
+    for reader in readers.iter().take(ALSO_READ_SHOWN) {
+        let link = Announcement::latest_for_book(pool, reader.user_id, &reader.book_id)
+            .await?
+            .map(|announcement| announcement.link());
+        lines.push(also_read_line(reader, link.as_deref()));
+    }
+    if readers.len() > ALSO_READ_SHOWN {
+        lines.push(format!("…and {} more", readers.len() - ALSO_READ_SHOWN));
+    }
+    embed.field(
+        also_read_heading(readers.len() + 1, settings.club_favourite_readers),
+        lines.join("\n"),
+        false,
+    );
+    Ok(())
+}
+
+/// `readers` counts the member the announcement is for
+fn also_read_heading(readers: usize, club_favourite_readers: Option<i64>) -> String {
+    match club_favourite_readers {
+        Some(threshold) if readers as i64 >= threshold => {
+            format!("🏆 Club favourite · read by {} members", readers)
+        }
+        _ => "Also read by".to_string(),
+    }
+}
+
+fn also_read_line(reader: &HistoryEntry, announcement: Option<&str>) -> String {
+    let mut line = format!("<@{}> also read this", reader.discord_user_id);
+    if reader.rating > 0 {
+        line.push_str(&format!(" ({})", "⭐".repeat(reader.rating as usize)));
+    }
+    if let Some(announcement) = announcement {
+        line.push_str(&format!(" · [announcement]({})", announcement));
+    }
+    line
+}
+
+/// List the badges finishing the book earned the member
+pub async fn add_badge_field(
+    pool: &SqlitePool,
+    embed: &mut CreateEmbed,
+    user: &User,
+    book: &Book,
+) -> anyhow::Result<()> {
+    let badges = EarnedBadge::for_book(pool, user.id, book.id()).await?;
+    if badges.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<String> = badges.iter().map(badge_line).collect();
+    let heading = if badges.len() == 1 {
+        "🏅 New badge"
+    } else {
+        "🏅 New badges"
+    };
+    embed.field(heading, lines.join("\n"), false);
+    Ok(())
+}
+
+pub fn badge_line(badge: &Badge) -> String {
+    format!("**{}** · {}", badge, badge.description())
+}
+
+/// Say how long the member took over the book and how their weekly streak is going
+pub async fn add_pace_field(
+    pool: &SqlitePool,
+    embed: &mut CreateEmbed,
+    user: &User,
+    book: &Book,
+    settings: &GuildSettings,
+) -> anyhow::Result<()> {
+    let history =
+        HistoryEntry::for_member(pool, user.discord_user_id, user.discord_guild_id, true).await?;
+    let today = Utc::now()
+        .with_timezone(&settings.time_zone)
+        .date()
+        .naive_local();
+    let streak = WeeklyStreak::current(history.iter().map(|entry| entry.read_on), today);
+
+    let mut lines = Vec::new();
+    if let Some(days) = book.reading_days(settings.time_zone) {
+        lines.push(pace_line(days, book.pages().map(i64::from)));
+    }
+    if let Some(streak) = streak_line(&streak) {
+        lines.push(streak);
+    }
+    if !lines.is_empty() {
+        embed.field("⏱️ Pace", lines.join("\n"), false);
+    }
+    Ok(())
+}
+
+fn pace_line(days: i64, pages: Option<i64>) -> String {
+    let mut line = if days == 1 {
+        "finished in 1 day".to_string()
+    } else {
+        format!("finished in {} days", days)
+    };
+    if let Some(pages) = pages.filter(|pages| *pages > 0) {
+        line.push_str(&format!(", {:.0} pages/day", pages as f64 / days as f64));
+    }
+    line
+}
+
+/// A single week isn't much of a streak
+pub fn streak_line(streak: &WeeklyStreak) -> Option<String> {
+    if streak.weeks >= 2 {
+        Some(format!("🔥 {} weeks in a row", streak.weeks))
+    } else {
+        None
+    }
+}
+
+/// After a five star read, suggest following the author to a member who doesn't already
+pub async fn add_author_suggestion(
+    pool: &SqlitePool,
+    embed: &mut CreateEmbed,
+    user: &User,
+    book: &Book,
+) -> anyhow::Result<()> {
+    if book.rating() != 5 {
+        return Ok(());
+    }
+    let following =
+        AuthorFollow::following(pool, user.discord_user_id, user.discord_guild_id).await?;
+    if following
+        .iter()
+        .any(|author| author.name.to_lowercase() == book.author().to_lowercase())
+    {
+        return Ok(());
+    }
+    embed.field(
+        "✍️ Loved it?",
+        format!(
+            "Type `~followauthor {}` to get a DM when they have a new book out.",
+            book.author()
+        ),
+        false,
     );
+    Ok(())
+}
+
+/// What an attached reading chart is called, so an embed can show it with `attachment://`
+const CHART_FILENAME: &str = "reading.png";
+
+/// Show the charts for `stats` as the embed's image. The returned file has to be attached to the
+/// same message.
+pub fn attach_reading_chart(
+    embed: &mut CreateEmbed,
+    stats: &ReadingStats,
+) -> anyhow::Result<AttachmentType<'static>> {
+    let png = reading_chart(stats)?;
+    embed.image(format!("attachment://{}", CHART_FILENAME));
+    Ok(AttachmentType::Bytes {
+        data: Cow::from(png),
+        filename: CHART_FILENAME.to_string(),
+    })
+}
+
+/// Point readers of an announcement at the thread where the book is already being discussed
+pub fn with_discussion(content: &str, thread: ChannelId) -> String {
+    format!("{}\n💬 Join the discussion in <#{}>", content, thread.0)
+}
+
+/// Post through the guild's webhook when it's enabled, otherwise as the bot
+async fn send_announcement(
+    http: &Http,
+    pool: &SqlitePool,
+    settings: &GuildSettings,
+    channel: ChannelId,
+    post: &MemberPost<'_>,
+) -> anyhow::Result<Message> {
+    // Webhooks are tied to a single channel, so only the notification channel gets one
+    if settings.webhook_enabled && channel == settings.notify_channel() {
+        match post_as_member(http, pool, settings, channel, post).await {
+            Ok(message) => return Ok(message),
+            Err(why) => {
+                tracing::error!(
+                    error.cause_chain = ?why,
+                    error.message = %why,
+                    "Unable to post through the guild webhook, posting as the bot instead"
+                );
+            }
+        }
+    }
+
+    let embed = post.embed.clone();
+    let components = post.components.clone();
     channel
-        .send_message(&cache_and_http.http, |m| {
-            m.add_embed(|e| e.url(book.url()).description(rating).title("Review"))
-                .content(msg)
-                .add_file(AttachmentType::Image(
-                    Url::parse(book.image()).expect("valid url struct for the book image"),
-                ))
+        .send_message(http, |m| {
+            m.set_embed(embed)
+                .set_components(components)
+                .content(post.content)
         })
         .await
         .with_context(|| format!("Unable to send message to discord channel {}", channel))
 }
 
+/// The embed attached to every finished book announcement
+fn book_embed(
+    book: &Book,
+    display_name: &str,
+    avatar_url: &str,
+    review: Option<&str>,
+    time_zone: Tz,
+) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(book.title())
+        .url(book.url())
+        .author(|a| a.name(display_name).icon_url(avatar_url))
+        .colour(rating_colour(book.rating()))
+        .field("Author", book.author(), true)
+        .field("Rating", rating_stars(book.rating()), true)
+        .footer(|f| {
+            f.text(format!(
+                "Read on {}",
+                book.read_on(time_zone).format("%B %-d, %Y")
+            ))
+        });
+    if let Some(pages) = book.pages() {
+        embed.field("Pages", pages, true);
+    }
+    if let Some(review) = review {
+        embed.description(review);
+    }
+    if let Some(cover) = cover_url(book.image()) {
+        embed.thumbnail(cover);
+    }
+    embed
+}
+
+fn rating_stars(rating: usize) -> String {
+    match rating {
+        0 => "Unrated".to_string(),
+        rating => "⭐".repeat(rating),
+    }
+}
+
+fn rating_colour(rating: usize) -> Colour {
+    match rating {
+        5 => Colour::GOLD,
+        4 => Colour::DARK_GREEN,
+        3 => Colour::BLUE,
+        2 => Colour::ORANGE,
+        1 => Colour::RED,
+        _ => Colour::LIGHT_GREY,
+    }
+}
+
+/// Goodreads sometimes sends an empty or relative image url, which Discord would reject
+fn cover_url(image: &str) -> Option<Url> {
+    Url::parse(image.trim())
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+/// Goodreads hands reviews over as HTML, so flatten it to plain text short enough for an embed
+fn review_text(review: &str) -> String {
+    const MAX_REVIEW_CHARS: usize = 1000;
+
+    let review = review
+        .replace("<br />", "\n")
+        .replace("<br/>", "\n")
+        .replace("<br>", "\n");
+    let mut text = String::with_capacity(review.len());
+    let mut in_tag = false;
+    for c in review.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text.trim();
+    if text.chars().count() > MAX_REVIEW_CHARS {
+        let truncated: String = text.chars().take(MAX_REVIEW_CHARS).collect();
+        format!("{}…", truncated.trim_end())
+    } else {
+        text.to_string()
+    }
+}
+
 #[tracing::instrument(name = "Verifying guild is setup in database", skip(pool))]
 async fn verify_guild(pool: &SqlitePool, guild: GuildId) -> anyhow::Result<bool> {
     let mut conn = pool.acquire().await?;
@@ -171,7 +660,7 @@ async fn insert_guild(
     let guild_name = &guild.name;
     let system_channel_id = system_channel.0 as i64;
     sqlx::query!(
-        r#"INSERT INTO guilds VALUES (?, ?, ?)"#,
+        r#"INSERT INTO guilds (guild_id, guild_name, notify_channel_id) VALUES (?, ?, ?)"#,
         guild_id,
         guild_name,
         system_channel_id
@@ -181,3 +670,58 @@ async fn insert_guild(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::discord::common::{also_read_heading, cover_url, pace_line, review_text};
+    use claim::{assert_none, assert_some};
+
+    #[test]
+    fn books_become_club_favourites_at_the_guilds_threshold() {
+        assert_eq!(also_read_heading(2, Some(3)), "Also read by");
+        assert_eq!(
+            also_read_heading(3, Some(3)),
+            "🏆 Club favourite · read by 3 members"
+        );
+        assert_eq!(also_read_heading(12, None), "Also read by");
+    }
+
+    #[test]
+    fn pace_lines_leave_out_unknown_page_counts() {
+        assert_eq!(pace_line(4, Some(480)), "finished in 4 days, 120 pages/day");
+        assert_eq!(pace_line(1, None), "finished in 1 day");
+        assert_eq!(pace_line(3, Some(0)), "finished in 3 days");
+    }
+
+    #[test]
+    fn cover_url_accepts_goodreads_images() {
+        let url = assert_some!(cover_url(
+            "https://i.gr-assets.com/images/S/compressed.photo.goodreads.com/books/1440319389l/4981._SX98_.jpg"
+        ));
+        assert_eq!(url.host_str(), Some("i.gr-assets.com"));
+    }
+
+    #[test]
+    fn cover_url_ignores_missing_images() {
+        assert_none!(cover_url(""));
+        assert_none!(cover_url("   "));
+        assert_none!(cover_url("/assets/nophoto/book/111x148.png"));
+        assert_none!(cover_url("data:image/png;base64,AAAA"));
+    }
+
+    #[test]
+    fn review_text_strips_html() {
+        assert_eq!(
+            review_text("So it goes.<br /><br />Funnier than <i>I</i> expected."),
+            "So it goes.\n\nFunnier than I expected."
+        );
+    }
+
+    #[test]
+    fn review_text_truncates_long_reviews() {
+        let review = "a".repeat(1500);
+        let text = review_text(&review);
+        assert_eq!(text.chars().count(), 1001);
+        assert!(text.ends_with('…'));
+    }
+}
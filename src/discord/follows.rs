@@ -0,0 +1,165 @@
+use governor::clock::DefaultClock;
+use governor::middleware::NoOpMiddleware;
+use governor::state::keyed::DefaultKeyedStateStore;
+use governor::{Quota, RateLimiter};
+use nonzero_ext::*;
+use serenity::model::id::UserId;
+use serenity::utils::Colour;
+use serenity::CacheAndHttp;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+use crate::crawler::BookInfo;
+use crate::discord::common::render_announcement;
+use crate::model::{Announcement, Author, Book, Follow, GuildSettings, Preferences, User};
+
+/// DMs followers when a member finishes a book, keeping any one follower from being flooded
+pub struct FollowerDms {
+    limiter: RateLimiter<u64, DefaultKeyedStateStore<u64>, DefaultClock, NoOpMiddleware>,
+}
+
+impl Default for FollowerDms {
+    fn default() -> Self {
+        // A handful an hour, so following a fast reader doesn't turn into spam
+        Self {
+            limiter: RateLimiter::keyed(Quota::per_hour(nonzero!(6u32))),
+        }
+    }
+}
+
+impl FollowerDms {
+    /// Tell the member's followers about a book the member's preferences let us share. Followers
+    /// who are over their limit miss out rather than getting the DM late.
+    #[tracing::instrument(name = "DMing followers", skip(self, cache_and_http, pool))]
+    pub async fn notify(
+        &self,
+        cache_and_http: Arc<CacheAndHttp>,
+        pool: &SqlitePool,
+        book: &Book,
+        user: &User,
+        prefs: &Preferences,
+        settings: &GuildSettings,
+    ) -> anyhow::Result<()> {
+        if !prefs.allow_followers {
+            return Ok(());
+        }
+        let followers =
+            Follow::followers_of(pool, user.discord_user_id, user.discord_guild_id).await?;
+        if followers.is_empty() {
+            return Ok(());
+        }
+        self.limiter.retain_recent();
+
+        let rendered = render_announcement(&cache_and_http, book, user, prefs, settings).await?;
+        let link = Announcement::latest_for_book(pool, user.id, book.id())
+            .await?
+            .map(|announcement| announcement.link());
+        let content = dm_content(
+            &rendered.display_name,
+            &settings.guild_name,
+            link.as_deref(),
+        );
+
+        for follower in followers {
+            let follower = UserId(follower as u64);
+            if self.limiter.check_key(&follower.0).is_err() {
+                tracing::info!(
+                    "Skipping DM to follower {} who has had enough for now",
+                    follower
+                );
+                continue;
+            }
+            let embed = rendered.embed.clone();
+            let sent = match follower.create_dm_channel(&cache_and_http).await {
+                Ok(dm) => dm
+                    .send_message(&cache_and_http.http, |m| {
+                        m.content(&content).set_embed(embed)
+                    })
+                    .await
+                    .map(|_| ()),
+                Err(why) => Err(why),
+            };
+            if let Err(why) = sent {
+                // Usually the follower has DMs from server members turned off
+                tracing::warn!("Unable to DM follower {} because: {}", follower, why);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Tell everyone following the author about a book of theirs the author watch just found. Books
+/// are rare enough that followers aren't rate limited.
+#[tracing::instrument(name = "DMing author followers", skip(cache_and_http, pool))]
+pub async fn dm_author_followers(
+    cache_and_http: &Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    author: &Author,
+    book: &BookInfo,
+) -> anyhow::Result<()> {
+    for follower in author.followers(pool).await? {
+        let follower = UserId(follower as u64);
+        let sent = match follower.create_dm_channel(cache_and_http).await {
+            Ok(dm) => dm
+                .send_message(&cache_and_http.http, |m| {
+                    m.content(format!("📖 There's a new book by **{}**!", author.name))
+                        .embed(|e| {
+                            e.title(&book.title)
+                                .url(book.url())
+                                .colour(Colour::TEAL)
+                                .description(format!(
+                                    "by {}\nType `~unfollowauthor {}` to stop hearing about their books.",
+                                    author.name, author.name
+                                ));
+                            if !book.image_url.is_empty() {
+                                e.thumbnail(&book.image_url);
+                            }
+                            e
+                        })
+                })
+                .await
+                .map(|_| ()),
+            Err(why) => Err(why),
+        };
+        if let Err(why) = sent {
+            tracing::warn!("Unable to DM author follower {} because: {}", follower, why);
+        }
+    }
+
+    Ok(())
+}
+
+fn dm_content(display_name: &str, guild_name: &str, link: Option<&str>) -> String {
+    match link {
+        Some(link) => format!(
+            "📚 {} just finished a book in **{}**\n{}",
+            display_name, guild_name, link
+        ),
+        None => format!(
+            "📚 {} just finished a book in **{}**",
+            display_name, guild_name
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::discord::follows::dm_content;
+
+    #[test]
+    fn dms_link_to_the_announcement_when_there_is_one() {
+        assert_eq!(
+            dm_content(
+                "Herp",
+                "Book Club",
+                Some("https://discord.com/channels/1/2/3")
+            ),
+            "📚 Herp just finished a book in **Book Club**\nhttps://discord.com/channels/1/2/3"
+        );
+        assert_eq!(
+            dm_content("Herp", "Book Club", None),
+            "📚 Herp just finished a book in **Book Club**"
+        );
+    }
+}
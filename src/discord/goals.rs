@@ -0,0 +1,118 @@
+use chrono::Utc;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::CacheAndHttp;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+use crate::model::{GuildSettings, HistoryEntry, Milestone, Preferences, ReadingGoal, User};
+
+/// Announce the furthest goal milestone the member has passed in `year`, if it's new. During
+/// quiet hours it's held for the scheduler to announce once they're over.
+#[tracing::instrument(name = "Checking goal milestones", skip(cache_and_http, pool))]
+pub async fn celebrate_milestone(
+    cache_and_http: &Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    user: &User,
+    prefs: &Preferences,
+    settings: &GuildSettings,
+    year: i32,
+) -> anyhow::Result<()> {
+    let goal = match ReadingGoal::get(pool, user.id, year).await? {
+        Some(goal) => goal,
+        None => return Ok(()),
+    };
+    let books = HistoryEntry::count_in_year(pool, user.id, year).await?;
+    let milestone = match Milestone::reached(goal.target, books) {
+        Some(milestone) => milestone,
+        None => return Ok(()),
+    };
+    let held = settings
+        .quiet_hours
+        .and_then(|quiet| quiet.release_at(settings.time_zone, Utc::now()))
+        .is_some();
+    if !goal.record_milestone(pool, milestone, !held).await? {
+        return Ok(());
+    }
+    if held {
+        return Ok(());
+    }
+
+    post_milestone(
+        cache_and_http,
+        user,
+        prefs,
+        settings,
+        &goal,
+        milestone,
+        books,
+    )
+    .await
+}
+
+#[tracing::instrument(name = "Posting goal milestone", skip(cache_and_http))]
+pub async fn post_milestone(
+    cache_and_http: &Arc<CacheAndHttp>,
+    user: &User,
+    prefs: &Preferences,
+    settings: &GuildSettings,
+    goal: &ReadingGoal,
+    milestone: Milestone,
+    books: i64,
+) -> anyhow::Result<()> {
+    let name = if prefs.mention {
+        format!("<@{}>", user.discord_user_id)
+    } else {
+        let discord_user = UserId(user.discord_user_id as u64)
+            .to_user(cache_and_http.clone())
+            .await?;
+        discord_user
+            .nick_in(cache_and_http, GuildId(user.discord_guild_id as u64))
+            .await
+            .unwrap_or_else(|| discord_user.name.clone())
+    };
+    ChannelId(settings.notify_channel_id as u64)
+        .say(
+            &cache_and_http.http,
+            milestone_message(&name, milestone, goal, books),
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn milestone_message(name: &str, milestone: Milestone, goal: &ReadingGoal, books: i64) -> String {
+    match milestone {
+        Milestone::Halfway => format!(
+            "📖 {} is halfway to their goal of {}! {} down, {} to go.",
+            name,
+            goal,
+            books,
+            goal.target - books
+        ),
+        Milestone::Reached => format!("🎉 {} reached their goal of {}!", name, goal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::discord::goals::milestone_message;
+    use crate::model::{Milestone, ReadingGoal};
+
+    #[test]
+    fn milestones_say_how_far_the_member_has_got() {
+        let goal = ReadingGoal {
+            user_id: 1,
+            year: 2022,
+            target: 30,
+        };
+
+        assert_eq!(
+            milestone_message("Herp", Milestone::Halfway, &goal, 15),
+            "📖 Herp is halfway to their goal of 30 books in 2022! 15 down, 15 to go."
+        );
+        assert_eq!(
+            milestone_message("Herp", Milestone::Reached, &goal, 30),
+            "🎉 Herp reached their goal of 30 books in 2022!"
+        );
+    }
+}
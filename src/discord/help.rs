@@ -0,0 +1,115 @@
+/// Posted when the bot joins a guild and for a plain `~help`. The rest of the help is split into
+/// topics, since it doesn't fit in one message.
+pub const HELP_STR: &str = r#"👋
+To sign up for sending notifications about your book progress, type `~lurk <good reads id>` where <good reads id> is your integer id assigned by goodreads. Sign in to good reads, go to your profile, and look at the URL. You should see something like `https://www.goodreads.com/user/show/<good reads id>-herp-derplinson`
+
+To chose which channel is used for notifications, (1) be an admin and (2) type `~set_notify_channel` in the channel that should have it.
+
+There's a lot more I can do. Type `~help <topic>` to read about:
+`setup` - wording announcements, webhooks, discussion threads and time zones, for admins
+`posting` - digests, quiet hours, the year-in-review, club favourites and routing, for admins
+`me` - choosing what gets announced about you, following members and authors, and leaving
+`stats` - stats, reading goals, the leaderboard and badges
+`books` - finding readers, comparing tastes, recommendations and want to read lists
+`club` - reading books together and voting on the next one
+
+To see this message again, type `~help`"#;
+
+const SETUP_HELP: &str = r#"To chose which channel is used for notifications, (1) be an admin and (2) type `~set_notify_channel` in the channel that should have it.
+
+To change how announcements are worded, (1) be an admin and (2) type `~set_template <template>`. Templates can use `{user}`, `{title}`, `{author}`, `{rating}`, `{pages}` and `{review}`. Type `~set_template reset` to go back to the default.
+
+To have announcements posted under each member's name and avatar, (1) be an admin and (2) type `~set_webhook_mode on`. I'll need the _Manage Webhooks_ permission in the notification channel.
+
+To open a discussion thread under each announcement, (1) be an admin and (2) type `~set_threads on`, optionally followed by how long a quiet thread stays open: `1h`, `24h`, `3d` or `1w`. When someone finishes a book that already has a thread, I'll point them to it instead.
+
+To choose what happens to an announcement when someone changes their rating or takes the book off their read shelf, (1) be an admin and (2) type `~set_announcement_sync <off|edit|delete>`. `edit` updates the rating and strikes removed books through, `delete` updates the rating and deletes removed books. It's `off` until you choose, and announcements are kept in sync for two weeks.
+
+To set the server's time zone, (1) be an admin and (2) type `~set_timezone <zone>` with a name like `Europe/London`. Digests, quiet hours and reading dates all follow it."#;
+
+const POSTING_HELP: &str = r#"To swap one announcement per book for a regular round-up, (1) be an admin and (2) type `~set_digest <daily|weekly|monthly>`, optionally followed by the time to post it, like `~set_digest weekly 18:00`. Weekly digests go out on Mondays and monthly digests on the 1st. Type `~set_digest off` to go back to announcing each book.
+
+To hold announcements back overnight, (1) be an admin and (2) type `~set_quiet_hours 22:00-07:00`. Books finished in that window are announced when it ends. Type `~set_quiet_hours off` to announce at any time.
+
+To choose when the year-in-review is posted, (1) be an admin and (2) type `~set_recap <MM-DD>`. It goes out on January 1st by default, at the same time of day as digests. Recaps in January look back on the year before, and later ones on the year so far. Type `~set_recap off` to skip it.
+
+When another member has already finished a book, its announcement names them with their rating and a link to their post. To choose how many readers make a book a club favourite, (1) be an admin and (2) type `~set_club_favourite <readers>`. It's 3 by default; type `~set_club_favourite off` to stop flagging them.
+
+To announce some books somewhere other than the notification channel, (1) be an admin and (2) type `~route shelf <shelf> <#channel>` or `~route rating <1-5> <#channel>`. Rules are checked in the order they were added, and books that match none go to the notification channel. Type `~route` to see the rules, `~route remove <number>` to drop one or `~route clear` to drop them all."#;
+
+const ME_HELP: &str = r#"To choose what gets announced about your reading, type `~prefs`. You can set a minimum rating, hide unrated books, skip shelves, include your review text, be @mentioned, get your own year-in-review by DM, or have announcements say how long each book took and how your weekly reading streak is going.
+
+To get a DM whenever another member finishes a book, type `~follow @member`. Type `~follow` to see who you follow and `~unfollow @member` to stop. If you'd rather not be followed, type `~prefs followers off`.
+
+To get a DM when an author has a new book out, type `~followauthor <name>`. Type `~followauthor` to see which authors you follow and `~unfollowauthor <name>` to stop. I'll suggest it when you give a book five stars.
+
+To remove yourself from notifications, type `~unlurk`. You'll be excluded from further... _lurking_ 😏."#;
+
+const STATS_HELP: &str = r#"To see what you've read this year, type `~stats`. Add a year like `~stats 2021` to look further back, or @mention someone to see theirs.
+
+To set yourself a reading challenge, type `~goal <number>` with how many books you want to finish this year. I'll cheer when you're halfway and when you get there. Type `~goal` to check how it's going.
+
+To see who's read the most, type `~leaderboard`. Add `month` for just this month, or `pages` to rank by pages instead of books.
+
+I hand out badges for reading milestones, like a first book, a 1000 page book or a book every week for a month, and announce them with the book that earned them. Type `~badges` to see yours, or @mention someone to see theirs."#;
+
+const BOOKS_HELP: &str = r#"To find out who here has read a book, type `~whohasread <title or author>`. You don't need the whole name: `~whohasread le guin` works.
+
+To see how alike your reading is to someone else's, type `~compat @member`, or mention two members to compare them. Type `~twins` for the server's most similar readers.
+
+For something new to read, type `~recommend`. Suggestions come from what members with similar taste rated highly, leaving out anything you've read or are reading.
+
+To keep a list of books you want to read, type `~wtr add <goodreads book url>` or click 📚 _Want to read_ on an announcement. Type `~wtr` to see the list and `~wtr remove <number>` to take a book off. Books are ticked off once they turn up on your read shelf, and `~wtr sync on` adds the books on your Goodreads to-read shelf too."#;
+
+const CLUB_HELP: &str = r#"To read a book together, type `~club start <goodreads book url> <YYYY-MM-DD>` and have everyone type `~club join`. I'll post in the notification channel as members finish it, and wrap up everyone's ratings on the deadline. Type `~club` to see how it's going.
+
+To choose the next group read together, have everyone type `~nominate <goodreads book url>`. Whoever nominated first types `~vote start <YYYY-MM-DD>` to open the voting, adding `ranked` to rank favourites instead of picking every book you'd read, or `club <YYYY-MM-DD>` to start a group read of the winner. Type `~vote` for the ballot."#;
+
+/// The pages of `~help <topic>`, each short enough to post as one message
+const HELP_TOPICS: &[(&str, &str)] = &[
+    ("setup", SETUP_HELP),
+    ("posting", POSTING_HELP),
+    ("me", ME_HELP),
+    ("stats", STATS_HELP),
+    ("books", BOOKS_HELP),
+    ("club", CLUB_HELP),
+];
+
+/// The help page for a topic named in `HELP_STR`, ignoring case
+pub fn help_topic(topic: &str) -> Option<&'static str> {
+    HELP_TOPICS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(topic.trim()))
+        .map(|(_, text)| *text)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::discord::help::{help_topic, HELP_STR, HELP_TOPICS};
+    use claim::{assert_none, assert_some};
+
+    /// The longest message discord will post
+    const MAX_MESSAGE_LENGTH: usize = 2000;
+
+    #[test]
+    fn every_help_message_fits_in_one_discord_message() {
+        assert!(HELP_STR.chars().count() <= MAX_MESSAGE_LENGTH);
+        for (name, text) in HELP_TOPICS {
+            assert!(
+                text.chars().count() <= MAX_MESSAGE_LENGTH,
+                "~help {} is {} characters",
+                name,
+                text.chars().count()
+            );
+        }
+    }
+
+    #[test]
+    fn every_topic_is_listed_in_the_overview() {
+        for (name, _) in HELP_TOPICS {
+            assert!(HELP_STR.contains(&format!("`{}` - ", name)));
+        }
+        assert_some!(help_topic("Stats"));
+        assert_none!(help_topic("nonsense"));
+    }
+}
@@ -1,4 +1,16 @@
 mod commands;
 mod common;
+mod follows;
+mod goals;
+mod help;
+mod polls;
+mod reactions;
+mod sync;
+mod template;
+mod thread;
+mod webhook;
 
-pub use common::{get_discord_client, post_book};
+pub use common::{attach_reading_chart, get_discord_client, post_book};
+pub use follows::{dm_author_followers, FollowerDms};
+pub use goals::{celebrate_milestone, post_milestone};
+pub use sync::{retract_announcement, update_announcement};
@@ -0,0 +1,235 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::interactions::message_component::MessageComponentInteraction;
+use serenity::model::interactions::InteractionResponseType;
+use serenity::prelude::Context;
+use serenity::utils::Colour;
+use sqlx::SqlitePool;
+
+use crate::model::{Nomination, Poll, PollMethod};
+
+/// Ballot ids look like `poll:12:approve` or `poll:12:rank:1`, so old ballots keep pointing at
+/// their own poll
+pub const BALLOT_PREFIX: &str = "poll:";
+
+/// How many choices a ranked ballot asks for
+const RANKED_CHOICES: usize = 3;
+
+/// Discord cuts select menu labels and descriptions off at 100 characters
+const OPTION_LENGTH: usize = 100;
+
+/// The poll's nominations, numbered, with when voting closes
+pub fn ballot_embed(poll: &Poll, nominations: &[Nomination], time_zone: Tz) -> CreateEmbed {
+    let how = match poll.method {
+        PollMethod::Approval => "Pick every book you'd be happy to read.",
+        PollMethod::Ranked => {
+            "Pick your favourites in order. If no book is anyone's first choice often enough, the least popular is knocked out and its votes go to each voter's next choice."
+        }
+    };
+    let mut embed = CreateEmbed::default();
+    embed
+        .title("🗳️ Vote for the next book")
+        .colour(Colour::PURPLE)
+        .description(format!("{}\n\n{}", how, nomination_lines(nominations)));
+    if let Some(last_day) = poll.last_day(time_zone) {
+        embed.footer(|f| {
+            f.text(format!(
+                "Voting closes at the end of {}",
+                last_day.format("%A %B %-d")
+            ))
+        });
+    }
+    embed
+}
+
+/// Each nomination numbered in the order it was put forward
+pub fn nomination_lines(nominations: &[Nomination]) -> String {
+    nominations
+        .iter()
+        .enumerate()
+        .map(|(index, nomination)| {
+            format!(
+                "{}. [{}]({}) by {} · nominated by <@{}>",
+                index + 1,
+                nomination.title,
+                nomination.url,
+                nomination.author,
+                nomination.nominated_by
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// One menu of every nomination for approval polls, and one menu per choice for ranked polls
+pub fn ballot_components(poll: &Poll, nominations: &[Nomination]) -> CreateComponents {
+    let menus: Vec<(String, String, u64)> = match poll.method {
+        PollMethod::Approval => vec![(
+            format!("{}{}:approve", BALLOT_PREFIX, poll.id),
+            "Books you'd read".to_string(),
+            nominations.len() as u64,
+        )],
+        PollMethod::Ranked => (1..=RANKED_CHOICES.min(nominations.len()))
+            .map(|rank| {
+                (
+                    format!("{}{}:rank:{}", BALLOT_PREFIX, poll.id, rank),
+                    format!("{} choice", ordinal(rank)),
+                    1,
+                )
+            })
+            .collect(),
+    };
+
+    let mut components = CreateComponents::default();
+    for (custom_id, placeholder, max_values) in menus {
+        components.create_action_row(|row| {
+            row.create_select_menu(|menu| {
+                menu.custom_id(custom_id)
+                    .placeholder(placeholder)
+                    .min_values(1)
+                    .max_values(max_values)
+                    .options(|options| {
+                        for nomination in nominations {
+                            options.create_option(|option| {
+                                option
+                                    .label(shorten(&nomination.title))
+                                    .description(shorten(&nomination.author))
+                                    .value(&nomination.book_id)
+                            });
+                        }
+                        options
+                    })
+            })
+        });
+    }
+    components
+}
+
+/// Record a choice from one of a poll's menus, and let the voter know what their ballot says
+/// now without anyone else seeing it
+#[tracing::instrument(name = "Handling poll ballot", skip(ctx, pool, component))]
+pub async fn handle_ballot(
+    ctx: &Context,
+    pool: &SqlitePool,
+    component: &MessageComponentInteraction,
+) -> anyhow::Result<()> {
+    let (poll_id, rank) = match parse_ballot_id(&component.data.custom_id) {
+        Some(ballot) => ballot,
+        None => return Ok(()),
+    };
+    let voter = component.user.id.0 as i64;
+
+    let reply = match Poll::find(pool, poll_id).await? {
+        Some(poll) if poll.is_voting(Utc::now().timestamp()) => {
+            let nominations = poll.nominations(pool).await?;
+            let choices: Vec<String> = component
+                .data
+                .values
+                .iter()
+                .filter(|book_id| {
+                    nominations
+                        .iter()
+                        .any(|nomination| &nomination.book_id == *book_id)
+                })
+                .cloned()
+                .collect();
+            match (rank, choices.first()) {
+                (None, _) => poll.approve(pool, voter, &choices).await?,
+                (Some(rank), Some(book_id)) => poll.rank(pool, voter, book_id, rank).await?,
+                (Some(_), None) => {}
+            }
+            let ballot = poll.ballot(pool, voter).await?;
+            ballot_summary(&poll, &ballot, &nominations)
+        }
+        _ => "Voting on this poll has closed.".to_string(),
+    };
+
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|d| d.ephemeral(true).content(reply))
+        })
+        .await?;
+    Ok(())
+}
+
+/// The poll id, and which choice a ranked menu is for
+fn parse_ballot_id(custom_id: &str) -> Option<(i64, Option<i64>)> {
+    let mut parts = custom_id.strip_prefix(BALLOT_PREFIX)?.split(':');
+    let poll_id = parts.next()?.parse().ok()?;
+    match (parts.next()?, parts.next(), parts.next()) {
+        ("approve", None, None) => Some((poll_id, None)),
+        ("rank", Some(rank), None) => {
+            let rank = rank.parse().ok()?;
+            Some((poll_id, Some(rank)))
+        }
+        _ => None,
+    }
+}
+
+fn ballot_summary(poll: &Poll, ballot: &[String], nominations: &[Nomination]) -> String {
+    let titles: Vec<&str> = ballot
+        .iter()
+        .filter_map(|book_id| {
+            nominations
+                .iter()
+                .find(|nomination| &nomination.book_id == book_id)
+                .map(|nomination| nomination.title.as_str())
+        })
+        .collect();
+    if titles.is_empty() {
+        return "Your ballot is empty.".to_string();
+    }
+    match poll.method {
+        PollMethod::Approval => format!("🗳️ You'd read: **{}**", titles.join("**, **")),
+        PollMethod::Ranked => {
+            let choices: Vec<String> = titles
+                .iter()
+                .enumerate()
+                .map(|(index, title)| format!("{}. **{}**", index + 1, title))
+                .collect();
+            format!("🗳️ Your ballot:\n{}", choices.join("\n"))
+        }
+    }
+}
+
+fn ordinal(rank: usize) -> &'static str {
+    match rank {
+        1 => "1st",
+        2 => "2nd",
+        3 => "3rd",
+        _ => "Next",
+    }
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= OPTION_LENGTH {
+        text.to_string()
+    } else {
+        let mut short: String = text.chars().take(OPTION_LENGTH - 1).collect();
+        short.push('…');
+        short
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::discord::polls::{parse_ballot_id, shorten};
+
+    #[test]
+    fn ballot_ids_name_the_poll_and_choice() {
+        assert_eq!(parse_ballot_id("poll:12:approve"), Some((12, None)));
+        assert_eq!(parse_ballot_id("poll:12:rank:2"), Some((12, Some(2))));
+        assert_eq!(parse_ballot_id("poll:12:rank"), None);
+        assert_eq!(parse_ballot_id("announcement:congrats"), None);
+    }
+
+    #[test]
+    fn long_titles_fit_in_a_menu() {
+        let title = "a".repeat(120);
+
+        assert_eq!(shorten(&title).chars().count(), 100);
+        assert_eq!(shorten("Dune"), "Dune");
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::anyhow;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::interactions::message_component::{ButtonStyle, MessageComponentInteraction};
+use serenity::model::interactions::InteractionResponseType;
+use serenity::prelude::Context;
+use sqlx::SqlitePool;
+
+use crate::model::{Announcement, Reaction, ReactionCounts, ReactionKind, WantToRead};
+
+/// Button ids look like `announcement:congrats` so other components can share the handler
+const BUTTON_PREFIX: &str = "announcement:";
+
+const REACTIONS_FIELD: &str = "Reactions";
+
+/// The Congrats, Want to read and Me too buttons, labelled with how many members clicked each
+pub fn announcement_buttons(counts: &ReactionCounts) -> CreateComponents {
+    let mut components = CreateComponents::default();
+    components.create_action_row(|row| {
+        for kind in ReactionKind::ALL {
+            row.create_button(|b| {
+                b.custom_id(format!("{}{}", BUTTON_PREFIX, kind.as_str()))
+                    .style(ButtonStyle::Secondary)
+                    .emoji(emoji(kind))
+                    .label(label(kind, counts.get(kind)))
+            });
+        }
+        row
+    });
+    components
+}
+
+/// Tally the reactions on the embed, once anyone has reacted
+pub fn add_reaction_field(embed: &mut CreateEmbed, counts: &ReactionCounts) {
+    if let Some(summary) = reaction_summary(counts) {
+        embed.field(REACTIONS_FIELD, summary, false);
+    }
+}
+
+/// Record a click on one of an announcement's buttons and refresh the counts shown on it
+#[tracing::instrument(name = "Handling announcement button", skip(ctx, pool, component))]
+pub async fn handle_button(
+    ctx: &Context,
+    pool: &SqlitePool,
+    component: &MessageComponentInteraction,
+) -> anyhow::Result<()> {
+    let kind = match component
+        .data
+        .custom_id
+        .strip_prefix(BUTTON_PREFIX)
+        .and_then(ReactionKind::parse)
+    {
+        Some(kind) => kind,
+        None => return Ok(()),
+    };
+    let announcement = Announcement::find_by_message(pool, component.message.id.0 as i64)
+        .await?
+        .ok_or_else(|| {
+            anyhow!(
+                "No announcement recorded for message {}",
+                component.message.id
+            )
+        })?;
+    let clicker = component.user.id.0 as i64;
+
+    let reacted = Reaction::toggle(pool, announcement.id, clicker, kind).await?;
+    if kind == ReactionKind::WantToRead {
+        if reacted {
+            WantToRead::new(
+                clicker,
+                announcement.guild_id,
+                &announcement.book_id,
+                &announcement.title,
+                &announcement.author,
+                &announcement.url,
+            )
+            .add(pool)
+            .await?;
+        } else {
+            WantToRead::remove(pool, clicker, announcement.guild_id, &announcement.book_id).await?;
+        }
+    }
+
+    let counts = ReactionCounts::for_announcement(pool, announcement.id).await?;
+    let embed = component.message.embeds.first().cloned().map(|mut embed| {
+        embed.fields.retain(|field| field.name != REACTIONS_FIELD);
+        let mut embed = CreateEmbed::from(embed);
+        add_reaction_field(&mut embed, &counts);
+        embed
+    });
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    if let Some(embed) = embed {
+                        d.set_embed(embed);
+                    }
+                    d.set_components(announcement_buttons(&counts))
+                })
+        })
+        .await?;
+
+    if kind == ReactionKind::WantToRead {
+        let confirmation = if reacted {
+            format!(
+                "Added _{}_ to your want to read list 📚",
+                announcement.title
+            )
+        } else {
+            format!("Took _{}_ off your want to read list", announcement.title)
+        };
+        component
+            .create_followup_message(&ctx.http, |f| f.ephemeral(true).content(confirmation))
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn emoji(kind: ReactionKind) -> char {
+    match kind {
+        ReactionKind::Congrats => '🎉',
+        ReactionKind::WantToRead => '📚',
+        ReactionKind::MeToo => '🙋',
+    }
+}
+
+fn label(kind: ReactionKind, count: i64) -> String {
+    let name = match kind {
+        ReactionKind::Congrats => "Congrats",
+        ReactionKind::WantToRead => "Want to read",
+        ReactionKind::MeToo => "Me too",
+    };
+    match count {
+        0 => name.to_string(),
+        count => format!("{} · {}", name, count),
+    }
+}
+
+fn reaction_summary(counts: &ReactionCounts) -> Option<String> {
+    if counts.is_empty() {
+        return None;
+    }
+    let summary = ReactionKind::ALL
+        .iter()
+        .filter(|kind| counts.get(**kind) > 0)
+        .map(|kind| format!("{} {}", emoji(*kind), counts.get(*kind)))
+        .collect::<Vec<_>>()
+        .join("  ");
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::discord::reactions::{label, reaction_summary};
+    use crate::model::{ReactionCounts, ReactionKind};
+    use claim::{assert_none, assert_some};
+
+    #[test]
+    fn labels_only_show_counts_once_someone_has_clicked() {
+        assert_eq!(label(ReactionKind::Congrats, 0), "Congrats");
+        assert_eq!(label(ReactionKind::WantToRead, 2), "Want to read · 2");
+    }
+
+    #[test]
+    fn summary_skips_reactions_nobody_used() {
+        assert_none!(reaction_summary(&ReactionCounts::default()));
+        let counts = ReactionCounts {
+            congrats: 4,
+            want_to_read: 0,
+            me_too: 1,
+        };
+        assert_eq!(assert_some!(reaction_summary(&counts)), "🎉 4  🙋 1");
+    }
+}
@@ -0,0 +1,249 @@
+use anyhow::Context;
+use serenity::builder::CreateEmbed;
+use serenity::http::Http;
+use serenity::model::channel::Embed;
+use serenity::model::id::{ChannelId, MessageId};
+use serenity::CacheAndHttp;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+use crate::discord::common::{
+    add_also_read_field, add_author_suggestion, add_badge_field, add_pace_field,
+    render_announcement, with_discussion,
+};
+use crate::discord::reactions::add_reaction_field;
+use crate::discord::webhook::{is_not_found, managed_webhook};
+use crate::model::{
+    Announcement, Book, GuildSettings, Preferences, ReactionCounts, SyncMode, User,
+};
+
+/// Discord refuses messages longer than this
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+const RETRACTED_NOTE: &str = "_Taken off the read shelf since this was posted._";
+
+/// Re-render an announcement after the member changed their rating of the book
+#[tracing::instrument(name = "Updating announcement", skip(cache_and_http, pool))]
+pub async fn update_announcement(
+    cache_and_http: Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    announcement: &mut Announcement,
+    book: &Book,
+    user: &User,
+    prefs: &Preferences,
+    settings: &GuildSettings,
+) -> anyhow::Result<()> {
+    if settings.announcement_sync == SyncMode::Off {
+        return Ok(());
+    }
+    if !can_edit(settings, announcement) {
+        return stop_syncing(pool, announcement).await;
+    }
+
+    let mut rendered = render_announcement(&cache_and_http, book, user, prefs, settings).await?;
+    add_also_read_field(pool, &mut rendered.embed, user, book, settings).await?;
+    add_badge_field(pool, &mut rendered.embed, user, book).await?;
+    if prefs.pace {
+        add_pace_field(pool, &mut rendered.embed, user, book, settings).await?;
+    }
+    add_author_suggestion(pool, &mut rendered.embed, user, book).await?;
+    let counts = ReactionCounts::for_announcement(pool, announcement.id).await?;
+    add_reaction_field(&mut rendered.embed, &counts);
+    let content = match announcement.joined_discussion() {
+        Some(thread) => with_discussion(&rendered.content, ChannelId(thread as u64)),
+        None => rendered.content,
+    };
+    match edit_message(
+        &cache_and_http.http,
+        settings,
+        announcement,
+        &content,
+        Some(rendered.embed),
+    )
+    .await
+    {
+        Ok(()) => announcement.set_rating(pool, book.rating() as i64).await,
+        Err(why) if is_gone(&why) => {
+            tracing::info!(
+                "Announcement {} was deleted from discord, no longer keeping it in sync",
+                announcement.id
+            );
+            announcement.retract(pool).await
+        }
+        Err(why) => Err(why),
+    }
+}
+
+/// Take back the announcement of a book the member removed from their read shelf, either by
+/// striking it through or deleting it depending on the guild's sync mode
+#[tracing::instrument(name = "Retracting announcement", skip(http, pool))]
+pub async fn retract_announcement(
+    http: &Http,
+    pool: &SqlitePool,
+    announcement: &mut Announcement,
+    settings: &GuildSettings,
+) -> anyhow::Result<()> {
+    let result = match settings.announcement_sync {
+        SyncMode::Off => return Ok(()),
+        SyncMode::Edit if !can_edit(settings, announcement) => {
+            return stop_syncing(pool, announcement).await
+        }
+        SyncMode::Edit => mark_retracted(http, settings, announcement).await,
+        SyncMode::Delete => delete_message(http, settings, announcement).await,
+    };
+    match result {
+        // Nothing left to take back when the message is already gone
+        Err(why) if !is_gone(&why) => Err(why),
+        _ => announcement.retract(pool).await,
+    }
+}
+
+async fn mark_retracted(
+    http: &Http,
+    settings: &GuildSettings,
+    announcement: &Announcement,
+) -> anyhow::Result<()> {
+    let message = http
+        .get_message(
+            announcement.channel_id as u64,
+            announcement.message_id as u64,
+        )
+        .await
+        .with_context(|| format!("Unable to retrieve message {}", announcement.message_id))?;
+
+    edit_message(
+        http,
+        settings,
+        announcement,
+        &struck_through(&message.content),
+        None,
+    )
+    .await
+}
+
+/// Edit an announcement, going through the webhook when it was posted by one
+async fn edit_message(
+    http: &Http,
+    settings: &GuildSettings,
+    announcement: &Announcement,
+    content: &str,
+    embed: Option<CreateEmbed>,
+) -> anyhow::Result<()> {
+    let message_id = MessageId(announcement.message_id as u64);
+    match announcement.webhook_id {
+        Some(webhook_id) => {
+            let webhook = managed_webhook(http, settings, webhook_id).await?;
+            webhook
+                .edit_message(http, message_id, |m| {
+                    m.content(content);
+                    if let Some(embed) = embed {
+                        m.embeds(vec![Embed::fake(|e| {
+                            *e = embed;
+                            e
+                        })]);
+                    }
+                    m
+                })
+                .await
+                .with_context(|| format!("Unable to edit webhook message {}", message_id))?;
+        }
+        None => {
+            ChannelId(announcement.channel_id as u64)
+                .edit_message(http, message_id, |m| {
+                    m.content(content);
+                    if let Some(embed) = embed {
+                        m.set_embed(embed);
+                    }
+                    m
+                })
+                .await
+                .with_context(|| format!("Unable to edit message {}", message_id))?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn delete_message(
+    http: &Http,
+    settings: &GuildSettings,
+    announcement: &Announcement,
+) -> anyhow::Result<()> {
+    let message_id = MessageId(announcement.message_id as u64);
+    // The bot needs Manage Messages to delete a webhook's message, unless it still has the webhook
+    if let Some(webhook_id) = announcement.webhook_id {
+        if let Ok(webhook) = managed_webhook(http, settings, webhook_id).await {
+            return webhook
+                .delete_message(http, message_id)
+                .await
+                .with_context(|| format!("Unable to delete webhook message {}", message_id));
+        }
+    }
+
+    ChannelId(announcement.channel_id as u64)
+        .delete_message(http, message_id)
+        .await
+        .with_context(|| format!("Unable to delete message {}", message_id))
+}
+
+/// Webhook messages can only be edited by the webhook that posted them
+fn can_edit(settings: &GuildSettings, announcement: &Announcement) -> bool {
+    announcement.webhook_id.is_none() || announcement.webhook_id == settings.webhook_id
+}
+
+async fn stop_syncing(pool: &SqlitePool, announcement: &mut Announcement) -> anyhow::Result<()> {
+    tracing::info!(
+        "Announcement {} was posted by a webhook that has since been replaced, no longer keeping it in sync",
+        announcement.id
+    );
+    announcement.retract(pool).await
+}
+
+fn is_gone(why: &anyhow::Error) -> bool {
+    matches!(why.downcast_ref::<serenity::Error>(), Some(inner) if is_not_found(inner))
+}
+
+/// Strike through every line of an announcement and note why
+fn struck_through(content: &str) -> String {
+    let struck = content
+        .lines()
+        .map(|line| match line.trim() {
+            "" => String::new(),
+            line => format!("~~{}~~", line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let marked = format!("{}\n{}", struck, RETRACTED_NOTE);
+    if marked.chars().count() <= MAX_MESSAGE_LENGTH {
+        marked
+    } else {
+        // Too long to strike through, so just tack the note on the end
+        let room = MAX_MESSAGE_LENGTH - RETRACTED_NOTE.chars().count() - 1;
+        let truncated: String = content.chars().take(room).collect();
+        format!("{}\n{}", truncated, RETRACTED_NOTE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::discord::sync::{struck_through, MAX_MESSAGE_LENGTH, RETRACTED_NOTE};
+
+    #[test]
+    fn struck_through_strikes_each_line() {
+        assert_eq!(
+            struck_through("🎉\n brett finished Dune by Frank Herbert"),
+            format!(
+                "~~🎉~~\n~~brett finished Dune by Frank Herbert~~\n{}",
+                RETRACTED_NOTE
+            )
+        );
+    }
+
+    #[test]
+    fn struck_through_stays_within_discords_limit() {
+        let content = "a\n".repeat(990);
+        let marked = struck_through(&content);
+        assert!(marked.chars().count() <= MAX_MESSAGE_LENGTH);
+        assert!(marked.ends_with(RETRACTED_NOTE));
+    }
+}
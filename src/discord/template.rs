@@ -0,0 +1,234 @@
+use anyhow::anyhow;
+
+use crate::model::Book;
+
+pub const DEFAULT_TEMPLATE: &str = "🎉\n {user} finished {title} by {author}";
+
+/// Discord refuses messages longer than this
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placeholder {
+    User,
+    Title,
+    Author,
+    Rating,
+    Pages,
+    Review,
+}
+
+impl Placeholder {
+    const ALL: [(&'static str, Placeholder); 6] = [
+        ("user", Placeholder::User),
+        ("title", Placeholder::Title),
+        ("author", Placeholder::Author),
+        ("rating", Placeholder::Rating),
+        ("pages", Placeholder::Pages),
+        ("review", Placeholder::Review),
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(known, _)| *known == name)
+            .map(|(_, placeholder)| *placeholder)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// An announcement template such as `{user} finished {title} by {author}`.
+/// Literal braces are written as `{{` and `}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Template::parse(DEFAULT_TEMPLATE).expect("default template is valid")
+    }
+}
+
+impl Template {
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        if source.trim().is_empty() {
+            return Err(anyhow!("The template is empty"));
+        }
+        if source.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(anyhow!(
+                "The template is longer than {} characters",
+                MAX_MESSAGE_LENGTH
+            ));
+        }
+
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => {
+                                return Err(anyhow!("A `{{` is missing its closing `}}`"))
+                            }
+                            Some(c) => name.push(c),
+                        }
+                    }
+                    let placeholder = Placeholder::from_name(name.trim()).ok_or_else(|| {
+                        anyhow!(
+                            "`{{{}}}` isn't a placeholder I know. Try one of: {}",
+                            name,
+                            Template::placeholders()
+                        )
+                    })?;
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Placeholder(placeholder));
+                }
+                '}' => return Err(anyhow!("Found a `}}` without an opening `{{`")),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+
+        Ok(Template { segments })
+    }
+
+    /// Every placeholder a template may use, formatted for help messages
+    pub fn placeholders() -> String {
+        Placeholder::ALL
+            .iter()
+            .map(|(name, _)| format!("`{{{}}}`", name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Fill in the template for `book`. `review` is left blank when the user hasn't opted in to
+    /// sharing it.
+    pub fn render(&self, user: &str, book: &Book, review: Option<&str>) -> String {
+        let rendered: String = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.to_owned(),
+                Segment::Placeholder(Placeholder::User) => user.to_owned(),
+                Segment::Placeholder(Placeholder::Title) => book.title().to_owned(),
+                Segment::Placeholder(Placeholder::Author) => book.author().to_owned(),
+                Segment::Placeholder(Placeholder::Rating) => match book.rating() {
+                    0 => "unrated".to_string(),
+                    rating => "⭐".repeat(rating),
+                },
+                Segment::Placeholder(Placeholder::Pages) => book
+                    .pages()
+                    .map(|pages| pages.to_string())
+                    .unwrap_or_else(|| "?".to_string()),
+                Segment::Placeholder(Placeholder::Review) => review.unwrap_or("").to_owned(),
+            })
+            .collect();
+
+        if rendered.chars().count() > MAX_MESSAGE_LENGTH {
+            let mut truncated: String = rendered.chars().take(MAX_MESSAGE_LENGTH - 1).collect();
+            truncated.push('…');
+            truncated
+        } else {
+            rendered
+        }
+    }
+}
+
+/// A made up book used to preview templates
+pub fn sample_book() -> Book {
+    Book::new(
+        "The Left Hand of Darkness",
+        "https://www.goodreads.com/book/show/18423",
+        "Sat, 06 Aug 2022 19:07:34 -0700",
+        "18423",
+        5,
+        "Ursula K. Le Guin",
+        "",
+    )
+    .expect("sample book is valid")
+    .with_review("Genly Ai and Estraven will stay with me for a long time.")
+    .with_pages(Some(304))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::discord::template::{sample_book, Template, DEFAULT_TEMPLATE};
+    use claim::{assert_err, assert_ok};
+
+    #[test]
+    fn default_template_renders_the_original_announcement() {
+        let template = assert_ok!(Template::parse(DEFAULT_TEMPLATE));
+        assert_eq!(
+            template.render("brett", &sample_book(), None),
+            "🎉\n brett finished The Left Hand of Darkness by Ursula K. Le Guin"
+        );
+    }
+
+    #[test]
+    fn every_placeholder_is_rendered() {
+        let template = assert_ok!(Template::parse(
+            "{user}|{title}|{author}|{rating}|{pages}|{review}"
+        ));
+        assert_eq!(
+            template.render("brett", &sample_book(), Some("loved it")),
+            "brett|The Left Hand of Darkness|Ursula K. Le Guin|⭐⭐⭐⭐⭐|304|loved it"
+        );
+    }
+
+    #[test]
+    fn review_is_blank_when_not_shared() {
+        let template = assert_ok!(Template::parse("{user} says: {review}"));
+        assert_eq!(
+            template.render("brett", &sample_book(), None),
+            "brett says: "
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        let template = assert_ok!(Template::parse("{{{title}}}"));
+        assert_eq!(
+            template.render("brett", &sample_book(), None),
+            "{The Left Hand of Darkness}"
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        let err = assert_err!(Template::parse("{user} finished {book}"));
+        assert!(err.to_string().contains("{book}"));
+    }
+
+    #[test]
+    fn unbalanced_braces_are_rejected() {
+        assert_err!(Template::parse("{user finished"));
+        assert_err!(Template::parse("{user{title}"));
+        assert_err!(Template::parse("user} finished"));
+    }
+
+    #[test]
+    fn empty_templates_are_rejected() {
+        assert_err!(Template::parse("   "));
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Context;
+use serenity::http::Http;
+use serenity::model::channel::{Channel, Message};
+use serenity::model::id::ChannelId;
+use sqlx::SqlitePool;
+
+use crate::model::{Announcement, Book, GuildSettings};
+
+/// Discord refuses thread names longer than this
+const MAX_THREAD_NAME_LENGTH: usize = 100;
+
+/// Discord only accepts these auto archive durations, in minutes
+pub const ARCHIVE_DURATIONS: [(&str, u16); 4] =
+    [("1h", 60), ("24h", 1440), ("3d", 4320), ("1w", 10080)];
+
+/// Find the discussion thread opened when someone else in the guild finished this book,
+/// as long as it hasn't been deleted since.
+#[tracing::instrument(name = "Looking up existing discussion thread", skip(http, pool))]
+pub async fn existing_thread(
+    http: &Http,
+    pool: &SqlitePool,
+    guild_id: i64,
+    book: &Book,
+) -> Option<ChannelId> {
+    let announcement = match Announcement::find_discussion(pool, guild_id, book.id()).await {
+        Ok(announcement) => announcement?,
+        Err(why) => {
+            tracing::error!("Unable to look up discussion thread because: {}", why);
+            return None;
+        }
+    };
+    let thread = ChannelId(announcement.thread_id? as u64);
+    match http.get_channel(thread.0).await {
+        Ok(Channel::Guild(_)) => Some(thread),
+        _ => None,
+    }
+}
+
+/// Open a public thread on the announcement, named after the book
+#[tracing::instrument(name = "Opening discussion thread", skip(http, message))]
+pub async fn open_thread(
+    http: &Http,
+    settings: &GuildSettings,
+    message: &Message,
+    book: &Book,
+) -> anyhow::Result<ChannelId> {
+    let name: String = book.title().chars().take(MAX_THREAD_NAME_LENGTH).collect();
+    let thread = message
+        .channel_id
+        .create_public_thread(http, message.id, |t| {
+            t.name(name)
+                .auto_archive_duration(archive_duration(settings.thread_archive_minutes))
+        })
+        .await
+        .with_context(|| format!("Unable to open a thread on message {}", message.id))?;
+
+    Ok(thread.id)
+}
+
+/// Let the existing discussion know someone else has finished the book
+#[tracing::instrument(name = "Posting to existing discussion thread", skip(http, message))]
+pub async fn join_thread(
+    http: &Http,
+    thread: ChannelId,
+    name: &str,
+    message: &Message,
+    guild_id: i64,
+) -> anyhow::Result<()> {
+    let link = format!(
+        "https://discord.com/channels/{}/{}/{}",
+        guild_id, message.channel_id, message.id
+    );
+    thread
+        .say(
+            http,
+            format!("📚 {} just finished this one too! {}", name, link),
+        )
+        .await
+        .with_context(|| format!("Unable to post in thread {}", thread))?;
+
+    Ok(())
+}
+
+/// Snap a stored duration to the nearest one Discord accepts
+fn archive_duration(minutes: i64) -> u16 {
+    ARCHIVE_DURATIONS
+        .iter()
+        .map(|(_, duration)| *duration)
+        .min_by_key(|duration| (i64::from(*duration) - minutes).abs())
+        .unwrap_or(1440)
+}
@@ -0,0 +1,173 @@
+use anyhow::{anyhow, Context};
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::http::Http;
+use serenity::model::channel::{Embed, Message};
+use serenity::model::id::ChannelId;
+use serenity::model::webhook::Webhook;
+use sqlx::SqlitePool;
+
+use crate::model::GuildSettings;
+
+const WEBHOOK_NAME: &str = "Bookcreep";
+
+/// Discord rejects webhook usernames longer than this
+const MAX_USERNAME_LENGTH: usize = 80;
+
+/// What an announcement looks like when it's posted under a member's name
+pub struct MemberPost<'a> {
+    pub username: &'a str,
+    pub avatar_url: &'a str,
+    pub content: &'a str,
+    pub embed: &'a CreateEmbed,
+    pub components: &'a CreateComponents,
+}
+
+/// Post an announcement through the guild's webhook so it shows up under the member's name and
+/// avatar. If the webhook was deleted out from under us a new one is created and the post retried.
+#[tracing::instrument(name = "Posting message through guild webhook", skip(http, pool, post))]
+pub async fn post_as_member(
+    http: &Http,
+    pool: &SqlitePool,
+    settings: &GuildSettings,
+    channel: ChannelId,
+    post: &MemberPost<'_>,
+) -> anyhow::Result<Message> {
+    let webhook = get_or_create_webhook(http, pool, settings, channel).await?;
+    match execute(http, &webhook, post).await {
+        Err(why) if is_not_found(&why) => {
+            tracing::info!(
+                "Webhook for guild ({}) has disappeared, creating a new one",
+                settings.guild_id
+            );
+            let webhook = create_webhook(http, pool, settings.guild_id, channel).await?;
+            execute(http, &webhook, post)
+                .await
+                .with_context(|| format!("Unable to execute webhook in channel {}", channel))
+        }
+        result => {
+            result.with_context(|| format!("Unable to execute webhook in channel {}", channel))
+        }
+    }
+}
+
+/// Fetch the webhook the bot manages for this guild, creating it in `channel` when it doesn't
+/// exist yet, was deleted, or belongs to a previous notification channel.
+#[tracing::instrument(name = "Retrieving guild webhook", skip(http, pool))]
+pub async fn get_or_create_webhook(
+    http: &Http,
+    pool: &SqlitePool,
+    settings: &GuildSettings,
+    channel: ChannelId,
+) -> anyhow::Result<Webhook> {
+    if let (Some(id), Some(token)) = (settings.webhook_id, &settings.webhook_token) {
+        match http.get_webhook_with_token(id as u64, token).await {
+            Ok(webhook) if webhook.channel_id == Some(channel) => return Ok(webhook),
+            Ok(webhook) => {
+                if let Err(why) = webhook.delete(http).await {
+                    tracing::warn!(
+                        "Unable to delete the old webhook for guild ({}) because: {}",
+                        settings.guild_id,
+                        why
+                    );
+                }
+            }
+            Err(why) if is_not_found(&why) => {
+                tracing::info!(
+                    "Webhook for guild ({}) was deleted, creating a new one",
+                    settings.guild_id
+                );
+            }
+            Err(why) => return Err(anyhow!(why)),
+        }
+    }
+
+    create_webhook(http, pool, settings.guild_id, channel).await
+}
+
+/// Delete the guild's webhook, if it still exists, and forget about it
+#[tracing::instrument(name = "Removing guild webhook", skip(http, pool))]
+pub async fn remove_webhook(
+    http: &Http,
+    pool: &SqlitePool,
+    settings: &GuildSettings,
+) -> anyhow::Result<()> {
+    if let (Some(id), Some(token)) = (settings.webhook_id, &settings.webhook_token) {
+        if let Err(why) = http.delete_webhook_with_token(id as u64, token).await {
+            if !is_not_found(&why) {
+                return Err(anyhow!(why));
+            }
+        }
+    }
+    GuildSettings::set_webhook(pool, settings.guild_id, None).await
+}
+
+/// The guild's webhook, as long as it's still the one with this id. Messages can only be edited
+/// through the webhook that posted them.
+#[tracing::instrument(name = "Retrieving webhook that posted a message", skip(http))]
+pub async fn managed_webhook(
+    http: &Http,
+    settings: &GuildSettings,
+    webhook_id: i64,
+) -> anyhow::Result<Webhook> {
+    match (settings.webhook_id, &settings.webhook_token) {
+        (Some(id), Some(token)) if id == webhook_id => http
+            .get_webhook_with_token(id as u64, token)
+            .await
+            .with_context(|| format!("Unable to retrieve webhook {}", id)),
+        _ => Err(anyhow!(
+            "Webhook {} has been replaced since it posted the message",
+            webhook_id
+        )),
+    }
+}
+
+async fn create_webhook(
+    http: &Http,
+    pool: &SqlitePool,
+    guild_id: i64,
+    channel: ChannelId,
+) -> anyhow::Result<Webhook> {
+    let webhook = channel
+        .create_webhook(http, WEBHOOK_NAME)
+        .await
+        .with_context(|| format!("Unable to create a webhook in channel {}", channel))?;
+    let token = webhook
+        .token
+        .as_deref()
+        .ok_or(anyhow!("Discord didn't return a token for the new webhook"))?;
+    GuildSettings::set_webhook(pool, guild_id, Some((webhook.id.0 as i64, token))).await?;
+
+    Ok(webhook)
+}
+
+async fn execute(
+    http: &Http,
+    webhook: &Webhook,
+    post: &MemberPost<'_>,
+) -> serenity::Result<Message> {
+    let username: String = post.username.chars().take(MAX_USERNAME_LENGTH).collect();
+    let embed = post.embed.clone();
+    let components = post.components.clone();
+    webhook
+        .execute(http, true, |w| {
+            w.username(username)
+                .avatar_url(post.avatar_url)
+                .content(post.content)
+                .embeds(vec![Embed::fake(|e| {
+                    *e = embed;
+                    e
+                })])
+                .set_components(components)
+        })
+        .await?
+        .ok_or(serenity::Error::Other(
+            "Discord didn't return the message posted by the webhook",
+        ))
+}
+
+pub fn is_not_found(why: &serenity::Error) -> bool {
+    match why {
+        serenity::Error::Http(inner) => inner.status_code().map(|code| code.as_u16()) == Some(404),
+        _ => false,
+    }
+}
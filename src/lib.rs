@@ -1,5 +1,7 @@
+pub mod chart;
 pub mod crawler;
 pub mod discord;
 pub mod model;
+pub mod scheduler;
 pub mod startup;
 pub mod telemetry;
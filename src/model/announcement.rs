@@ -0,0 +1,284 @@
+use chrono::offset::Utc;
+use serenity::model::channel::Message;
+use serenity::model::prelude::ChannelId;
+use sqlx::sqlite::{SqlitePool, SqliteQueryResult};
+
+use crate::model::{Book, User};
+
+/// A finished book the bot has posted to discord, and where it was posted
+#[derive(Debug, Clone)]
+pub struct Announcement {
+    pub id: i64,
+    pub user_id: i64,
+    pub guild_id: i64,
+    pub book_id: String,
+    pub channel_id: i64,
+    pub message_id: i64,
+    pub thread_id: Option<i64>,
+    pub announced_at: i64,
+    pub rating: i64,
+    pub webhook_id: Option<i64>,
+    pub retracted: bool,
+    pub title: String,
+    pub author: String,
+    pub url: String,
+}
+
+impl Announcement {
+    #[tracing::instrument(name = "Recording announcement", skip(pool, message))]
+    pub async fn record(
+        pool: &SqlitePool,
+        user: &User,
+        book: &Book,
+        message: &Message,
+        thread: Option<ChannelId>,
+    ) -> anyhow::Result<Self> {
+        let mut conn = pool.acquire().await?;
+        let book_id = book.id();
+        let channel_id = message.channel_id.0 as i64;
+        let message_id = message.id.0 as i64;
+        let thread_id = thread.map(|thread| thread.0 as i64);
+        let announced_at = Utc::now().timestamp();
+        let rating = book.rating() as i64;
+        let webhook_id = message.webhook_id.map(|webhook| webhook.0 as i64);
+        let title = book.title();
+        let author = book.author();
+        let url = book.url();
+
+        let result: SqliteQueryResult = sqlx::query!(
+            r#"
+            INSERT INTO announcements (user_id, guild_id, book_id, channel_id, message_id, thread_id, announced_at, rating, webhook_id, title, author, url)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            user.id,
+            user.discord_guild_id,
+            book_id,
+            channel_id,
+            message_id,
+            thread_id,
+            announced_at,
+            rating,
+            webhook_id,
+            title,
+            author,
+            url,
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(Self {
+            id: result.last_insert_rowid(),
+            user_id: user.id,
+            guild_id: user.discord_guild_id,
+            book_id: book_id.to_string(),
+            channel_id,
+            message_id,
+            thread_id,
+            announced_at,
+            rating,
+            webhook_id,
+            retracted: false,
+            title: title.to_string(),
+            author: author.to_string(),
+            url: url.to_string(),
+        })
+    }
+
+    /// The most recent announcement of this book in the guild that has a discussion thread
+    #[tracing::instrument(name = "Finding discussion thread for book", skip(pool))]
+    pub async fn find_discussion(
+        pool: &SqlitePool,
+        guild_id: i64,
+        book_id: &str,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut conn = pool.acquire().await?;
+        let result = sqlx::query_as!(
+            AnnouncementRow,
+            r#"
+            SELECT id as "id!", user_id as "user_id!", guild_id as "guild_id!", book_id as "book_id!",
+                channel_id as "channel_id!", message_id as "message_id!", thread_id, announced_at as "announced_at!",
+                rating as "rating!", webhook_id, retracted as "retracted!", title as "title!", author as "author!",
+                url as "url!"
+            FROM announcements
+            WHERE guild_id = ? AND book_id = ? AND thread_id IS NOT NULL
+            ORDER BY announced_at DESC
+            LIMIT 1
+            "#,
+            guild_id,
+            book_id
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(Self::from);
+
+        Ok(result)
+    }
+
+    /// The member's latest announcement of the book, for linking to it from elsewhere
+    #[tracing::instrument(name = "Finding latest announcement of book", skip(pool))]
+    pub async fn latest_for_book(
+        pool: &SqlitePool,
+        user_id: i64,
+        book_id: &str,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut conn = pool.acquire().await?;
+        let result = sqlx::query_as!(
+            AnnouncementRow,
+            r#"
+            SELECT id as "id!", user_id as "user_id!", guild_id as "guild_id!", book_id as "book_id!",
+                channel_id as "channel_id!", message_id as "message_id!", thread_id, announced_at as "announced_at!",
+                rating as "rating!", webhook_id, retracted as "retracted!", title as "title!", author as "author!",
+                url as "url!"
+            FROM announcements
+            WHERE user_id = ? AND book_id = ? AND retracted = FALSE
+            ORDER BY announced_at DESC
+            LIMIT 1
+            "#,
+            user_id,
+            book_id
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(Self::from);
+
+        Ok(result)
+    }
+
+    /// The announcement posted as this message, which is how button clicks find their way back
+    #[tracing::instrument(name = "Finding announcement by message", skip(pool))]
+    pub async fn find_by_message(
+        pool: &SqlitePool,
+        message_id: i64,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut conn = pool.acquire().await?;
+        let result = sqlx::query_as!(
+            AnnouncementRow,
+            r#"
+            SELECT id as "id!", user_id as "user_id!", guild_id as "guild_id!", book_id as "book_id!",
+                channel_id as "channel_id!", message_id as "message_id!", thread_id, announced_at as "announced_at!",
+                rating as "rating!", webhook_id, retracted as "retracted!", title as "title!", author as "author!",
+                url as "url!"
+            FROM announcements
+            WHERE message_id = ?
+            "#,
+            message_id
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(Self::from);
+
+        Ok(result)
+    }
+
+    /// Announcements of the user's books made since `since` that are still up in discord
+    #[tracing::instrument(name = "Retrieving recent announcements", skip(pool))]
+    pub async fn recent_for_user(
+        pool: &SqlitePool,
+        user_id: i64,
+        since: i64,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let result = sqlx::query_as!(
+            AnnouncementRow,
+            r#"
+            SELECT id as "id!", user_id as "user_id!", guild_id as "guild_id!", book_id as "book_id!",
+                channel_id as "channel_id!", message_id as "message_id!", thread_id, announced_at as "announced_at!",
+                rating as "rating!", webhook_id, retracted as "retracted!", title as "title!", author as "author!",
+                url as "url!"
+            FROM announcements
+            WHERE user_id = ? AND announced_at >= ? AND retracted = FALSE
+            ORDER BY announced_at DESC
+            "#,
+            user_id,
+            since
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(Self::from)
+        .collect();
+
+        Ok(result)
+    }
+
+    #[tracing::instrument(name = "Updating announced rating", skip(pool))]
+    pub async fn set_rating(&mut self, pool: &SqlitePool, rating: i64) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"UPDATE announcements SET rating = ? WHERE id = ?"#,
+            rating,
+            self.id
+        )
+        .execute(&mut conn)
+        .await?;
+
+        self.rating = rating;
+        Ok(())
+    }
+
+    /// Remember the announcement was taken back so it isn't synced again
+    #[tracing::instrument(name = "Retracting announcement", skip(pool))]
+    pub async fn retract(&mut self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"UPDATE announcements SET retracted = TRUE WHERE id = ?"#,
+            self.id
+        )
+        .execute(&mut conn)
+        .await?;
+
+        self.retracted = true;
+        Ok(())
+    }
+
+    /// Threads opened on a message share its id, any other thread was an earlier discussion
+    pub fn joined_discussion(&self) -> Option<i64> {
+        self.thread_id
+            .filter(|thread_id| *thread_id != self.message_id)
+    }
+
+    pub fn link(&self) -> String {
+        format!(
+            "https://discord.com/channels/{}/{}/{}",
+            self.guild_id, self.channel_id, self.message_id
+        )
+    }
+}
+
+struct AnnouncementRow {
+    id: i64,
+    user_id: i64,
+    guild_id: i64,
+    book_id: String,
+    channel_id: i64,
+    message_id: i64,
+    thread_id: Option<i64>,
+    announced_at: i64,
+    rating: i64,
+    webhook_id: Option<i64>,
+    retracted: bool,
+    title: String,
+    author: String,
+    url: String,
+}
+
+impl From<AnnouncementRow> for Announcement {
+    fn from(row: AnnouncementRow) -> Self {
+        Self {
+            id: row.id,
+            user_id: row.user_id,
+            guild_id: row.guild_id,
+            book_id: row.book_id,
+            channel_id: row.channel_id,
+            message_id: row.message_id,
+            thread_id: row.thread_id,
+            announced_at: row.announced_at,
+            rating: row.rating,
+            webhook_id: row.webhook_id,
+            retracted: row.retracted,
+            title: row.title,
+            author: row.author,
+            url: row.url,
+        }
+    }
+}
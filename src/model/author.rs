@@ -0,0 +1,225 @@
+use chrono::offset::Utc;
+use sqlx::sqlite::SqlitePool;
+use std::collections::HashSet;
+
+/// A Goodreads author somebody follows, checked now and then for new books
+#[derive(Debug, Clone, PartialEq)]
+pub struct Author {
+    pub author_id: i64,
+    pub name: String,
+    /// Never checked yet when 0
+    pub last_checked: i64,
+}
+
+/// One member following an author's new books by DM, within a guild
+pub struct AuthorFollow;
+
+impl Author {
+    /// Followed authors that haven't been checked since `checked_before`, longest waiting first
+    #[tracing::instrument(name = "Retrieving authors due a check", skip(pool))]
+    pub async fn due(pool: &SqlitePool, checked_before: i64) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let authors = sqlx::query!(
+            r#"
+            SELECT author_id, name, last_checked FROM authors
+            WHERE last_checked < ?
+                AND EXISTS (SELECT 1 FROM author_follows WHERE author_follows.author_id = authors.author_id)
+            ORDER BY last_checked
+            "#,
+            checked_before
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| Self {
+            author_id: row.author_id,
+            name: row.name,
+            last_checked: row.last_checked,
+        })
+        .collect();
+
+        Ok(authors)
+    }
+
+    /// Ids of every book of theirs seen so far
+    #[tracing::instrument(name = "Retrieving known author books", skip(pool))]
+    pub async fn known_books(&self, pool: &SqlitePool) -> anyhow::Result<HashSet<String>> {
+        let mut conn = pool.acquire().await?;
+        let books = sqlx::query!(
+            r#"SELECT book_id FROM author_books WHERE author_id = ?"#,
+            self.author_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| row.book_id)
+        .collect();
+
+        Ok(books)
+    }
+
+    /// Remember the books found on this check, so they're only announced once
+    #[tracing::instrument(name = "Recording author check", skip(pool))]
+    pub async fn checked(&self, pool: &SqlitePool, book_ids: &[String]) -> anyhow::Result<()> {
+        let mut tx = pool.begin().await?;
+        let now = Utc::now().timestamp();
+        for book_id in book_ids {
+            sqlx::query!(
+                r#"
+                INSERT INTO author_books (author_id, book_id, found_at)
+                VALUES (?, ?, ?)
+                ON CONFLICT (author_id, book_id) DO NOTHING
+                "#,
+                self.author_id,
+                book_id,
+                now
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+        sqlx::query!(
+            r#"UPDATE authors SET last_checked = ? WHERE author_id = ?"#,
+            now,
+            self.author_id
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Discord ids of everyone following them, once each however many guilds they follow them in
+    #[tracing::instrument(name = "Retrieving author followers", skip(pool))]
+    pub async fn followers(&self, pool: &SqlitePool) -> anyhow::Result<Vec<i64>> {
+        let mut conn = pool.acquire().await?;
+        let followers = sqlx::query!(
+            r#"SELECT DISTINCT discord_user_id FROM author_follows WHERE author_id = ?"#,
+            self.author_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| row.discord_user_id)
+        .collect();
+
+        Ok(followers)
+    }
+}
+
+impl AuthorFollow {
+    /// Returns false when the member was already following them
+    #[tracing::instrument(name = "Following author", skip(pool))]
+    pub async fn add(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        guild_id: i64,
+        author_id: i64,
+        name: &str,
+    ) -> anyhow::Result<bool> {
+        let mut tx = pool.begin().await?;
+        let followed_at = Utc::now().timestamp();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO authors (author_id, name)
+            VALUES (?, ?)
+            ON CONFLICT (author_id) DO UPDATE SET name = excluded.name
+            "#,
+            author_id,
+            name
+        )
+        .execute(&mut tx)
+        .await?;
+        let added = sqlx::query!(
+            r#"
+            INSERT INTO author_follows (discord_user_id, guild_id, author_id, followed_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (discord_user_id, guild_id, author_id) DO NOTHING
+            "#,
+            discord_user_id,
+            guild_id,
+            author_id,
+            followed_at
+        )
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+        tx.commit().await?;
+
+        Ok(added > 0)
+    }
+
+    /// Stop following the author with that name, ignoring case. Returns the author, or `None` when
+    /// the member wasn't following anyone called that. Authors nobody follows any more are
+    /// forgotten, so following them again later doesn't announce everything they wrote meanwhile.
+    #[tracing::instrument(name = "Unfollowing author", skip(pool))]
+    pub async fn remove(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        guild_id: i64,
+        name: &str,
+    ) -> anyhow::Result<Option<Author>> {
+        let author = match Self::following(pool, discord_user_id, guild_id)
+            .await?
+            .into_iter()
+            .find(|author| author.name.to_lowercase() == name.trim().to_lowercase())
+        {
+            Some(author) => author,
+            None => return Ok(None),
+        };
+
+        let mut tx = pool.begin().await?;
+        sqlx::query!(
+            r#"DELETE FROM author_follows WHERE discord_user_id = ? AND guild_id = ? AND author_id = ?"#,
+            discord_user_id,
+            guild_id,
+            author.author_id
+        )
+        .execute(&mut tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM authors
+            WHERE author_id = ?
+                AND NOT EXISTS (SELECT 1 FROM author_follows WHERE author_follows.author_id = authors.author_id)
+            "#,
+            author.author_id
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(author))
+    }
+
+    /// The authors the member follows in the guild, in the order they followed them
+    #[tracing::instrument(name = "Retrieving followed authors", skip(pool))]
+    pub async fn following(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<Author>> {
+        let mut conn = pool.acquire().await?;
+        let authors = sqlx::query!(
+            r#"
+            SELECT authors.author_id, authors.name, authors.last_checked FROM author_follows
+            JOIN authors ON authors.author_id = author_follows.author_id
+            WHERE author_follows.discord_user_id = ? AND author_follows.guild_id = ?
+            ORDER BY author_follows.followed_at
+            "#,
+            discord_user_id,
+            guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| Author {
+            author_id: row.author_id,
+            name: row.name,
+            last_checked: row.last_checked,
+        })
+        .collect();
+
+        Ok(authors)
+    }
+}
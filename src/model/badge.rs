@@ -0,0 +1,263 @@
+use chrono::offset::Utc;
+use sqlx::sqlite::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::model::{HistoryEntry, User, WeeklyStreak};
+
+/// A reading milestone, earned once per member per guild
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Badge {
+    FirstBook,
+    TenBooks,
+    FiftyBooks,
+    HundredBooks,
+    Doorstopper,
+    Devotee,
+    BookAWeek,
+    Streak,
+}
+
+/// When a member earned a badge, and with which book
+#[derive(Debug, Clone, PartialEq)]
+pub struct EarnedBadge {
+    pub badge: Badge,
+    pub book_id: String,
+    pub earned_at: i64,
+}
+
+const DOORSTOPPER_PAGES: i64 = 1000;
+const DEVOTEE_BOOKS: usize = 5;
+const BOOK_A_WEEK_WEEKS: usize = 4;
+const STREAK_WEEKS: usize = 12;
+
+impl Badge {
+    pub const ALL: [Badge; 8] = [
+        Badge::FirstBook,
+        Badge::TenBooks,
+        Badge::FiftyBooks,
+        Badge::HundredBooks,
+        Badge::Doorstopper,
+        Badge::Devotee,
+        Badge::BookAWeek,
+        Badge::Streak,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|badge| badge.as_str() == value)
+            .copied()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Badge::FirstBook => "first_book",
+            Badge::TenBooks => "ten_books",
+            Badge::FiftyBooks => "fifty_books",
+            Badge::HundredBooks => "hundred_books",
+            Badge::Doorstopper => "doorstopper",
+            Badge::Devotee => "devotee",
+            Badge::BookAWeek => "book_a_week",
+            Badge::Streak => "streak",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Badge::FirstBook => "finished a first book",
+            Badge::TenBooks => "finished 10 books",
+            Badge::FiftyBooks => "finished 50 books",
+            Badge::HundredBooks => "finished 100 books",
+            Badge::Doorstopper => "finished a book of 1000 pages or more",
+            Badge::Devotee => "finished five books by the same author",
+            Badge::BookAWeek => "finished a book every week for four weeks",
+            Badge::Streak => "finished a book every week for twelve weeks",
+        }
+    }
+
+    /// Every badge the member's reading so far has earned. Rereads count towards totals and
+    /// streaks, but not twice towards the same author.
+    pub fn earned(entries: &[HistoryEntry]) -> Vec<Self> {
+        let mut by_author: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for entry in entries {
+            by_author
+                .entry(entry.author.as_str())
+                .or_default()
+                .insert(entry.book_id.as_str());
+        }
+        let weeks = WeeklyStreak::longest(entries.iter().map(|entry| entry.read_on));
+
+        Self::ALL
+            .iter()
+            .filter(|badge| match badge {
+                Badge::FirstBook => !entries.is_empty(),
+                Badge::TenBooks => entries.len() >= 10,
+                Badge::FiftyBooks => entries.len() >= 50,
+                Badge::HundredBooks => entries.len() >= 100,
+                Badge::Doorstopper => entries
+                    .iter()
+                    .filter_map(|entry| entry.pages)
+                    .any(|pages| pages >= DOORSTOPPER_PAGES),
+                Badge::Devotee => by_author.values().any(|books| books.len() >= DEVOTEE_BOOKS),
+                Badge::BookAWeek => weeks >= BOOK_A_WEEK_WEEKS,
+                Badge::Streak => weeks >= STREAK_WEEKS,
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Record every badge the member's reading has earned that they didn't have yet, crediting
+    /// `book_id`. Returns the new ones.
+    #[tracing::instrument(name = "Granting badges", skip(pool))]
+    pub async fn grant(pool: &SqlitePool, user: &User, book_id: &str) -> anyhow::Result<Vec<Self>> {
+        let entries =
+            HistoryEntry::for_member(pool, user.discord_user_id, user.discord_guild_id, true)
+                .await?;
+        let mut conn = pool.acquire().await?;
+        let earned_at = Utc::now().timestamp();
+
+        let mut granted = Vec::new();
+        for badge in Self::earned(&entries) {
+            let name = badge.as_str();
+            let inserted = sqlx::query!(
+                r#"
+                INSERT INTO badges (user_id, badge, book_id, earned_at)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT (user_id, badge) DO NOTHING
+                "#,
+                user.id,
+                name,
+                book_id,
+                earned_at
+            )
+            .execute(&mut conn)
+            .await?
+            .rows_affected();
+            if inserted > 0 {
+                granted.push(badge);
+            }
+        }
+
+        Ok(granted)
+    }
+}
+
+impl fmt::Display for Badge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Badge::FirstBook => "📗 First Chapter",
+            Badge::TenBooks => "📚 Bookworm",
+            Badge::FiftyBooks => "🏛️ Librarian",
+            Badge::HundredBooks => "💯 Centurion",
+            Badge::Doorstopper => "🧱 Doorstopper",
+            Badge::Devotee => "✍️ Devotee",
+            Badge::BookAWeek => "🗓️ Book a Week",
+            Badge::Streak => "🔥 On a Roll",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl EarnedBadge {
+    /// The member's badges, in the order they earned them
+    #[tracing::instrument(name = "Retrieving badges", skip(pool))]
+    pub async fn for_user(pool: &SqlitePool, user_id: i64) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let badges = sqlx::query!(
+            r#"
+            SELECT badge, book_id, earned_at
+            FROM badges
+            WHERE user_id = ?
+            ORDER BY earned_at, rowid
+            "#,
+            user_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            Badge::parse(&row.badge).map(|badge| Self {
+                badge,
+                book_id: row.book_id,
+                earned_at: row.earned_at,
+            })
+        })
+        .collect();
+
+        Ok(badges)
+    }
+
+    /// The badges the member earned by finishing `book_id`
+    #[tracing::instrument(name = "Retrieving badges earned with book", skip(pool))]
+    pub async fn for_book(
+        pool: &SqlitePool,
+        user_id: i64,
+        book_id: &str,
+    ) -> anyhow::Result<Vec<Badge>> {
+        Ok(Self::for_user(pool, user_id)
+            .await?
+            .into_iter()
+            .filter(|earned| earned.book_id == book_id)
+            .map(|earned| earned.badge)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Badge, HistoryEntry};
+    use chrono::{Duration, NaiveDate};
+
+    #[test]
+    fn book_counts_earn_their_badges() {
+        let start = NaiveDate::from_ymd(2022, 1, 3);
+        let entries: Vec<HistoryEntry> = (0..10)
+            .map(|day| {
+                let book_id = day.to_string();
+                HistoryEntry::test_read(1, &book_id)
+                    .with_author(&book_id)
+                    .with_pages(None)
+                    .with_read_on(start + Duration::days(day))
+            })
+            .collect();
+
+        assert_eq!(Badge::earned(&entries[..1]), vec![Badge::FirstBook]);
+        assert_eq!(
+            Badge::earned(&entries),
+            vec![Badge::FirstBook, Badge::TenBooks]
+        );
+        assert!(Badge::earned(&[]).is_empty());
+    }
+
+    #[test]
+    fn devotees_need_five_different_books_by_one_author() {
+        let read_on = NaiveDate::from_ymd(2022, 1, 3);
+        let mut entries: Vec<HistoryEntry> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|book_id| {
+                HistoryEntry::test_read(1, book_id)
+                    .with_author("Ursula K. Le Guin")
+                    .with_pages(None)
+                    .with_read_on(read_on)
+            })
+            .collect();
+        entries.push(
+            HistoryEntry::test_read(1, "a")
+                .with_author("Ursula K. Le Guin")
+                .with_pages(None)
+                .with_read_on(read_on),
+        );
+
+        assert!(!Badge::earned(&entries).contains(&Badge::Devotee));
+        entries.push(
+            HistoryEntry::test_read(1, "e")
+                .with_author("Ursula K. Le Guin")
+                .with_pages(Some(1040))
+                .with_read_on(read_on),
+        );
+        let earned = Badge::earned(&entries);
+        assert!(earned.contains(&Badge::Devotee));
+        assert!(earned.contains(&Badge::Doorstopper));
+    }
+}
@@ -1,16 +1,26 @@
 use std::fmt;
 
-use chrono::NaiveDate;
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use chrono_tz::Tz;
+
+use crate::model::reading_days;
+
+pub(crate) const DATE_FORMAT: &str = "%a, %d %h %Y %H:%M:%S %z";
 
 #[derive(Debug)]
 pub struct Book {
     title: String,
     url: String,
-    completed: NaiveDate,
+    completed: DateTime<FixedOffset>,
     id: String,
     rating: usize,
     author: String,
     image_url: String,
+    review: String,
+    shelves: Vec<String>,
+    pages: Option<u32>,
+    read_at: Option<NaiveDate>,
+    added_on: Option<NaiveDate>,
 }
 
 impl fmt::Display for Book {
@@ -29,7 +39,7 @@ impl Book {
         author: &str,
         image_url: &str,
     ) -> Option<Self> {
-        let completed_date = NaiveDate::parse_from_str(&completed, "%a, %d %h %Y %H:%M:%S %z");
+        let completed_date = DateTime::parse_from_str(completed, DATE_FORMAT);
         match completed_date {
             Ok(date) => Some(Book {
                 title: title.to_string(),
@@ -39,6 +49,11 @@ impl Book {
                 rating,
                 author: author.to_string(),
                 image_url: image_url.to_string(),
+                review: String::new(),
+                shelves: Vec::new(),
+                pages: None,
+                read_at: None,
+                added_on: None,
             }),
             Err(_) => None,
         }
@@ -62,4 +77,79 @@ impl Book {
     pub fn image(&self) -> &String {
         &self.image_url
     }
+    pub fn review(&self) -> &String {
+        &self.review
+    }
+    pub fn shelves(&self) -> &Vec<String> {
+        &self.shelves
+    }
+    pub fn pages(&self) -> Option<u32> {
+        self.pages
+    }
+    pub fn completed(&self) -> DateTime<FixedOffset> {
+        self.completed
+    }
+    pub fn read_at(&self) -> Option<NaiveDate> {
+        self.read_at
+    }
+    /// The date the user says they finished the book, falling back to the day it was shelved in
+    /// the guild's time zone
+    pub fn read_on(&self, time_zone: Tz) -> NaiveDate {
+        self.read_at.unwrap_or_else(|| {
+            self.completed
+                .with_timezone(&time_zone)
+                .naive_local()
+                .date()
+        })
+    }
+
+    /// The day the book went on the user's shelves, if the feed said
+    pub fn added_on(&self) -> Option<NaiveDate> {
+        self.added_on
+    }
+    /// How many days the user took to read the book, if it can be told
+    pub fn reading_days(&self, time_zone: Tz) -> Option<i64> {
+        reading_days(self.added_on, self.read_on(time_zone))
+    }
+
+    pub fn with_review(mut self, review: &str) -> Self {
+        self.review = review.trim().to_string();
+        self
+    }
+
+    /// Goodreads lists a user's shelves for a book as a comma separated string
+    pub fn with_shelves(mut self, shelves: &str) -> Self {
+        self.shelves = shelves
+            .split(',')
+            .map(|shelf| shelf.trim().to_lowercase())
+            .filter(|shelf| !shelf.is_empty())
+            .collect();
+        self
+    }
+
+    pub fn with_pages(mut self, pages: Option<u32>) -> Self {
+        self.pages = pages;
+        self
+    }
+
+    /// Goodreads leaves the read date blank when the user didn't fill it in
+    pub fn with_read_at(mut self, read_at: &str) -> Self {
+        self.read_at = NaiveDate::parse_from_str(read_at.trim(), DATE_FORMAT).ok();
+        self
+    }
+
+    pub fn with_read_on(mut self, read_on: Option<NaiveDate>) -> Self {
+        self.read_at = read_on;
+        self
+    }
+
+    pub fn with_date_added(mut self, date_added: &str) -> Self {
+        self.added_on = NaiveDate::parse_from_str(date_added.trim(), DATE_FORMAT).ok();
+        self
+    }
+
+    pub fn with_added_on(mut self, added_on: Option<NaiveDate>) -> Self {
+        self.added_on = added_on;
+        self
+    }
 }
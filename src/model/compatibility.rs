@@ -0,0 +1,243 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::model::HistoryEntry;
+
+/// Fewer books in common than this and a pair is too much of a coincidence to call twins
+pub const MIN_TWIN_BOOKS: usize = 3;
+
+/// A book two members both read, with what each of them rated it. Unrated is 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedBook {
+    pub entry: HistoryEntry,
+    pub first_rating: i64,
+    pub second_rating: i64,
+}
+
+/// How alike two members' reading is
+#[derive(Debug, Clone, PartialEq)]
+pub struct Compatibility {
+    pub first: i64,
+    pub second: i64,
+    /// Best loved first
+    pub shared: Vec<SharedBook>,
+    /// How many of the books both rated the agreement is based on
+    pub rated: usize,
+    /// From 0 when every rating is four stars apart to 1 when every rating matches. None when
+    /// there's no book they both rated.
+    pub agreement: Option<f64>,
+    /// Out of 100, from how much of their reading overlaps and how well their ratings agree
+    pub score: i64,
+}
+
+impl Compatibility {
+    /// Compare the members with Discord ids `first` and `second`, ignoring anyone else's entries
+    pub fn between(first: i64, second: i64, entries: &[HistoryEntry]) -> Self {
+        let shelves = shelves(entries);
+        let empty = HashMap::new();
+        compare(
+            first,
+            shelves.get(&first).unwrap_or(&empty),
+            second,
+            shelves.get(&second).unwrap_or(&empty),
+        )
+    }
+
+    /// How everyone with at least one book in common with `member` compares to them, with
+    /// `member` always first
+    pub fn with_member(member: i64, entries: &[HistoryEntry]) -> Vec<Self> {
+        let shelves = shelves(entries);
+        let shelf = match shelves.get(&member) {
+            Some(shelf) => shelf,
+            None => return Vec::new(),
+        };
+        shelves
+            .iter()
+            .filter(|(other, _)| **other != member)
+            .map(|(other, other_shelf)| compare(member, shelf, *other, other_shelf))
+            .filter(|pair| !pair.shared.is_empty())
+            .collect()
+    }
+
+    /// Every pair of members with at least `MIN_TWIN_BOOKS` in common, most compatible first
+    pub fn twins(entries: &[HistoryEntry]) -> Vec<Self> {
+        let shelves: Vec<(i64, Shelf)> = shelves(entries).into_iter().collect();
+        let mut pairs = Vec::new();
+        for (index, (first, first_shelf)) in shelves.iter().enumerate() {
+            for (second, second_shelf) in &shelves[index + 1..] {
+                let pair = compare(*first, first_shelf, *second, second_shelf);
+                if pair.shared.len() >= MIN_TWIN_BOOKS {
+                    pairs.push(pair);
+                }
+            }
+        }
+        pairs.sort_by_key(|pair| (Reverse(pair.score), Reverse(pair.shared.len())));
+        pairs
+    }
+}
+
+/// Each book a member read once, with the rating from their latest rated read
+type Shelf<'a> = HashMap<&'a str, (&'a HistoryEntry, i64)>;
+
+/// Members' shelves by Discord id, in id order so pairs come out the same every time
+fn shelves(entries: &[HistoryEntry]) -> BTreeMap<i64, Shelf<'_>> {
+    let mut shelves: BTreeMap<i64, Shelf<'_>> = BTreeMap::new();
+    for entry in entries {
+        let (_, rating) = shelves
+            .entry(entry.discord_user_id)
+            .or_default()
+            .entry(entry.book_id.as_str())
+            .or_insert((entry, 0));
+        if entry.rating > 0 {
+            *rating = entry.rating;
+        }
+    }
+    shelves
+}
+
+fn compare(
+    first: i64,
+    first_shelf: &Shelf<'_>,
+    second: i64,
+    second_shelf: &Shelf<'_>,
+) -> Compatibility {
+    let mut shared: Vec<SharedBook> = first_shelf
+        .iter()
+        .filter_map(|(book_id, (entry, first_rating))| {
+            second_shelf
+                .get(book_id)
+                .map(|(_, second_rating)| SharedBook {
+                    entry: (*entry).clone(),
+                    first_rating: *first_rating,
+                    second_rating: *second_rating,
+                })
+        })
+        .collect();
+    shared.sort_by(|a, b| {
+        (b.first_rating + b.second_rating)
+            .cmp(&(a.first_rating + a.second_rating))
+            .then_with(|| a.entry.title.cmp(&b.entry.title))
+    });
+
+    let differences: Vec<i64> = shared
+        .iter()
+        .filter(|book| book.first_rating > 0 && book.second_rating > 0)
+        .map(|book| (book.first_rating - book.second_rating).abs())
+        .collect();
+    let agreement = if differences.is_empty() {
+        None
+    } else {
+        let average = differences.iter().sum::<i64>() as f64 / differences.len() as f64;
+        Some(1.0 - average / 4.0)
+    };
+
+    let score = if shared.is_empty() {
+        0
+    } else {
+        // Even close friends only share a small part of what they read, so the overlap is
+        // square rooted to keep it from swamping the ratings. Ratings nobody gave count as
+        // neither agreeing nor disagreeing.
+        let union = first_shelf.len() + second_shelf.len() - shared.len();
+        let overlap = (shared.len() as f64 / union as f64).sqrt();
+        ((overlap + agreement.unwrap_or(0.5)) * 50.0).round() as i64
+    };
+
+    Compatibility {
+        first,
+        second,
+        rated: differences.len(),
+        shared,
+        agreement,
+        score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Compatibility, HistoryEntry, MIN_TWIN_BOOKS};
+    use claim::assert_none;
+
+    #[test]
+    fn identical_shelves_are_fully_compatible() {
+        let entries = [
+            HistoryEntry::test_read(1, "a").with_rating(5),
+            HistoryEntry::test_read(1, "b").with_rating(3),
+            HistoryEntry::test_read(2, "a").with_rating(5),
+            HistoryEntry::test_read(2, "b").with_rating(3),
+        ];
+        let compatibility = Compatibility::between(1, 2, &entries);
+
+        assert_eq!(compatibility.shared.len(), 2);
+        assert_eq!(compatibility.agreement, Some(1.0));
+        assert_eq!(compatibility.score, 100);
+    }
+
+    #[test]
+    fn ratings_agreement_only_counts_books_both_rated() {
+        let entries = [
+            HistoryEntry::test_read(1, "a").with_rating(5),
+            HistoryEntry::test_read(1, "b").with_rating(0),
+            HistoryEntry::test_read(1, "c").with_rating(4),
+            HistoryEntry::test_read(2, "a").with_rating(1),
+            HistoryEntry::test_read(2, "b").with_rating(4),
+            HistoryEntry::test_read(2, "d").with_rating(2),
+        ];
+        let compatibility = Compatibility::between(1, 2, &entries);
+
+        assert_eq!(compatibility.shared.len(), 2);
+        assert_eq!(compatibility.rated, 1);
+        assert_eq!(compatibility.agreement, Some(0.0));
+        // Two of four books overlap, so half the score comes from sqrt(0.5)
+        assert_eq!(compatibility.score, 35);
+    }
+
+    #[test]
+    fn rereads_count_once_with_their_latest_rating() {
+        let entries = [
+            HistoryEntry::test_read(1, "a").with_rating(2),
+            HistoryEntry::test_read(1, "a").with_rating(5),
+            HistoryEntry::test_read(1, "a").with_rating(0),
+            HistoryEntry::test_read(2, "a").with_rating(5),
+        ];
+        let compatibility = Compatibility::between(1, 2, &entries);
+
+        assert_eq!(compatibility.shared.len(), 1);
+        assert_eq!(compatibility.shared[0].first_rating, 5);
+        assert_eq!(compatibility.score, 100);
+    }
+
+    #[test]
+    fn members_with_nothing_in_common_score_nothing() {
+        let compatibility = Compatibility::between(
+            1,
+            2,
+            &[
+                HistoryEntry::test_read(1, "a").with_rating(5),
+                HistoryEntry::test_read(2, "b").with_rating(5),
+            ],
+        );
+
+        assert!(compatibility.shared.is_empty());
+        assert_none!(compatibility.agreement);
+        assert_eq!(compatibility.score, 0);
+    }
+
+    #[test]
+    fn twins_need_enough_books_in_common() {
+        let mut entries = Vec::new();
+        for book_id in ["a", "b", "c"] {
+            entries.push(HistoryEntry::test_read(1, book_id).with_rating(4));
+            entries.push(HistoryEntry::test_read(2, book_id).with_rating(4));
+            entries.push(HistoryEntry::test_read(3, book_id).with_rating(2));
+        }
+        // A perfect match on one book is a coincidence, not a twin
+        entries.push(HistoryEntry::test_read(4, "a").with_rating(4));
+        let twins = Compatibility::twins(&entries);
+
+        assert_eq!(twins.len(), 3);
+        assert_eq!((twins[0].first, twins[0].second), (1, 2));
+        assert!(twins
+            .iter()
+            .all(|pair| pair.shared.len() >= MIN_TWIN_BOOKS && pair.second != 4));
+    }
+}
@@ -0,0 +1,74 @@
+use chrono::offset::Utc;
+use sqlx::sqlite::SqlitePool;
+
+use crate::model::Book;
+
+/// The books on a member's currently-reading shelf, as of their last crawl
+pub struct CurrentlyReading;
+
+impl CurrentlyReading {
+    /// Swap what was on the shelf for `books`
+    #[tracing::instrument(name = "Replacing currently reading shelf", skip(pool, books))]
+    pub async fn replace(pool: &SqlitePool, user_id: i64, books: &[Book]) -> anyhow::Result<()> {
+        let mut tx = pool.begin().await?;
+        let seen_at = Utc::now().timestamp();
+
+        sqlx::query!(
+            r#"DELETE FROM currently_reading WHERE user_id = ?"#,
+            user_id
+        )
+        .execute(&mut tx)
+        .await?;
+        for book in books {
+            let book_id = book.id();
+            let title = book.title();
+            let author = book.author();
+            let url = book.url();
+            sqlx::query!(
+                r#"
+                INSERT INTO currently_reading (user_id, book_id, title, author, url, seen_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                ON CONFLICT (user_id, book_id) DO NOTHING
+                "#,
+                user_id,
+                book_id,
+                title,
+                author,
+                url,
+                seen_at
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Goodreads ids of the books the member is reading in the guild
+    #[tracing::instrument(name = "Retrieving currently reading shelf", skip(pool))]
+    pub async fn book_ids(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut conn = pool.acquire().await?;
+        let book_ids = sqlx::query!(
+            r#"
+            SELECT currently_reading.book_id
+            FROM currently_reading
+            JOIN users ON users.id = currently_reading.user_id
+            WHERE users.discord_user_id = ? AND users.discord_guild_id = ?
+            "#,
+            discord_user_id,
+            guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| row.book_id)
+        .collect();
+
+        Ok(book_ids)
+    }
+}
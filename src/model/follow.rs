@@ -0,0 +1,107 @@
+use chrono::offset::Utc;
+use sqlx::sqlite::SqlitePool;
+
+/// One member following another's reading by DM, within a guild
+pub struct Follow;
+
+impl Follow {
+    /// Returns false when the member was already following them
+    #[tracing::instrument(name = "Following member", skip(pool))]
+    pub async fn add(
+        pool: &SqlitePool,
+        follower_id: i64,
+        followee_id: i64,
+        guild_id: i64,
+    ) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+        let followed_at = Utc::now().timestamp();
+
+        let added = sqlx::query!(
+            r#"
+            INSERT INTO follows (follower_id, followee_id, guild_id, followed_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (follower_id, followee_id, guild_id) DO NOTHING
+            "#,
+            follower_id,
+            followee_id,
+            guild_id,
+            followed_at
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(added > 0)
+    }
+
+    /// Returns false when the member wasn't following them
+    #[tracing::instrument(name = "Unfollowing member", skip(pool))]
+    pub async fn remove(
+        pool: &SqlitePool,
+        follower_id: i64,
+        followee_id: i64,
+        guild_id: i64,
+    ) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+
+        let removed = sqlx::query!(
+            r#"DELETE FROM follows WHERE follower_id = ? AND followee_id = ? AND guild_id = ?"#,
+            follower_id,
+            followee_id,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(removed > 0)
+    }
+
+    /// Discord ids of everyone following the member
+    #[tracing::instrument(name = "Retrieving followers", skip(pool))]
+    pub async fn followers_of(
+        pool: &SqlitePool,
+        followee_id: i64,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<i64>> {
+        let mut conn = pool.acquire().await?;
+        let followers = sqlx::query!(
+            r#"SELECT follower_id FROM follows WHERE followee_id = ? AND guild_id = ?"#,
+            followee_id,
+            guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| row.follower_id)
+        .collect();
+
+        Ok(followers)
+    }
+
+    /// Discord ids of everyone the member follows
+    #[tracing::instrument(name = "Retrieving followed members", skip(pool))]
+    pub async fn following(
+        pool: &SqlitePool,
+        follower_id: i64,
+        guild_id: i64,
+    ) -> anyhow::Result<Vec<i64>> {
+        let mut conn = pool.acquire().await?;
+        let following = sqlx::query!(
+            r#"
+            SELECT followee_id FROM follows
+            WHERE follower_id = ? AND guild_id = ?
+            ORDER BY followed_at
+            "#,
+            follower_id,
+            guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| row.followee_id)
+        .collect();
+
+        Ok(following)
+    }
+}
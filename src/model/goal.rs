@@ -0,0 +1,310 @@
+use chrono::offset::Utc;
+use sqlx::sqlite::SqlitePool;
+use std::fmt;
+
+/// How far a member has got through their goal, announced once per goal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Milestone {
+    Halfway,
+    Reached,
+}
+
+impl Milestone {
+    /// The furthest milestone `books` has got to, if any
+    pub fn reached(target: i64, books: i64) -> Option<Self> {
+        if target <= 0 {
+            None
+        } else if books >= target {
+            Some(Milestone::Reached)
+        } else if books * 2 >= target {
+            Some(Milestone::Halfway)
+        } else {
+            None
+        }
+    }
+
+    pub fn percent(&self) -> i64 {
+        match self {
+            Milestone::Halfway => 50,
+            Milestone::Reached => 100,
+        }
+    }
+
+    pub fn from_percent(percent: i64) -> Option<Self> {
+        match percent {
+            50 => Some(Milestone::Halfway),
+            100 => Some(Milestone::Reached),
+            _ => None,
+        }
+    }
+}
+
+/// How many books a member wants to finish in a year
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadingGoal {
+    pub user_id: i64,
+    pub year: i32,
+    pub target: i64,
+}
+
+impl ReadingGoal {
+    #[tracing::instrument(name = "Retrieving reading goal", skip(pool))]
+    pub async fn get(pool: &SqlitePool, user_id: i64, year: i32) -> anyhow::Result<Option<Self>> {
+        let mut conn = pool.acquire().await?;
+        let goal = sqlx::query!(
+            r#"SELECT target FROM reading_goals WHERE user_id = ? AND year = ?"#,
+            user_id,
+            year
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(|row| Self {
+            user_id,
+            year,
+            target: row.target,
+        });
+
+        Ok(goal)
+    }
+
+    /// Every goal set for `year` by members of the guild
+    #[tracing::instrument(name = "Retrieving guild reading goals", skip(pool))]
+    pub async fn for_guild(
+        pool: &SqlitePool,
+        guild_id: i64,
+        year: i32,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let goals = sqlx::query!(
+            r#"
+            SELECT reading_goals.user_id, reading_goals.target
+            FROM reading_goals
+            JOIN users ON users.id = reading_goals.user_id
+            WHERE users.discord_guild_id = ? AND reading_goals.year = ?
+            "#,
+            guild_id,
+            year
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| Self {
+            user_id: row.user_id,
+            year,
+            target: row.target,
+        })
+        .collect();
+
+        Ok(goals)
+    }
+
+    /// Replace the member's goal for the year. Milestones start over, since they were for the old
+    /// target.
+    #[tracing::instrument(name = "Setting reading goal", skip(pool))]
+    pub async fn set(
+        pool: &SqlitePool,
+        user_id: i64,
+        year: i32,
+        target: i64,
+    ) -> anyhow::Result<Self> {
+        let mut tx = pool.begin().await?;
+        let set_at = Utc::now().timestamp();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO reading_goals (user_id, year, target, set_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (user_id, year) DO UPDATE SET target = excluded.target, set_at = excluded.set_at
+            "#,
+            user_id,
+            year,
+            target,
+            set_at
+        )
+        .execute(&mut tx)
+        .await?;
+        sqlx::query!(
+            r#"DELETE FROM goal_milestones WHERE user_id = ? AND year = ?"#,
+            user_id,
+            year
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Self {
+            user_id,
+            year,
+            target,
+        })
+    }
+
+    #[tracing::instrument(name = "Clearing reading goal", skip(pool))]
+    pub async fn clear(pool: &SqlitePool, user_id: i64, year: i32) -> anyhow::Result<()> {
+        let mut tx = pool.begin().await?;
+        sqlx::query!(
+            r#"DELETE FROM reading_goals WHERE user_id = ? AND year = ?"#,
+            user_id,
+            year
+        )
+        .execute(&mut tx)
+        .await?;
+        sqlx::query!(
+            r#"DELETE FROM goal_milestones WHERE user_id = ? AND year = ?"#,
+            user_id,
+            year
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Remember that the milestone was reached, and whether it's been announced or is waiting for
+    /// quiet hours to end. Returns false when it, or one further along, was already reached, so a
+    /// removed book doesn't bring back the halfway message.
+    #[tracing::instrument(name = "Recording goal milestone", skip(pool))]
+    pub async fn record_milestone(
+        &self,
+        pool: &SqlitePool,
+        milestone: Milestone,
+        announced: bool,
+    ) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+        let percent = milestone.percent();
+        let reached_at = Utc::now().timestamp();
+
+        let recorded = sqlx::query!(
+            r#"
+            INSERT INTO goal_milestones (user_id, year, percent, reached_at, announced)
+            SELECT ?, ?, ?, ?, ?
+            WHERE NOT EXISTS (
+                SELECT 1 FROM goal_milestones WHERE user_id = ? AND year = ? AND percent >= ?
+            )
+            "#,
+            self.user_id,
+            self.year,
+            percent,
+            reached_at,
+            announced,
+            self.user_id,
+            self.year,
+            percent
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(recorded > 0)
+    }
+}
+
+/// A milestone reached during the guild's quiet hours, waiting to be announced
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldMilestone {
+    pub discord_user_id: i64,
+    pub guild_id: i64,
+    pub goal: ReadingGoal,
+    pub milestone: Milestone,
+}
+
+impl HeldMilestone {
+    /// The furthest unannounced milestone of each goal, since there's no point cheering for
+    /// halfway alongside the goal itself
+    #[tracing::instrument(name = "Retrieving held goal milestones", skip(pool))]
+    pub async fn all(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let held = sqlx::query!(
+            r#"
+            SELECT goal_milestones.user_id, goal_milestones.year, goal_milestones.percent,
+                reading_goals.target, users.discord_user_id, users.discord_guild_id
+            FROM goal_milestones
+            JOIN reading_goals ON reading_goals.user_id = goal_milestones.user_id
+                AND reading_goals.year = goal_milestones.year
+            JOIN users ON users.id = goal_milestones.user_id
+            WHERE goal_milestones.announced = FALSE AND NOT EXISTS (
+                SELECT 1 FROM goal_milestones further
+                WHERE further.user_id = goal_milestones.user_id AND further.year = goal_milestones.year
+                    AND further.percent > goal_milestones.percent
+            )
+            "#
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            Some(Self {
+                discord_user_id: row.discord_user_id,
+                guild_id: row.discord_guild_id,
+                goal: ReadingGoal {
+                    user_id: row.user_id,
+                    year: row.year as i32,
+                    target: row.target,
+                },
+                milestone: Milestone::from_percent(row.percent)?,
+            })
+        })
+        .collect();
+
+        Ok(held)
+    }
+
+    /// Mark this milestone, and any before it, as announced
+    #[tracing::instrument(name = "Releasing held goal milestone", skip(pool))]
+    pub async fn mark_announced(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let percent = self.milestone.percent();
+        sqlx::query!(
+            r#"
+            UPDATE goal_milestones SET announced = TRUE
+            WHERE user_id = ? AND year = ? AND percent <= ?
+            "#,
+            self.goal.user_id,
+            self.goal.year,
+            percent
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReadingGoal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.target == 1 {
+            write!(f, "1 book in {}", self.year)
+        } else {
+            write!(f, "{} books in {}", self.target, self.year)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::Milestone;
+    use claim::assert_none;
+
+    #[test]
+    fn milestones_are_halfway_and_the_goal_itself() {
+        assert_none!(Milestone::reached(30, 14));
+        assert_eq!(Milestone::reached(30, 15), Some(Milestone::Halfway));
+        assert_eq!(Milestone::reached(30, 29), Some(Milestone::Halfway));
+        assert_eq!(Milestone::reached(30, 30), Some(Milestone::Reached));
+        assert_eq!(Milestone::reached(30, 31), Some(Milestone::Reached));
+        // Odd targets need a little over half
+        assert_none!(Milestone::reached(5, 2));
+        assert_eq!(Milestone::reached(5, 3), Some(Milestone::Halfway));
+        assert_none!(Milestone::reached(0, 3));
+    }
+
+    #[test]
+    fn milestones_are_stored_by_percent() {
+        for milestone in [Milestone::Halfway, Milestone::Reached] {
+            assert_eq!(
+                Milestone::from_percent(milestone.percent()),
+                Some(milestone)
+            );
+        }
+        assert_none!(Milestone::from_percent(75));
+    }
+}
@@ -0,0 +1,376 @@
+use chrono::offset::Utc;
+use chrono::{DateTime, Duration, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use sqlx::sqlite::SqlitePool;
+
+use crate::model::from_local;
+
+/// A book the guild reads together, with a deadline to finish it by
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupRead {
+    pub id: i64,
+    pub guild_id: i64,
+    pub book_id: String,
+    pub title: String,
+    pub author: String,
+    pub url: String,
+    pub started_by: i64,
+    pub started_at: i64,
+    pub deadline: i64,
+    pub wrapped_up: bool,
+}
+
+/// Someone taking part in a group read
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupReadMember {
+    pub discord_user_id: i64,
+    pub joined_at: i64,
+    pub finished_at: Option<i64>,
+    /// `None` when their preferences keep the book out of announcements, and 0 when unrated
+    pub rating: Option<i64>,
+    /// Whether their finishing has been in a progress update yet
+    pub reported: bool,
+}
+
+impl GroupReadMember {
+    pub fn finished(&self) -> bool {
+        self.finished_at.is_some()
+    }
+}
+
+impl GroupRead {
+    pub fn new(
+        guild_id: i64,
+        started_by: i64,
+        book_id: &str,
+        title: &str,
+        author: &str,
+        url: &str,
+        deadline: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            id: 0,
+            guild_id,
+            book_id: book_id.to_string(),
+            title: title.to_string(),
+            author: author.to_string(),
+            url: url.to_string(),
+            started_by,
+            started_at: Utc::now().timestamp(),
+            deadline: deadline.timestamp(),
+            wrapped_up: false,
+        }
+    }
+
+    /// The end of the day `value` names in the guild's time zone, as long as that's still to
+    /// come
+    pub fn parse_deadline(value: &str, time_zone: Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let date = NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok()?;
+        let deadline = from_local(time_zone, (date + Duration::days(1)).and_hms(0, 0, 0));
+        if deadline > now {
+            Some(deadline)
+        } else {
+            None
+        }
+    }
+
+    /// The last day to finish the book on, in the guild's time zone
+    pub fn last_day(&self, time_zone: Tz) -> NaiveDate {
+        time_zone
+            .timestamp(self.deadline - 1, 0)
+            .date()
+            .naive_local()
+    }
+
+    /// Start the group read, with whoever started it as its first member. Returns `None` when
+    /// the guild already has one running.
+    #[tracing::instrument(name = "Starting group read", skip(pool))]
+    pub async fn start(&self, pool: &SqlitePool) -> anyhow::Result<Option<Self>> {
+        let mut tx = pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO group_reads (guild_id, book_id, title, author, url, started_by, started_at, deadline)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT DO NOTHING
+            "#,
+            self.guild_id,
+            self.book_id,
+            self.title,
+            self.author,
+            self.url,
+            self.started_by,
+            self.started_at,
+            self.deadline
+        )
+        .execute(&mut tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        let id = result.last_insert_rowid();
+        sqlx::query!(
+            r#"
+            INSERT INTO group_read_members (group_read_id, discord_user_id, joined_at)
+            VALUES (?, ?, ?)
+            "#,
+            id,
+            self.started_by,
+            self.started_at
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(Self { id, ..self.clone() }))
+    }
+
+    /// The guild's group read, until it's wrapped up
+    #[tracing::instrument(name = "Retrieving running group read", skip(pool))]
+    pub async fn running(pool: &SqlitePool, guild_id: i64) -> anyhow::Result<Option<Self>> {
+        let mut conn = pool.acquire().await?;
+        let group_read = sqlx::query!(
+            r#"
+            SELECT id as "id!", guild_id, book_id, title, author, url, started_by, started_at, deadline, wrapped_up
+            FROM group_reads
+            WHERE guild_id = ? AND wrapped_up = FALSE
+            "#,
+            guild_id
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(|row| Self {
+            id: row.id,
+            guild_id: row.guild_id,
+            book_id: row.book_id,
+            title: row.title,
+            author: row.author,
+            url: row.url,
+            started_by: row.started_by,
+            started_at: row.started_at,
+            deadline: row.deadline,
+            wrapped_up: row.wrapped_up,
+        });
+
+        Ok(group_read)
+    }
+
+    /// Every guild's running group read
+    #[tracing::instrument(name = "Retrieving all running group reads", skip(pool))]
+    pub async fn all_running(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let group_reads = sqlx::query!(
+            r#"
+            SELECT id as "id!", guild_id, book_id, title, author, url, started_by, started_at, deadline, wrapped_up
+            FROM group_reads
+            WHERE wrapped_up = FALSE
+            "#
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| Self {
+            id: row.id,
+            guild_id: row.guild_id,
+            book_id: row.book_id,
+            title: row.title,
+            author: row.author,
+            url: row.url,
+            started_by: row.started_by,
+            started_at: row.started_at,
+            deadline: row.deadline,
+            wrapped_up: row.wrapped_up,
+        })
+        .collect();
+
+        Ok(group_reads)
+    }
+
+    /// Returns false when the member had already joined
+    #[tracing::instrument(name = "Joining group read", skip(pool))]
+    pub async fn join(&self, pool: &SqlitePool, discord_user_id: i64) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+        let joined_at = Utc::now().timestamp();
+
+        let joined = sqlx::query!(
+            r#"
+            INSERT INTO group_read_members (group_read_id, discord_user_id, joined_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT (group_read_id, discord_user_id) DO NOTHING
+            "#,
+            self.id,
+            discord_user_id,
+            joined_at
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(joined > 0)
+    }
+
+    /// Returns false when the member wasn't taking part
+    #[tracing::instrument(name = "Leaving group read", skip(pool))]
+    pub async fn leave(&self, pool: &SqlitePool, discord_user_id: i64) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+
+        let left = sqlx::query!(
+            r#"DELETE FROM group_read_members WHERE group_read_id = ? AND discord_user_id = ?"#,
+            self.id,
+            discord_user_id
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(left > 0)
+    }
+
+    /// Everyone taking part, in the order they joined
+    #[tracing::instrument(name = "Retrieving group read members", skip(pool))]
+    pub async fn members(&self, pool: &SqlitePool) -> anyhow::Result<Vec<GroupReadMember>> {
+        let mut conn = pool.acquire().await?;
+        let members = sqlx::query!(
+            r#"
+            SELECT discord_user_id, joined_at, finished_at, rating, reported
+            FROM group_read_members
+            WHERE group_read_id = ?
+            ORDER BY joined_at
+            "#,
+            self.id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| GroupReadMember {
+            discord_user_id: row.discord_user_id,
+            joined_at: row.joined_at,
+            finished_at: row.finished_at,
+            rating: row.rating,
+            reported: row.reported,
+        })
+        .collect();
+
+        Ok(members)
+    }
+
+    /// Mark the member done with the guild's running group read of the book, if they joined it.
+    /// Only the first read counts.
+    #[tracing::instrument(name = "Marking group read finished", skip(pool))]
+    pub async fn mark_finished(
+        pool: &SqlitePool,
+        guild_id: i64,
+        discord_user_id: i64,
+        book_id: &str,
+        rating: Option<i64>,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let finished_at = Utc::now().timestamp();
+
+        sqlx::query!(
+            r#"
+            UPDATE group_read_members SET finished_at = ?, rating = ?
+            WHERE discord_user_id = ? AND finished_at IS NULL AND group_read_id IN (
+                SELECT id FROM group_reads WHERE guild_id = ? AND book_id = ? AND wrapped_up = FALSE
+            )
+            "#,
+            finished_at,
+            rating,
+            discord_user_id,
+            guild_id,
+            book_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Remember that everyone who has finished so far was in a progress update
+    #[tracing::instrument(name = "Marking group read progress reported", skip(pool))]
+    pub async fn mark_reported(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE group_read_members SET reported = TRUE
+            WHERE group_read_id = ? AND finished_at IS NOT NULL
+            "#,
+            self.id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Finish the group read once its wrap-up is posted, which lets the guild start another
+    #[tracing::instrument(name = "Wrapping up group read", skip(pool))]
+    pub async fn wrap_up(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"UPDATE group_reads SET wrapped_up = TRUE WHERE id = ?"#,
+            self.id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Call the group read off without a wrap-up
+    #[tracing::instrument(name = "Cancelling group read", skip(pool))]
+    pub async fn cancel(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM group_read_members WHERE group_read_id = ?"#,
+            self.id
+        )
+        .execute(&mut tx)
+        .await?;
+        sqlx::query!(r#"DELETE FROM group_reads WHERE id = ?"#, self.id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::GroupRead;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use chrono_tz::Tz;
+    use claim::{assert_none, assert_some};
+
+    #[test]
+    fn deadlines_run_to_the_end_of_the_day_in_the_guilds_zone() {
+        let now = Utc.ymd(2022, 9, 27).and_hms(12, 0, 0);
+
+        assert_eq!(
+            GroupRead::parse_deadline("2022-10-31", Tz::Europe__Berlin, now),
+            Some(Utc.ymd(2022, 10, 31).and_hms(23, 0, 0))
+        );
+        assert_eq!(
+            GroupRead::parse_deadline("2022-09-27", Tz::UTC, now),
+            Some(Utc.ymd(2022, 9, 28).and_hms(0, 0, 0))
+        );
+        assert_none!(GroupRead::parse_deadline("2022-09-26", Tz::UTC, now));
+        assert_none!(GroupRead::parse_deadline("next month", Tz::UTC, now));
+    }
+
+    #[test]
+    fn the_last_day_is_the_one_the_deadline_was_set_for() {
+        let now = Utc.ymd(2022, 9, 27).and_hms(12, 0, 0);
+        let deadline = assert_some!(GroupRead::parse_deadline(
+            "2022-10-31",
+            Tz::Europe__Berlin,
+            now
+        ));
+        let group_read = GroupRead::new(0, 0, "4981", "", "", "", deadline);
+
+        assert_eq!(
+            group_read.last_day(Tz::Europe__Berlin),
+            NaiveDate::from_ymd(2022, 10, 31)
+        );
+    }
+}
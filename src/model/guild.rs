@@ -0,0 +1,729 @@
+use chrono::offset::Utc;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use serenity::model::prelude::ChannelId;
+use sqlx::sqlite::SqlitePool;
+use std::fmt;
+
+pub const DIGEST_TIME_FORMAT: &str = "%H:%M";
+
+const RECAP_DATE_FORMAT: &str = "%m-%d";
+
+/// What happens to an announcement when the member changes their mind about the book
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncMode {
+    /// Leave announcements as they were posted
+    Off,
+    /// Update changed ratings, and strike through books taken off the read shelf
+    Edit,
+    /// Update changed ratings, and delete books taken off the read shelf
+    Delete,
+}
+
+impl SyncMode {
+    pub const ALL: [SyncMode; 3] = [SyncMode::Off, SyncMode::Edit, SyncMode::Delete];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|mode| mode.as_str() == value.trim().to_lowercase())
+            .copied()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncMode::Off => "off",
+            SyncMode::Edit => "edit",
+            SyncMode::Delete => "delete",
+        }
+    }
+}
+
+/// How often a guild gets a reading digest instead of one announcement per book
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DigestSchedule {
+    Off,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl DigestSchedule {
+    pub const ALL: [DigestSchedule; 4] = [
+        DigestSchedule::Off,
+        DigestSchedule::Daily,
+        DigestSchedule::Weekly,
+        DigestSchedule::Monthly,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|schedule| schedule.as_str() == value.trim().to_lowercase())
+            .copied()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestSchedule::Off => "off",
+            DigestSchedule::Daily => "daily",
+            DigestSchedule::Weekly => "weekly",
+            DigestSchedule::Monthly => "monthly",
+        }
+    }
+}
+
+/// A nightly window in the guild's time zone when announcements are held back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Read a window such as `22:00-07:00`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (start, end) = value.trim().split_once('-')?;
+        let start = NaiveTime::parse_from_str(start.trim(), DIGEST_TIME_FORMAT).ok()?;
+        let end = NaiveTime::parse_from_str(end.trim(), DIGEST_TIME_FORMAT).ok()?;
+        if start == end {
+            return None;
+        }
+        Some(Self { start, end })
+    }
+
+    /// When an announcement made at `now` can go out, or `None` if it's outside the window
+    pub fn release_at(&self, time_zone: Tz, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let local_now = now.with_timezone(&time_zone).naive_local();
+        let today = local_now.date();
+        let time = local_now.time();
+
+        let release_on = if self.start < self.end {
+            if time < self.start || time >= self.end {
+                return None;
+            }
+            today
+        } else if time >= self.start {
+            today + Duration::days(1)
+        } else if time < self.end {
+            today
+        } else {
+            return None;
+        };
+
+        Some(from_local(time_zone, release_on.and_time(self.end)))
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format(DIGEST_TIME_FORMAT),
+            self.end.format(DIGEST_TIME_FORMAT)
+        )
+    }
+}
+
+/// The day of the year a guild gets its year-in-review. Recaps in January look back on the year
+/// before, and recaps on any other day on the year so far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecapDate {
+    pub month: u32,
+    pub day: u32,
+}
+
+impl RecapDate {
+    /// Read a date such as `01-05`
+    pub fn parse(value: &str) -> Option<Self> {
+        // A leap year, so the 29th of February is allowed
+        let date = NaiveDate::parse_from_str(&format!("2000-{}", value.trim()), "%Y-%m-%d").ok()?;
+        Some(Self {
+            month: date.month(),
+            day: date.day(),
+        })
+    }
+
+    /// The recap's date in `year`, with the 29th of February falling back a day in other years
+    pub fn in_year(&self, year: i32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, self.month, self.day)
+            .unwrap_or_else(|| NaiveDate::from_ymd(year, self.month, self.day - 1))
+    }
+
+    /// The year a recap posted in `year` looks back on
+    pub fn covers(&self, year: i32) -> i32 {
+        if self.month == 1 {
+            year - 1
+        } else {
+            year
+        }
+    }
+
+    /// The year of the recap that's due at `now`, at `time` in the guild's zone, unless one was
+    /// already posted this year
+    pub fn due(
+        &self,
+        time: NaiveTime,
+        time_zone: Tz,
+        now: DateTime<Utc>,
+        last_recap_year: Option<i32>,
+    ) -> Option<i32> {
+        let year = now.with_timezone(&time_zone).year();
+        let slot = from_local(time_zone, self.in_year(year).and_time(time));
+        if now >= slot && !matches!(last_recap_year, Some(last) if last >= year) {
+            Some(year)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for RecapDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.in_year(2000).format(RECAP_DATE_FORMAT))
+    }
+}
+
+/// The instant a wall clock time happens in the zone. Times skipped by a daylight saving change
+/// are taken an hour later, and repeated times the first time round.
+pub fn from_local(time_zone: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    time_zone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            time_zone
+                .from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|at| at.with_timezone(&Utc))
+        .unwrap_or_else(|| DateTime::from_utc(local, Utc))
+}
+
+#[derive(Clone)]
+pub struct GuildSettings {
+    pub guild_id: i64,
+    pub guild_name: String,
+    pub notify_channel_id: i64,
+    pub announcement_template: Option<String>,
+    pub webhook_enabled: bool,
+    pub webhook_id: Option<i64>,
+    pub webhook_token: Option<String>,
+    pub threads_enabled: bool,
+    pub thread_archive_minutes: i64,
+    pub announcement_sync: SyncMode,
+    pub digest_schedule: DigestSchedule,
+    pub digest_time: NaiveTime,
+    pub last_digest_at: Option<i64>,
+    /// Digests, quiet hours and dates in announcements all follow this zone
+    pub time_zone: Tz,
+    pub quiet_hours: Option<QuietHours>,
+    /// `None` when the guild has turned the year-in-review off
+    pub recap_date: Option<RecapDate>,
+    pub last_recap_year: Option<i32>,
+    /// How many members have to finish a book for it to be a club favourite. `None` when the
+    /// guild has turned club favourites off.
+    pub club_favourite_readers: Option<i64>,
+}
+
+impl fmt::Debug for GuildSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Keep the webhook token out of the logs
+        f.debug_struct("GuildSettings")
+            .field("guild_id", &self.guild_id)
+            .field("guild_name", &self.guild_name)
+            .field("notify_channel_id", &self.notify_channel_id)
+            .field("announcement_template", &self.announcement_template)
+            .field("webhook_enabled", &self.webhook_enabled)
+            .field("webhook_id", &self.webhook_id)
+            .field("threads_enabled", &self.threads_enabled)
+            .field("thread_archive_minutes", &self.thread_archive_minutes)
+            .field("announcement_sync", &self.announcement_sync)
+            .field("digest_schedule", &self.digest_schedule)
+            .field("digest_time", &self.digest_time)
+            .field("last_digest_at", &self.last_digest_at)
+            .field("time_zone", &self.time_zone)
+            .field("quiet_hours", &self.quiet_hours)
+            .field("recap_date", &self.recap_date)
+            .field("last_recap_year", &self.last_recap_year)
+            .field("club_favourite_readers", &self.club_favourite_readers)
+            .finish()
+    }
+}
+
+impl GuildSettings {
+    #[tracing::instrument(name = "Retrieving guild settings", skip(pool))]
+    pub async fn get(pool: &SqlitePool, guild_id: i64) -> anyhow::Result<Self> {
+        let mut conn = pool.acquire().await?;
+        let row = sqlx::query!(r#"SELECT * FROM guilds WHERE guild_id = ?"#, guild_id)
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(Self {
+            guild_id: row.guild_id,
+            guild_name: row.guild_name,
+            notify_channel_id: row.notify_channel_id,
+            announcement_template: row.announcement_template,
+            webhook_enabled: row.webhook_enabled,
+            webhook_id: row.webhook_id,
+            webhook_token: row.webhook_token,
+            threads_enabled: row.threads_enabled,
+            thread_archive_minutes: row.thread_archive_minutes,
+            announcement_sync: SyncMode::parse(&row.announcement_sync).unwrap_or(SyncMode::Off),
+            digest_schedule: DigestSchedule::parse(&row.digest_schedule)
+                .unwrap_or(DigestSchedule::Off),
+            digest_time: NaiveTime::parse_from_str(&row.digest_time, DIGEST_TIME_FORMAT)
+                .unwrap_or_else(|_| NaiveTime::from_hms(9, 0, 0)),
+            last_digest_at: row.last_digest_at,
+            time_zone: row.time_zone.parse().unwrap_or(Tz::UTC),
+            quiet_hours: match (row.quiet_hours_start, row.quiet_hours_end) {
+                (Some(start), Some(end)) => QuietHours::parse(&format!("{}-{}", start, end)),
+                _ => None,
+            },
+            recap_date: row.recap_date.as_deref().and_then(RecapDate::parse),
+            last_recap_year: row.last_recap_year.map(|year| year as i32),
+            club_favourite_readers: row.club_favourite_readers,
+        })
+    }
+
+    pub fn notify_channel(&self) -> ChannelId {
+        ChannelId(self.notify_channel_id as u64)
+    }
+
+    /// Store a new announcement template, or clear it with `None` to go back to the default
+    #[tracing::instrument(name = "Updating the announcement template in DB", skip(pool))]
+    pub async fn set_announcement_template(
+        pool: &SqlitePool,
+        guild_id: i64,
+        template: Option<&str>,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"UPDATE guilds SET announcement_template = ? WHERE guild_id = ?"#,
+            template,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating webhook mode in DB", skip(pool))]
+    pub async fn set_webhook_enabled(
+        pool: &SqlitePool,
+        guild_id: i64,
+        enabled: bool,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"UPDATE guilds SET webhook_enabled = ? WHERE guild_id = ?"#,
+            enabled,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Remember the webhook the bot manages for this guild, or forget it with `None`
+    #[tracing::instrument(name = "Storing guild webhook in DB", skip(pool, webhook))]
+    pub async fn set_webhook(
+        pool: &SqlitePool,
+        guild_id: i64,
+        webhook: Option<(i64, &str)>,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let webhook_id = webhook.map(|(id, _)| id);
+        let webhook_token = webhook.map(|(_, token)| token);
+
+        sqlx::query!(
+            r#"UPDATE guilds SET webhook_id = ?, webhook_token = ? WHERE guild_id = ?"#,
+            webhook_id,
+            webhook_token,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating discussion thread settings in DB", skip(pool))]
+    pub async fn set_threads(
+        pool: &SqlitePool,
+        guild_id: i64,
+        enabled: bool,
+        archive_minutes: i64,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"UPDATE guilds SET threads_enabled = ?, thread_archive_minutes = ? WHERE guild_id = ?"#,
+            enabled,
+            archive_minutes,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating announcement sync mode in DB", skip(pool))]
+    pub async fn set_announcement_sync(
+        pool: &SqlitePool,
+        guild_id: i64,
+        mode: SyncMode,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let mode = mode.as_str();
+
+        sqlx::query!(
+            r#"UPDATE guilds SET announcement_sync = ? WHERE guild_id = ?"#,
+            mode,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Switch the guild's digest schedule. Books finished from now on go in the first digest.
+    #[tracing::instrument(name = "Updating digest schedule in DB", skip(pool))]
+    pub async fn set_digest(
+        pool: &SqlitePool,
+        guild_id: i64,
+        schedule: DigestSchedule,
+        time: NaiveTime,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let schedule = schedule.as_str();
+        let time = time.format(DIGEST_TIME_FORMAT).to_string();
+        let now = Utc::now().timestamp();
+
+        sqlx::query!(
+            r#"
+            UPDATE guilds SET digest_schedule = ?, digest_time = ?, last_digest_at = ?
+            WHERE guild_id = ?
+            "#,
+            schedule,
+            time,
+            now,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Recording digest in DB", skip(pool))]
+    pub async fn set_last_digest_at(
+        pool: &SqlitePool,
+        guild_id: i64,
+        at: i64,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"UPDATE guilds SET last_digest_at = ? WHERE guild_id = ?"#,
+            at,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Updating time zone in DB", skip(pool))]
+    pub async fn set_time_zone(
+        pool: &SqlitePool,
+        guild_id: i64,
+        time_zone: Tz,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let time_zone = time_zone.name();
+
+        sqlx::query!(
+            r#"UPDATE guilds SET time_zone = ? WHERE guild_id = ?"#,
+            time_zone,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Store a quiet hours window, or clear it with `None` to announce around the clock
+    #[tracing::instrument(name = "Updating quiet hours in DB", skip(pool))]
+    pub async fn set_quiet_hours(
+        pool: &SqlitePool,
+        guild_id: i64,
+        quiet_hours: Option<QuietHours>,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let start = quiet_hours.map(|q| q.start.format(DIGEST_TIME_FORMAT).to_string());
+        let end = quiet_hours.map(|q| q.end.format(DIGEST_TIME_FORMAT).to_string());
+
+        sqlx::query!(
+            r#"UPDATE guilds SET quiet_hours_start = ?, quiet_hours_end = ? WHERE guild_id = ?"#,
+            start,
+            end,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Store the day of the year-in-review, or clear it with `None` to stop it. A recap already
+    /// posted this year is remembered, so moving the date doesn't post another.
+    #[tracing::instrument(name = "Updating year-in-review settings in DB", skip(pool))]
+    pub async fn set_recap(
+        pool: &SqlitePool,
+        guild_id: i64,
+        date: Option<RecapDate>,
+        last_recap_year: Option<i32>,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let date = date.map(|date| date.to_string());
+
+        sqlx::query!(
+            r#"UPDATE guilds SET recap_date = ?, last_recap_year = ? WHERE guild_id = ?"#,
+            date,
+            last_recap_year,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Store how many readers make a club favourite, or clear it with `None` to stop flagging them
+    #[tracing::instrument(name = "Updating club favourite threshold in DB", skip(pool))]
+    pub async fn set_club_favourite_readers(
+        pool: &SqlitePool,
+        guild_id: i64,
+        readers: Option<i64>,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"UPDATE guilds SET club_favourite_readers = ? WHERE guild_id = ?"#,
+            readers,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Recording year-in-review in DB", skip(pool))]
+    pub async fn set_last_recap_year(
+        pool: &SqlitePool,
+        guild_id: i64,
+        year: i32,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"UPDATE guilds SET last_recap_year = ? WHERE guild_id = ?"#,
+            year,
+            guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Every guild that gets a year-in-review
+    #[tracing::instrument(name = "Retrieving guilds with recaps", skip(pool))]
+    pub async fn with_recaps(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let guild_ids = sqlx::query!(r#"SELECT guild_id FROM guilds WHERE recap_date IS NOT NULL"#)
+            .fetch_all(&mut conn)
+            .await?;
+        drop(conn);
+
+        Ok(Self::get_each(pool, guild_ids.into_iter().map(|row| row.guild_id)).await)
+    }
+
+    /// Every guild with a member who wants to hear about their reading pace
+    #[tracing::instrument(name = "Retrieving guilds with pace", skip(pool))]
+    pub async fn with_pace(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let guild_ids = sqlx::query!(
+            r#"
+            SELECT DISTINCT users.discord_guild_id FROM users
+            JOIN user_preferences ON user_preferences.user_id = users.id
+            WHERE user_preferences.pace = TRUE
+            "#
+        )
+        .fetch_all(&mut conn)
+        .await?;
+        drop(conn);
+
+        Ok(Self::get_each(pool, guild_ids.into_iter().map(|row| row.discord_guild_id)).await)
+    }
+
+    /// The settings of each guild, leaving out any that can't be looked up so one missing guild
+    /// doesn't hold up the rest
+    async fn get_each(pool: &SqlitePool, guild_ids: impl Iterator<Item = i64>) -> Vec<Self> {
+        let mut guilds = Vec::new();
+        for guild_id in guild_ids {
+            match Self::get(pool, guild_id).await {
+                Ok(settings) => guilds.push(settings),
+                Err(why) => {
+                    tracing::error!(
+                        error.cause_chain = ?why,
+                        error.message = %why,
+                        "Unable to look up settings for guild ({}) because: {}",
+                        guild_id,
+                        why
+                    );
+                }
+            }
+        }
+        guilds
+    }
+
+    /// Every guild that gets reading digests
+    #[tracing::instrument(name = "Retrieving guilds with digests", skip(pool))]
+    pub async fn with_digests(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let guild_ids =
+            sqlx::query!(r#"SELECT guild_id FROM guilds WHERE digest_schedule != 'off'"#)
+                .fetch_all(&mut conn)
+                .await?;
+        drop(conn);
+
+        Ok(Self::get_each(pool, guild_ids.into_iter().map(|row| row.guild_id)).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{from_local, DigestSchedule, QuietHours, RecapDate, SyncMode};
+    use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+    use chrono_tz::Tz;
+    use claim::{assert_none, assert_some};
+
+    #[test]
+    fn sync_modes_round_trip_through_their_names() {
+        for mode in SyncMode::ALL {
+            assert_eq!(assert_some!(SyncMode::parse(mode.as_str())), mode);
+        }
+        assert_eq!(assert_some!(SyncMode::parse(" Delete ")), SyncMode::Delete);
+        assert_none!(SyncMode::parse("retract"));
+    }
+
+    #[test]
+    fn digest_schedules_round_trip_through_their_names() {
+        for schedule in DigestSchedule::ALL {
+            assert_eq!(
+                assert_some!(DigestSchedule::parse(schedule.as_str())),
+                schedule
+            );
+        }
+        assert_none!(DigestSchedule::parse("hourly"));
+    }
+
+    #[test]
+    fn quiet_hours_are_read_from_a_window() {
+        let quiet = assert_some!(QuietHours::parse("22:00-07:30"));
+        assert_eq!(quiet.start, NaiveTime::from_hms(22, 0, 0));
+        assert_eq!(quiet.end, NaiveTime::from_hms(7, 30, 0));
+        assert_eq!(quiet.to_string(), "22:00-07:30");
+        assert_none!(QuietHours::parse("22:00"));
+        assert_none!(QuietHours::parse("09:00-09:00"));
+        assert_none!(QuietHours::parse("late-early"));
+    }
+
+    #[test]
+    fn overnight_quiet_hours_release_the_next_morning() {
+        let quiet = assert_some!(QuietHours::parse("22:00-07:00"));
+        // 23:30 in Berlin during summer time is 21:30 UTC
+        let release = assert_some!(
+            quiet.release_at(Tz::Europe__Berlin, Utc.ymd(2022, 8, 26).and_hms(21, 30, 0))
+        );
+        assert_eq!(release, Utc.ymd(2022, 8, 27).and_hms(5, 0, 0));
+
+        let release = assert_some!(
+            quiet.release_at(Tz::Europe__Berlin, Utc.ymd(2022, 8, 27).and_hms(1, 0, 0))
+        );
+        assert_eq!(release, Utc.ymd(2022, 8, 27).and_hms(5, 0, 0));
+
+        assert_none!(quiet.release_at(Tz::Europe__Berlin, Utc.ymd(2022, 8, 27).and_hms(12, 0, 0)));
+    }
+
+    #[test]
+    fn daytime_quiet_hours_release_the_same_day() {
+        let quiet = assert_some!(QuietHours::parse("09:00-17:00"));
+        let release =
+            assert_some!(quiet.release_at(Tz::UTC, Utc.ymd(2022, 8, 26).and_hms(9, 0, 0)));
+        assert_eq!(release, Utc.ymd(2022, 8, 26).and_hms(17, 0, 0));
+        assert_none!(quiet.release_at(Tz::UTC, Utc.ymd(2022, 8, 26).and_hms(17, 0, 0)));
+    }
+
+    #[test]
+    fn skipped_local_times_move_forward_an_hour() {
+        // Clocks in New York went from 02:00 to 03:00 on March 13th 2022
+        let skipped = NaiveDate::from_ymd(2022, 3, 13).and_hms(2, 30, 0);
+        assert_eq!(
+            from_local(Tz::America__New_York, skipped),
+            Utc.ymd(2022, 3, 13).and_hms(7, 30, 0)
+        );
+    }
+
+    #[test]
+    fn recap_dates_are_read_as_month_and_day() {
+        let date = assert_some!(RecapDate::parse("01-05"));
+        assert_eq!(date, RecapDate { month: 1, day: 5 });
+        assert_eq!(date.to_string(), "01-05");
+        assert_eq!(date.covers(2023), 2022);
+        assert_eq!(assert_some!(RecapDate::parse("12-31")).covers(2022), 2022);
+
+        let leap_day = assert_some!(RecapDate::parse("02-29"));
+        assert_eq!(leap_day.in_year(2023), NaiveDate::from_ymd(2023, 2, 28));
+        assert_none!(RecapDate::parse("02-30"));
+        assert_none!(RecapDate::parse("January"));
+    }
+
+    #[test]
+    fn recaps_are_due_once_a_year_after_the_date() {
+        let date = RecapDate { month: 1, day: 1 };
+        let nine = NaiveTime::from_hms(9, 0, 0);
+
+        assert_none!(date.due(
+            nine,
+            Tz::UTC,
+            Utc.ymd(2023, 1, 1).and_hms(8, 0, 0),
+            Some(2022)
+        ));
+        assert_eq!(
+            date.due(
+                nine,
+                Tz::UTC,
+                Utc.ymd(2023, 1, 1).and_hms(9, 0, 0),
+                Some(2022)
+            ),
+            Some(2023)
+        );
+        assert_eq!(
+            date.due(nine, Tz::UTC, Utc.ymd(2023, 3, 1).and_hms(9, 0, 0), None),
+            Some(2023)
+        );
+        assert_none!(date.due(
+            nine,
+            Tz::UTC,
+            Utc.ymd(2023, 3, 1).and_hms(9, 0, 0),
+            Some(2023)
+        ));
+        // Still 2022 in Los Angeles
+        assert_none!(date.due(
+            nine,
+            Tz::America__Los_Angeles,
+            Utc.ymd(2023, 1, 1).and_hms(9, 0, 0),
+            Some(2022)
+        ));
+    }
+}
@@ -0,0 +1,518 @@
+use chrono::offset::Utc;
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use sqlx::sqlite::{SqlitePool, SqliteQueryResult};
+
+use crate::model::{Book, User};
+
+const READ_ON_FORMAT: &str = "%Y-%m-%d";
+
+/// Plenty for a reply, and keeps a one letter search from pulling in the whole history
+const MAX_SEARCH_RESULTS: i64 = 50;
+
+/// A book a user finished, kept whether or not it was announced
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub user_id: i64,
+    pub discord_user_id: i64,
+    pub guild_id: i64,
+    pub book_id: String,
+    pub title: String,
+    pub author: String,
+    pub url: String,
+    pub image_url: String,
+    pub rating: i64,
+    pub pages: Option<i64>,
+    pub shelves: Vec<String>,
+    pub read_on: NaiveDate,
+    /// The day the book went on the user's shelves, when the feed said
+    pub added_on: Option<NaiveDate>,
+    pub recorded_at: i64,
+    /// Whether the user's preferences let the book be announced
+    pub shared: bool,
+}
+
+impl HistoryEntry {
+    #[tracing::instrument(name = "Recording finished book", skip(pool))]
+    pub async fn record(
+        pool: &SqlitePool,
+        user: &User,
+        book: &Book,
+        time_zone: Tz,
+        shared: bool,
+    ) -> anyhow::Result<Self> {
+        let mut conn = pool.acquire().await?;
+        let book_id = book.id();
+        let title = book.title();
+        let author = book.author();
+        let url = book.url();
+        let image_url = book.image();
+        let rating = book.rating() as i64;
+        let pages = book.pages().map(i64::from);
+        let shelves = book.shelves().join(",");
+        let read_on = book.read_on(time_zone);
+        let read_on_text = read_on.format(READ_ON_FORMAT).to_string();
+        let added_on = book.added_on();
+        let added_on_text = added_on.map(|added_on| added_on.format(READ_ON_FORMAT).to_string());
+        let recorded_at = Utc::now().timestamp();
+
+        let result: SqliteQueryResult = sqlx::query!(
+            r#"
+            INSERT INTO reading_history (user_id, discord_user_id, guild_id, book_id, title, author, url, image_url, rating, pages, shelves, read_on, added_on, recorded_at, shared)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            user.id,
+            user.discord_user_id,
+            user.discord_guild_id,
+            book_id,
+            title,
+            author,
+            url,
+            image_url,
+            rating,
+            pages,
+            shelves,
+            read_on_text,
+            added_on_text,
+            recorded_at,
+            shared,
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(Self {
+            id: result.last_insert_rowid(),
+            user_id: user.id,
+            discord_user_id: user.discord_user_id,
+            guild_id: user.discord_guild_id,
+            book_id: book_id.to_string(),
+            title: title.to_string(),
+            author: author.to_string(),
+            url: url.to_string(),
+            image_url: image_url.to_string(),
+            rating,
+            pages,
+            shelves: book.shelves().clone(),
+            read_on,
+            added_on,
+            recorded_at,
+            shared,
+        })
+    }
+
+    /// Books shared in the guild that were recorded after `after`, up to and including `until`
+    #[tracing::instrument(name = "Retrieving shared reading history", skip(pool))]
+    pub async fn shared_between(
+        pool: &SqlitePool,
+        guild_id: i64,
+        after: i64,
+        until: i64,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let entries = sqlx::query_as!(
+            HistoryRow,
+            r#"
+            SELECT id as "id!", user_id, discord_user_id, guild_id, book_id, title, author, url, image_url, rating,
+                pages, shelves, read_on, added_on, recorded_at, shared
+            FROM reading_history
+            WHERE guild_id = ? AND shared = TRUE AND recorded_at > ? AND recorded_at <= ?
+            ORDER BY recorded_at
+            "#,
+            guild_id,
+            after,
+            until
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(Self::from)
+        .collect();
+
+        Ok(entries)
+    }
+
+    /// Everything the member finished in the guild, oldest first. Books their preferences kept
+    /// from being announced are left out unless `include_private` is set.
+    #[tracing::instrument(name = "Retrieving member reading history", skip(pool))]
+    pub async fn for_member(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        guild_id: i64,
+        include_private: bool,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let entries = sqlx::query_as!(
+            HistoryRow,
+            r#"
+            SELECT id as "id!", user_id, discord_user_id, guild_id, book_id, title, author, url, image_url, rating,
+                pages, shelves, read_on, added_on, recorded_at, shared
+            FROM reading_history
+            WHERE discord_user_id = ? AND guild_id = ? AND (shared = TRUE OR ?)
+            ORDER BY read_on, recorded_at
+            "#,
+            discord_user_id,
+            guild_id,
+            include_private
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(Self::from)
+        .collect();
+
+        Ok(entries)
+    }
+
+    /// Every book shared in the guild, oldest first
+    #[tracing::instrument(name = "Retrieving guild reading history", skip(pool))]
+    pub async fn shared_in_guild(pool: &SqlitePool, guild_id: i64) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let entries = sqlx::query_as!(
+            HistoryRow,
+            r#"
+            SELECT id as "id!", user_id, discord_user_id, guild_id, book_id, title, author, url, image_url, rating,
+                pages, shelves, read_on, added_on, recorded_at, shared
+            FROM reading_history
+            WHERE guild_id = ? AND shared = TRUE
+            ORDER BY read_on, recorded_at
+            "#,
+            guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(Self::from)
+        .collect();
+
+        Ok(entries)
+    }
+
+    /// Everyone else in the guild who shared finishing the book, first reader first, with their
+    /// latest read of it
+    #[tracing::instrument(name = "Retrieving other readers of book", skip(pool))]
+    pub async fn other_readers(
+        pool: &SqlitePool,
+        guild_id: i64,
+        user_id: i64,
+        book_id: &str,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let entries: Vec<Self> = sqlx::query_as!(
+            HistoryRow,
+            r#"
+            SELECT id as "id!", user_id, discord_user_id, guild_id, book_id, title, author, url, image_url, rating,
+                pages, shelves, read_on, added_on, recorded_at, shared
+            FROM reading_history
+            WHERE guild_id = ? AND book_id = ? AND user_id != ? AND shared = TRUE
+            ORDER BY read_on, recorded_at
+            "#,
+            guild_id,
+            book_id,
+            user_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(Self::from)
+        .collect();
+
+        Ok(latest_per_reader(entries))
+    }
+
+    /// Books shared in the guild that were read from `from` up to and including `until`
+    #[tracing::instrument(name = "Retrieving shared reading history by date", skip(pool))]
+    pub async fn shared_read_between(
+        pool: &SqlitePool,
+        guild_id: i64,
+        from: NaiveDate,
+        until: NaiveDate,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        // The dates sort as text, so they can be compared without parsing
+        let from = from.format(READ_ON_FORMAT).to_string();
+        let until = until.format(READ_ON_FORMAT).to_string();
+        let entries = sqlx::query_as!(
+            HistoryRow,
+            r#"
+            SELECT id as "id!", user_id, discord_user_id, guild_id, book_id, title, author, url, image_url, rating,
+                pages, shelves, read_on, added_on, recorded_at, shared
+            FROM reading_history
+            WHERE guild_id = ? AND shared = TRUE AND read_on >= ? AND read_on <= ?
+            ORDER BY read_on, recorded_at
+            "#,
+            guild_id,
+            from,
+            until
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(Self::from)
+        .collect();
+
+        Ok(entries)
+    }
+
+    /// How many books the user finished in `year`, shared or not
+    #[tracing::instrument(name = "Counting books read in year", skip(pool))]
+    pub async fn count_in_year(pool: &SqlitePool, user_id: i64, year: i32) -> anyhow::Result<i64> {
+        let mut conn = pool.acquire().await?;
+        let pattern = format!("{:04}-%", year);
+        let row = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as "count!: i64" FROM reading_history
+            WHERE user_id = ? AND read_on LIKE ?
+            "#,
+            user_id,
+            pattern
+        )
+        .fetch_one(&mut conn)
+        .await?;
+
+        Ok(row.count)
+    }
+
+    /// Books shared in the guild whose title or author matches `query`, best matches first. Every
+    /// word has to match, but only the start of it, so `ursula guin` finds Ursula K. Le Guin.
+    #[tracing::instrument(name = "Searching reading history", skip(pool))]
+    pub async fn search(
+        pool: &SqlitePool,
+        guild_id: i64,
+        query: &str,
+    ) -> anyhow::Result<Vec<Self>> {
+        let expression = match match_expression(query) {
+            Some(expression) => expression,
+            None => return Ok(Vec::new()),
+        };
+        let mut conn = pool.acquire().await?;
+        let entries = sqlx::query_as!(
+            HistoryRow,
+            r#"
+            SELECT reading_history.id as "id!", user_id as "user_id!", discord_user_id as "discord_user_id!",
+                guild_id as "guild_id!", book_id as "book_id!", reading_history.title as "title!",
+                reading_history.author as "author!", url as "url!", image_url as "image_url!",
+                rating as "rating!", pages, shelves as "shelves!", read_on as "read_on!", added_on,
+                recorded_at as "recorded_at!", shared as "shared!"
+            FROM reading_history_search
+            JOIN reading_history ON reading_history.id = reading_history_search.rowid
+            WHERE reading_history_search MATCH ? AND guild_id = ? AND shared = TRUE
+            ORDER BY bm25(reading_history_search), read_on
+            LIMIT ?
+            "#,
+            expression,
+            guild_id,
+            MAX_SEARCH_RESULTS
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(Self::from)
+        .collect();
+
+        Ok(entries)
+    }
+
+    /// Keep the most recent read of the book in line with a new rating
+    #[tracing::instrument(name = "Updating rating in reading history", skip(pool))]
+    pub async fn set_rating(
+        pool: &SqlitePool,
+        user_id: i64,
+        book_id: &str,
+        rating: i64,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"
+            UPDATE reading_history SET rating = ?
+            WHERE id = (SELECT MAX(id) FROM reading_history WHERE user_id = ? AND book_id = ?)
+            "#,
+            rating,
+            user_id,
+            book_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Forget the most recent read of a book the user took off their read shelf
+    #[tracing::instrument(name = "Removing book from reading history", skip(pool))]
+    pub async fn remove(pool: &SqlitePool, user_id: i64, book_id: &str) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"
+            DELETE FROM reading_history
+            WHERE id = (SELECT MAX(id) FROM reading_history WHERE user_id = ? AND book_id = ?)
+            "#,
+            user_id,
+            book_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl HistoryEntry {
+    /// A shared four star read of the book, for tests to change what they need of
+    pub fn test_read(discord_user_id: i64, book_id: &str) -> Self {
+        Self {
+            id: 0,
+            user_id: discord_user_id,
+            discord_user_id,
+            guild_id: 0,
+            book_id: book_id.to_string(),
+            title: format!("Book {}", book_id),
+            author: "Ursula K. Le Guin".to_string(),
+            url: format!("https://www.goodreads.com/book/show/{}", book_id),
+            image_url: String::new(),
+            rating: 4,
+            pages: None,
+            shelves: Vec::new(),
+            read_on: NaiveDate::from_ymd(2022, 9, 1),
+            added_on: None,
+            recorded_at: 0,
+            shared: true,
+        }
+    }
+
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn with_author(mut self, author: &str) -> Self {
+        self.author = author.to_string();
+        self
+    }
+
+    pub fn with_rating(mut self, rating: i64) -> Self {
+        self.rating = rating;
+        self
+    }
+
+    pub fn with_pages(mut self, pages: Option<i64>) -> Self {
+        self.pages = pages;
+        self
+    }
+
+    pub fn with_read_on(mut self, read_on: NaiveDate) -> Self {
+        self.read_on = read_on;
+        self
+    }
+
+    pub fn with_added_on(mut self, added_on: Option<NaiveDate>) -> Self {
+        self.added_on = added_on;
+        self
+    }
+}
+
+/// A `reading_history` row as it's stored, before the shelves and dates are parsed
+struct HistoryRow {
+    id: i64,
+    user_id: i64,
+    discord_user_id: i64,
+    guild_id: i64,
+    book_id: String,
+    title: String,
+    author: String,
+    url: String,
+    image_url: String,
+    rating: i64,
+    pages: Option<i64>,
+    shelves: String,
+    read_on: String,
+    added_on: Option<String>,
+    recorded_at: i64,
+    shared: bool,
+}
+
+impl From<HistoryRow> for HistoryEntry {
+    fn from(row: HistoryRow) -> Self {
+        Self {
+            id: row.id,
+            user_id: row.user_id,
+            discord_user_id: row.discord_user_id,
+            guild_id: row.guild_id,
+            book_id: row.book_id,
+            title: row.title,
+            author: row.author,
+            url: row.url,
+            image_url: row.image_url,
+            rating: row.rating,
+            pages: row.pages,
+            shelves: parse_shelves(&row.shelves),
+            read_on: parse_read_on(&row.read_on),
+            added_on: row.added_on.as_deref().map(parse_read_on),
+            recorded_at: row.recorded_at,
+            shared: row.shared,
+        }
+    }
+}
+
+/// One entry per reader, where they first read it, but with their latest read in its place
+fn latest_per_reader(entries: Vec<HistoryEntry>) -> Vec<HistoryEntry> {
+    let mut readers: Vec<HistoryEntry> = Vec::new();
+    for entry in entries {
+        match readers
+            .iter_mut()
+            .find(|reader| reader.user_id == entry.user_id)
+        {
+            Some(reader) => *reader = entry,
+            None => readers.push(entry),
+        }
+    }
+    readers
+}
+
+/// Turn what a member typed into an FTS5 query that matches the start of every word. Each word is
+/// quoted, so nothing they type is read as FTS5 syntax.
+fn match_expression(query: &str) -> Option<String> {
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+fn parse_shelves(shelves: &str) -> Vec<String> {
+    shelves
+        .split(',')
+        .filter(|shelf| !shelf.is_empty())
+        .map(|shelf| shelf.to_string())
+        .collect()
+}
+
+/// Dates are only ever written by `record`, so a bad one means the row was edited by hand
+fn parse_read_on(read_on: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(read_on, READ_ON_FORMAT).unwrap_or_else(|_| {
+        tracing::warn!("Unparseable read_on date in reading history: {}", read_on);
+        NaiveDate::from_ymd(1970, 1, 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::history::match_expression;
+    use claim::assert_none;
+
+    #[test]
+    fn searches_match_the_start_of_every_word() {
+        assert_eq!(
+            match_expression("Le Guin, Ursula"),
+            Some(r#""Le"* "Guin"* "Ursula"*"#.to_string())
+        );
+        assert_eq!(
+            match_expression(r#"left hand" OR NOT"#),
+            Some(r#""left"* "hand"* "OR"* "NOT"*"#.to_string())
+        );
+        assert_none!(match_expression(" -*- "));
+    }
+}
@@ -1,6 +1,46 @@
+mod announcement;
+mod author;
+mod badge;
 mod book;
+mod compatibility;
+mod currently_reading;
+mod follow;
+mod goal;
+mod group_read;
+mod guild;
+mod history;
+mod outbox;
+mod pace;
+mod poll;
+mod preferences;
+mod reaction;
+mod recommendation;
+mod routing;
+mod stats;
 mod user;
+mod want_to_read;
 
 // pub use book::get_books;
+pub use announcement::Announcement;
+pub use author::{Author, AuthorFollow};
+pub use badge::{Badge, EarnedBadge};
 pub use book::Book;
+pub use compatibility::{Compatibility, SharedBook, MIN_TWIN_BOOKS};
+pub use currently_reading::CurrentlyReading;
+pub use follow::Follow;
+pub use goal::{HeldMilestone, Milestone, ReadingGoal};
+pub use group_read::{GroupRead, GroupReadMember};
+pub use guild::{
+    from_local, DigestSchedule, GuildSettings, QuietHours, RecapDate, SyncMode, DIGEST_TIME_FORMAT,
+};
+pub use history::HistoryEntry;
+pub use outbox::OutboxEntry;
+pub use pace::{reading_days, ReadingPace, WeeklyStreak};
+pub use poll::{Nomination, Poll, PollMethod, Tally, MAX_NOMINATIONS};
+pub use preferences::Preferences;
+pub use reaction::{Reaction, ReactionCounts, ReactionKind};
+pub use recommendation::Recommendation;
+pub use routing::{RouteCondition, RoutingRule};
+pub use stats::{LeaderboardMetric, ReadingStats, Standing};
 pub use user::User;
+pub use want_to_read::WantToRead;
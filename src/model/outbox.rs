@@ -0,0 +1,259 @@
+use chrono::offset::Utc;
+use chrono::NaiveDate;
+use sqlx::sqlite::SqlitePool;
+
+use super::book::DATE_FORMAT;
+use crate::model::{Book, User};
+
+const READ_AT_FORMAT: &str = "%Y-%m-%d";
+
+/// How long an announcement that keeps failing to post is retried after its quiet hours end
+const RETRY_FOR_DAYS: i64 = 2;
+
+/// An announcement held back until the guild's quiet hours are over
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboxEntry {
+    pub id: i64,
+    pub user_id: i64,
+    pub discord_user_id: i64,
+    pub guild_id: i64,
+    pub book_id: String,
+    pub title: String,
+    pub author: String,
+    pub url: String,
+    pub image_url: String,
+    pub rating: i64,
+    pub review: String,
+    pub shelves: String,
+    pub pages: Option<i64>,
+    /// When the book was shelved, as Goodreads wrote it in the feed
+    pub completed: String,
+    pub read_at: Option<String>,
+    pub added_at: Option<String>,
+    pub queued_at: i64,
+    pub release_at: i64,
+}
+
+impl OutboxEntry {
+    fn from_book(user: &User, book: &Book, release_at: i64) -> Self {
+        Self {
+            id: 0,
+            user_id: user.id,
+            discord_user_id: user.discord_user_id,
+            guild_id: user.discord_guild_id,
+            book_id: book.id().to_string(),
+            title: book.title().to_string(),
+            author: book.author().to_string(),
+            url: book.url().to_string(),
+            image_url: book.image().to_string(),
+            rating: book.rating() as i64,
+            review: book.review().to_string(),
+            shelves: book.shelves().join(","),
+            pages: book.pages().map(i64::from),
+            completed: book.completed().format(DATE_FORMAT).to_string(),
+            read_at: book
+                .read_at()
+                .map(|read_at| read_at.format(READ_AT_FORMAT).to_string()),
+            added_at: book
+                .added_on()
+                .map(|added_on| added_on.format(READ_AT_FORMAT).to_string()),
+            queued_at: Utc::now().timestamp(),
+            release_at,
+        }
+    }
+
+    #[tracing::instrument(name = "Holding announcement in outbox", skip(pool))]
+    pub async fn hold(
+        pool: &SqlitePool,
+        user: &User,
+        book: &Book,
+        release_at: i64,
+    ) -> anyhow::Result<()> {
+        Self::from_book(user, book, release_at).insert(pool).await
+    }
+
+    /// Return a claimed entry to the outbox, to be released next time round
+    #[tracing::instrument(name = "Putting announcement back in outbox", skip(pool))]
+    pub async fn put_back(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        self.insert(pool).await
+    }
+
+    async fn insert(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO outbox (user_id, discord_user_id, guild_id, book_id, title, author, url, image_url, rating, review, shelves, pages, completed, read_at, added_at, queued_at, release_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            self.user_id,
+            self.discord_user_id,
+            self.guild_id,
+            self.book_id,
+            self.title,
+            self.author,
+            self.url,
+            self.image_url,
+            self.rating,
+            self.review,
+            self.shelves,
+            self.pages,
+            self.completed,
+            self.read_at,
+            self.added_at,
+            self.queued_at,
+            self.release_at,
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    /// Everything whose quiet hours have ended, oldest first
+    #[tracing::instrument(name = "Retrieving due outbox entries", skip(pool))]
+    pub async fn due(pool: &SqlitePool, now: i64) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let entries = sqlx::query!(
+            r#"
+            SELECT id as "id!", user_id, discord_user_id, guild_id, book_id, title, author, url, image_url, rating,
+                review, shelves, pages, completed, read_at, added_at, queued_at, release_at
+            FROM outbox
+            WHERE release_at <= ?
+            ORDER BY queued_at
+            "#,
+            now
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| Self {
+            id: row.id,
+            user_id: row.user_id,
+            discord_user_id: row.discord_user_id,
+            guild_id: row.guild_id,
+            book_id: row.book_id,
+            title: row.title,
+            author: row.author,
+            url: row.url,
+            image_url: row.image_url,
+            rating: row.rating,
+            review: row.review,
+            shelves: row.shelves,
+            pages: row.pages,
+            completed: row.completed,
+            read_at: row.read_at,
+            added_at: row.added_at,
+            queued_at: row.queued_at,
+            release_at: row.release_at,
+        })
+        .collect();
+
+        Ok(entries)
+    }
+
+    /// Whether the entry has been failing to post for so long it should be given up on
+    pub fn expired(&self, now: i64) -> bool {
+        now - self.release_at > RETRY_FOR_DAYS * 24 * 60 * 60
+    }
+
+    /// The book as the crawler found it, or `None` if the stored dates can't be read back
+    pub fn book(&self) -> Option<Book> {
+        let read_on = self
+            .read_at
+            .as_deref()
+            .and_then(|read_at| NaiveDate::parse_from_str(read_at, READ_AT_FORMAT).ok());
+        let added_on = self
+            .added_at
+            .as_deref()
+            .and_then(|added_at| NaiveDate::parse_from_str(added_at, READ_AT_FORMAT).ok());
+        Book::new(
+            &self.title,
+            &self.url,
+            &self.completed,
+            &self.book_id,
+            self.rating as usize,
+            &self.author,
+            &self.image_url,
+        )
+        .map(|book| {
+            book.with_review(&self.review)
+                .with_shelves(&self.shelves)
+                .with_pages(self.pages.map(|pages| pages as u32))
+                .with_read_on(read_on)
+                .with_added_on(added_on)
+        })
+    }
+
+    /// Take the entry out of the outbox before releasing it, so it can only be released once.
+    /// Returns false when it was already taken.
+    #[tracing::instrument(name = "Claiming outbox entry", skip(pool))]
+    pub async fn claim(&self, pool: &SqlitePool) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+        let claimed = sqlx::query!(r#"DELETE FROM outbox WHERE id = ?"#, self.id)
+            .execute(&mut conn)
+            .await?
+            .rows_affected();
+        Ok(claimed > 0)
+    }
+
+    #[tracing::instrument(name = "Removing outbox entry", skip(pool))]
+    pub async fn delete(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(r#"DELETE FROM outbox WHERE id = ?"#, self.id)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Book, OutboxEntry, User};
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+    use claim::assert_some;
+
+    fn book() -> Book {
+        assert_some!(Book::new(
+            "The Dispossessed",
+            "https://www.goodreads.com/book/show/13651",
+            "Fri, 26 Aug 2022 22:41:09 -0700",
+            "13651",
+            5,
+            "Ursula K. Le Guin",
+            "https://i.gr-assets.com/images/S/compressed.photo.goodreads.com/books/1.jpg",
+        ))
+    }
+
+    #[test]
+    fn held_books_come_back_out_of_the_outbox_unchanged() {
+        let book = book()
+            .with_review("Anarchists on the moon")
+            .with_shelves("sci-fi, favourites")
+            .with_pages(Some(387))
+            .with_read_on(Some(NaiveDate::from_ymd(2022, 8, 25)))
+            .with_added_on(Some(NaiveDate::from_ymd(2022, 8, 19)));
+        let user = User::new(1, 2, 3, 4, None, 0, None);
+
+        let released = assert_some!(OutboxEntry::from_book(&user, &book, 0).book());
+
+        assert_eq!(released.id(), book.id());
+        assert_eq!(released.completed(), book.completed());
+        assert_eq!(released.review(), book.review());
+        assert_eq!(released.shelves(), book.shelves());
+        assert_eq!(released.pages(), book.pages());
+        assert_eq!(released.read_on(Tz::UTC), book.read_on(Tz::UTC));
+        assert_eq!(released.added_on(), book.added_on());
+    }
+
+    #[test]
+    fn entries_are_only_given_up_on_days_after_their_release() {
+        let user = User::new(1, 2, 3, 4, None, 0, None);
+        let release_at = 1_661_583_600;
+        let entry = OutboxEntry::from_book(&user, &book(), release_at);
+
+        assert!(!entry.expired(release_at + 60 * 60));
+        assert!(!entry.expired(release_at + 2 * 24 * 60 * 60));
+        assert!(entry.expired(release_at + 3 * 24 * 60 * 60));
+    }
+}
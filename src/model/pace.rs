@@ -0,0 +1,239 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use sqlx::sqlite::SqlitePool;
+use std::collections::BTreeSet;
+
+use crate::model::HistoryEntry;
+
+const WEEK_FORMAT: &str = "%Y-%m-%d";
+
+/// How long a member took over their books, from the day each went on their shelves to the day
+/// they finished it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadingPace {
+    /// Only books that can be timed
+    pub books: usize,
+    pub days_per_book: f64,
+    /// Only timed books Goodreads knew the page count of
+    pub pages_per_day: Option<f64>,
+}
+
+/// How many weeks in a row, Monday to Sunday, a member has finished at least one book
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeeklyStreak {
+    pub weeks: usize,
+    /// Nothing finished yet this week, so the streak ends on Sunday unless they do
+    pub at_risk: bool,
+}
+
+/// Days between shelving a book and finishing it. Books added straight to the read shelf, or
+/// finished before they were shelved, can't be timed.
+pub fn reading_days(added_on: Option<NaiveDate>, read_on: NaiveDate) -> Option<i64> {
+    added_on
+        .map(|added_on| (read_on - added_on).num_days())
+        .filter(|days| *days > 0)
+}
+
+impl ReadingPace {
+    pub fn new(entries: &[HistoryEntry]) -> Option<Self> {
+        let timed: Vec<(i64, Option<i64>)> = entries
+            .iter()
+            .filter_map(|entry| {
+                reading_days(entry.added_on, entry.read_on).map(|days| (days, entry.pages))
+            })
+            .collect();
+        if timed.is_empty() {
+            return None;
+        }
+
+        let days: i64 = timed.iter().map(|(days, _)| days).sum();
+        let (paged_days, pages) = timed
+            .iter()
+            .filter_map(|(days, pages)| pages.map(|pages| (*days, pages)))
+            .fold((0, 0), |(total_days, total_pages), (days, pages)| {
+                (total_days + days, total_pages + pages)
+            });
+        let pages_per_day = if paged_days > 0 {
+            Some(pages as f64 / paged_days as f64)
+        } else {
+            None
+        };
+
+        Some(Self {
+            books: timed.len(),
+            days_per_book: days as f64 / timed.len() as f64,
+            pages_per_day,
+        })
+    }
+}
+
+impl WeeklyStreak {
+    /// The streak running up to `today`. A week with nothing finished yet doesn't break it until
+    /// the week is over.
+    pub fn current<I: IntoIterator<Item = NaiveDate>>(dates: I, today: NaiveDate) -> Self {
+        let weeks = weeks_of(dates);
+        let this_week = week_of(today);
+        let at_risk = !weeks.contains(&this_week);
+        let mut week = if at_risk {
+            this_week - Duration::weeks(1)
+        } else {
+            this_week
+        };
+        let mut streak = 0;
+        while weeks.contains(&week) {
+            streak += 1;
+            week -= Duration::weeks(1);
+        }
+
+        Self {
+            weeks: streak,
+            at_risk: at_risk && streak > 0,
+        }
+    }
+
+    /// The most weeks in a row the member has ever kept up
+    pub fn longest<I: IntoIterator<Item = NaiveDate>>(dates: I) -> usize {
+        let mut longest = 0;
+        let mut current = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for week in weeks_of(dates) {
+            current = match previous {
+                Some(previous) if week - previous == Duration::weeks(1) => current + 1,
+                _ => 1,
+            };
+            longest = longest.max(current);
+            previous = Some(week);
+        }
+        longest
+    }
+
+    /// Note that the member was warned about their streak in the week of `today`. Returns false
+    /// when they already were.
+    #[tracing::instrument(name = "Recording streak reminder", skip(pool))]
+    pub async fn record_reminder(
+        pool: &SqlitePool,
+        user_id: i64,
+        today: NaiveDate,
+    ) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+        let week_of = week_of(today).format(WEEK_FORMAT).to_string();
+        let recorded = sqlx::query!(
+            r#"
+            INSERT INTO streak_reminders (user_id, week_of)
+            VALUES (?, ?)
+            ON CONFLICT (user_id) DO UPDATE SET week_of = excluded.week_of
+            WHERE week_of != excluded.week_of
+            "#,
+            user_id,
+            week_of
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(recorded > 0)
+    }
+}
+
+/// The Monday starting the date's week
+fn week_of(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn weeks_of<I: IntoIterator<Item = NaiveDate>>(dates: I) -> BTreeSet<NaiveDate> {
+    dates.into_iter().map(week_of).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{reading_days, HistoryEntry, ReadingPace, WeeklyStreak};
+    use chrono::{Duration, NaiveDate};
+    use claim::{assert_none, assert_some};
+
+    #[test]
+    fn books_shelved_as_read_cannot_be_timed() {
+        let read_on = NaiveDate::from_ymd(2022, 7, 12);
+
+        assert_eq!(
+            reading_days(Some(NaiveDate::from_ymd(2022, 7, 8)), read_on),
+            Some(4)
+        );
+        assert_none!(reading_days(Some(read_on), read_on));
+        assert_none!(reading_days(Some(read_on + Duration::days(1)), read_on));
+        assert_none!(reading_days(None, read_on));
+    }
+
+    #[test]
+    fn pace_averages_over_timed_books() {
+        let read_on = NaiveDate::from_ymd(2022, 7, 12);
+        let entries = vec![
+            HistoryEntry::test_read(1, "1")
+                .with_pages(Some(300))
+                .with_added_on(Some(read_on - Duration::days(2)))
+                .with_read_on(read_on),
+            HistoryEntry::test_read(1, "1")
+                .with_pages(None)
+                .with_added_on(Some(read_on - Duration::days(6)))
+                .with_read_on(read_on),
+            HistoryEntry::test_read(1, "1")
+                .with_pages(Some(1000))
+                .with_added_on(None)
+                .with_read_on(read_on),
+        ];
+
+        let pace = assert_some!(ReadingPace::new(&entries));
+        assert_eq!(pace.books, 2);
+        assert_eq!(pace.days_per_book, 4.0);
+        assert_eq!(pace.pages_per_day, Some(150.0));
+        assert_none!(ReadingPace::new(&entries[2..]));
+    }
+
+    #[test]
+    fn streaks_count_weeks_in_a_row() {
+        let monday = NaiveDate::from_ymd(2022, 1, 3);
+        let dates = vec![
+            // Sunday and Monday are in different weeks
+            monday - Duration::days(1),
+            monday,
+            monday + Duration::days(6),
+            monday + Duration::weeks(1),
+            monday + Duration::weeks(2),
+            // A week off starts over
+            monday + Duration::weeks(4),
+        ];
+
+        assert_eq!(WeeklyStreak::longest(dates), 4);
+        assert_eq!(WeeklyStreak::longest(Vec::new()), 0);
+    }
+
+    #[test]
+    fn current_streaks_survive_until_the_week_is_out() {
+        let monday = NaiveDate::from_ymd(2022, 1, 3);
+        let dates = vec![
+            monday - Duration::weeks(2),
+            monday - Duration::weeks(1),
+            monday + Duration::days(1),
+        ];
+
+        assert_eq!(
+            WeeklyStreak::current(dates.clone(), monday + Duration::days(6)),
+            WeeklyStreak {
+                weeks: 3,
+                at_risk: false
+            }
+        );
+        assert_eq!(
+            WeeklyStreak::current(dates[..2].to_vec(), monday + Duration::days(6)),
+            WeeklyStreak {
+                weeks: 2,
+                at_risk: true
+            }
+        );
+        assert_eq!(
+            WeeklyStreak::current(dates[..2].to_vec(), monday + Duration::weeks(1)),
+            WeeklyStreak {
+                weeks: 0,
+                at_risk: false
+            }
+        );
+    }
+}
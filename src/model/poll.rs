@@ -0,0 +1,648 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use chrono::offset::Utc;
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use sqlx::sqlite::SqlitePool;
+
+/// Discord select menus hold at most 25 options, so that's as many books as a poll can offer
+pub const MAX_NOMINATIONS: usize = 25;
+
+/// How a poll's votes are counted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PollMethod {
+    /// Members pick every book they'd be happy with, and the most picked wins
+    Approval,
+    /// Members rank their favourites, and the least popular book is knocked out round by
+    /// round, passing its votes on to each voter's next choice, until one has a majority
+    Ranked,
+}
+
+impl PollMethod {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "approval" => Some(PollMethod::Approval),
+            "ranked" => Some(PollMethod::Ranked),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PollMethod::Approval => "approval",
+            PollMethod::Ranked => "ranked",
+        }
+    }
+}
+
+/// A guild choosing its next book. Members nominate books until whoever opened the poll starts
+/// the voting, which runs until `closes_at`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poll {
+    pub id: i64,
+    pub guild_id: i64,
+    pub opened_by: i64,
+    pub opened_at: i64,
+    pub method: PollMethod,
+    /// `None` while members are still nominating
+    pub closes_at: Option<i64>,
+    /// When set, the winner starts a group read that has to be finished by then
+    pub club_deadline: Option<i64>,
+    pub closed: bool,
+}
+
+/// A book put forward in a poll
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nomination {
+    pub book_id: String,
+    pub title: String,
+    pub author: String,
+    pub url: String,
+    pub image_url: String,
+    pub nominated_by: i64,
+    pub nominated_at: i64,
+}
+
+/// How the votes were counted
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tally {
+    /// Votes for each book still in the running, most first, round by round. Approval polls
+    /// only take one round.
+    pub rounds: Vec<Vec<(String, usize)>>,
+    /// `None` when nobody voted
+    pub winner: Option<String>,
+}
+
+impl Poll {
+    /// The guild's open poll, opening one for `discord_user_id` when there isn't one
+    #[tracing::instrument(name = "Opening poll", skip(pool))]
+    pub async fn open(
+        pool: &SqlitePool,
+        guild_id: i64,
+        discord_user_id: i64,
+    ) -> anyhow::Result<Self> {
+        let mut conn = pool.acquire().await?;
+        let opened_at = Utc::now().timestamp();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO polls (guild_id, opened_by, opened_at)
+            VALUES (?, ?, ?)
+            ON CONFLICT DO NOTHING
+            "#,
+            guild_id,
+            discord_user_id,
+            opened_at
+        )
+        .execute(&mut conn)
+        .await?;
+        drop(conn);
+
+        Self::current(pool, guild_id)
+            .await?
+            .ok_or_else(|| anyhow!("No open poll for guild {} after opening one", guild_id))
+    }
+
+    /// The guild's poll, until it closes
+    #[tracing::instrument(name = "Retrieving current poll", skip(pool))]
+    pub async fn current(pool: &SqlitePool, guild_id: i64) -> anyhow::Result<Option<Self>> {
+        let mut conn = pool.acquire().await?;
+        let poll = sqlx::query_as!(
+            PollRow,
+            r#"
+            SELECT id as "id!", guild_id, opened_by, opened_at, method, closes_at, club_deadline, closed
+            FROM polls
+            WHERE guild_id = ? AND closed = FALSE
+            "#,
+            guild_id
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(Self::from);
+
+        Ok(poll)
+    }
+
+    #[tracing::instrument(name = "Retrieving poll", skip(pool))]
+    pub async fn find(pool: &SqlitePool, id: i64) -> anyhow::Result<Option<Self>> {
+        let mut conn = pool.acquire().await?;
+        let poll = sqlx::query_as!(
+            PollRow,
+            r#"
+            SELECT id as "id!", guild_id, opened_by, opened_at, method, closes_at, club_deadline, closed
+            FROM polls
+            WHERE id = ?
+            "#,
+            id
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(Self::from);
+
+        Ok(poll)
+    }
+
+    /// Every poll whose voting has run out but hasn't been counted yet
+    #[tracing::instrument(name = "Retrieving polls due to close", skip(pool))]
+    pub async fn due(pool: &SqlitePool, now: i64) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let polls = sqlx::query_as!(
+            PollRow,
+            r#"
+            SELECT id as "id!", guild_id, opened_by, opened_at, method, closes_at, club_deadline, closed
+            FROM polls
+            WHERE closed = FALSE AND closes_at <= ?
+            "#,
+            now
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(Self::from)
+        .collect();
+
+        Ok(polls)
+    }
+
+    /// Whether ballots still count at `now`
+    pub fn is_voting(&self, now: i64) -> bool {
+        !self.closed && matches!(self.closes_at, Some(closes_at) if now < closes_at)
+    }
+
+    /// The last day to vote on, in the guild's time zone
+    pub fn last_day(&self, time_zone: Tz) -> Option<NaiveDate> {
+        self.closes_at
+            .map(|closes_at| time_zone.timestamp(closes_at - 1, 0).date().naive_local())
+    }
+
+    /// Returns false when the book was already nominated
+    #[tracing::instrument(name = "Nominating book", skip(pool))]
+    pub async fn nominate(
+        &self,
+        pool: &SqlitePool,
+        nomination: &Nomination,
+    ) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+
+        let nominated = sqlx::query!(
+            r#"
+            INSERT INTO poll_nominations (poll_id, book_id, title, author, url, image_url, nominated_by, nominated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (poll_id, book_id) DO NOTHING
+            "#,
+            self.id,
+            nomination.book_id,
+            nomination.title,
+            nomination.author,
+            nomination.url,
+            nomination.image_url,
+            nomination.nominated_by,
+            nomination.nominated_at
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(nominated > 0)
+    }
+
+    /// The books up for the vote, in the order they were nominated
+    #[tracing::instrument(name = "Retrieving nominations", skip(pool))]
+    pub async fn nominations(&self, pool: &SqlitePool) -> anyhow::Result<Vec<Nomination>> {
+        let mut conn = pool.acquire().await?;
+        let nominations = sqlx::query!(
+            r#"
+            SELECT book_id, title, author, url, image_url, nominated_by, nominated_at
+            FROM poll_nominations
+            WHERE poll_id = ?
+            ORDER BY nominated_at, rowid
+            "#,
+            self.id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| Nomination {
+            book_id: row.book_id,
+            title: row.title,
+            author: row.author,
+            url: row.url,
+            image_url: row.image_url,
+            nominated_by: row.nominated_by,
+            nominated_at: row.nominated_at,
+        })
+        .collect();
+
+        Ok(nominations)
+    }
+
+    /// Close nominations and open the voting. Returns false when voting had already started.
+    #[tracing::instrument(name = "Starting poll voting", skip(pool))]
+    pub async fn start_voting(
+        &self,
+        pool: &SqlitePool,
+        method: PollMethod,
+        closes_at: i64,
+        club_deadline: Option<i64>,
+    ) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+        let method = method.as_str();
+
+        let started = sqlx::query!(
+            r#"
+            UPDATE polls SET method = ?, closes_at = ?, club_deadline = ?
+            WHERE id = ? AND closes_at IS NULL
+            "#,
+            method,
+            closes_at,
+            club_deadline,
+            self.id
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(started > 0)
+    }
+
+    /// Replace the member's approval ballot with `book_ids`
+    #[tracing::instrument(name = "Recording approval vote", skip(pool))]
+    pub async fn approve(
+        &self,
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        book_ids: &[String],
+    ) -> anyhow::Result<()> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
+            r#"DELETE FROM poll_votes WHERE poll_id = ? AND discord_user_id = ?"#,
+            self.id,
+            discord_user_id
+        )
+        .execute(&mut tx)
+        .await?;
+        for book_id in book_ids {
+            sqlx::query!(
+                r#"
+                INSERT INTO poll_votes (poll_id, discord_user_id, book_id, rank)
+                VALUES (?, ?, ?, 1)
+                ON CONFLICT DO NOTHING
+                "#,
+                self.id,
+                discord_user_id,
+                book_id
+            )
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Make `book_id` the member's choice at `rank`, moving it from wherever it was on their
+    /// ballot
+    #[tracing::instrument(name = "Recording ranked vote", skip(pool))]
+    pub async fn rank(
+        &self,
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        book_id: &str,
+        rank: i64,
+    ) -> anyhow::Result<()> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM poll_votes
+            WHERE poll_id = ? AND discord_user_id = ? AND (book_id = ? OR rank = ?)
+            "#,
+            self.id,
+            discord_user_id,
+            book_id,
+            rank
+        )
+        .execute(&mut tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO poll_votes (poll_id, discord_user_id, book_id, rank)
+            VALUES (?, ?, ?, ?)
+            "#,
+            self.id,
+            discord_user_id,
+            book_id,
+            rank
+        )
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The books on the member's ballot, first choice first
+    #[tracing::instrument(name = "Retrieving member's ballot", skip(pool))]
+    pub async fn ballot(
+        &self,
+        pool: &SqlitePool,
+        discord_user_id: i64,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut conn = pool.acquire().await?;
+        let ballot = sqlx::query!(
+            r#"
+            SELECT book_id
+            FROM poll_votes
+            WHERE poll_id = ? AND discord_user_id = ?
+            ORDER BY rank
+            "#,
+            self.id,
+            discord_user_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| row.book_id)
+        .collect();
+
+        Ok(ballot)
+    }
+
+    /// Everyone's ballots, each first choice first
+    #[tracing::instrument(name = "Retrieving ballots", skip(pool))]
+    pub async fn ballots(&self, pool: &SqlitePool) -> anyhow::Result<Vec<Vec<String>>> {
+        let mut conn = pool.acquire().await?;
+        let rows = sqlx::query!(
+            r#"
+            SELECT discord_user_id, book_id
+            FROM poll_votes
+            WHERE poll_id = ?
+            ORDER BY discord_user_id, rank
+            "#,
+            self.id
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let mut ballots: Vec<(i64, Vec<String>)> = Vec::new();
+        for row in rows {
+            match ballots.last_mut() {
+                Some((voter, ballot)) if *voter == row.discord_user_id => ballot.push(row.book_id),
+                _ => ballots.push((row.discord_user_id, vec![row.book_id])),
+            }
+        }
+        Ok(ballots.into_iter().map(|(_, ballot)| ballot).collect())
+    }
+
+    /// Stop counting ballots once the result is posted, which lets the guild open another poll
+    #[tracing::instrument(name = "Closing poll", skip(pool))]
+    pub async fn close(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+
+        sqlx::query!(r#"UPDATE polls SET closed = TRUE WHERE id = ?"#, self.id)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Call the poll off, nominations and votes included
+    #[tracing::instrument(name = "Cancelling poll", skip(pool))]
+    pub async fn cancel(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(r#"DELETE FROM poll_votes WHERE poll_id = ?"#, self.id)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query!(r#"DELETE FROM poll_nominations WHERE poll_id = ?"#, self.id)
+            .execute(&mut tx)
+            .await?;
+        sqlx::query!(r#"DELETE FROM polls WHERE id = ?"#, self.id)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Count `ballots` the poll's way. Ties go to the book nominated first.
+    pub fn tally(&self, nominations: &[Nomination], ballots: &[Vec<String>]) -> Tally {
+        let candidates: Vec<&str> = nominations
+            .iter()
+            .map(|nomination| nomination.book_id.as_str())
+            .collect();
+        match self.method {
+            PollMethod::Approval => approval_tally(&candidates, ballots),
+            PollMethod::Ranked => instant_runoff(&candidates, ballots),
+        }
+    }
+}
+
+fn approval_tally(candidates: &[&str], ballots: &[Vec<String>]) -> Tally {
+    let standings = count(candidates, ballots, |ballot| {
+        let approved: HashSet<&str> = ballot.iter().map(String::as_str).collect();
+        approved.into_iter().collect()
+    });
+    let winner = leader(&standings);
+    Tally {
+        rounds: vec![standings],
+        winner,
+    }
+}
+
+fn instant_runoff(candidates: &[&str], ballots: &[Vec<String>]) -> Tally {
+    let mut remaining: Vec<&str> = candidates.to_vec();
+    let mut rounds = Vec::new();
+    loop {
+        let standings = count(&remaining, ballots, |ballot| {
+            ballot
+                .iter()
+                .map(String::as_str)
+                .find(|book_id| remaining.contains(book_id))
+                .into_iter()
+                .collect()
+        });
+        // Ballots whose choices have all been knocked out don't count towards the majority
+        let active: usize = standings.iter().map(|(_, votes)| votes).sum();
+        let decided = match standings.first() {
+            Some((_, votes)) => active == 0 || *votes * 2 > active || standings.len() <= 2,
+            None => true,
+        };
+        if decided {
+            let winner = leader(&standings);
+            rounds.push(standings);
+            return Tally { rounds, winner };
+        }
+        // The stable sort leaves the latest nomination last among the least voted for
+        if let Some((knocked_out, _)) = standings.last() {
+            remaining.retain(|book_id| *book_id != knocked_out.as_str());
+        }
+        rounds.push(standings);
+    }
+}
+
+/// Votes for each candidate, most first and otherwise in nomination order, from the books
+/// `choices` picks out of each ballot
+fn count<'a, F>(candidates: &[&str], ballots: &'a [Vec<String>], choices: F) -> Vec<(String, usize)>
+where
+    F: Fn(&'a Vec<String>) -> Vec<&'a str>,
+{
+    let mut standings: Vec<(String, usize)> = candidates
+        .iter()
+        .map(|book_id| (book_id.to_string(), 0))
+        .collect();
+    for ballot in ballots {
+        for choice in choices(ballot) {
+            if let Some((_, votes)) = standings.iter_mut().find(|(book_id, _)| book_id == choice) {
+                *votes += 1;
+            }
+        }
+    }
+    standings.sort_by_key(|(_, votes)| Reverse(*votes));
+    standings
+}
+
+fn leader(standings: &[(String, usize)]) -> Option<String> {
+    standings
+        .first()
+        .filter(|(_, votes)| *votes > 0)
+        .map(|(book_id, _)| book_id.clone())
+}
+
+/// A `polls` row as it's stored, before the counting method is parsed
+struct PollRow {
+    id: i64,
+    guild_id: i64,
+    opened_by: i64,
+    opened_at: i64,
+    method: String,
+    closes_at: Option<i64>,
+    club_deadline: Option<i64>,
+    closed: bool,
+}
+
+impl From<PollRow> for Poll {
+    fn from(row: PollRow) -> Self {
+        Self {
+            id: row.id,
+            guild_id: row.guild_id,
+            opened_by: row.opened_by,
+            opened_at: row.opened_at,
+            method: PollMethod::parse(&row.method).unwrap_or(PollMethod::Approval),
+            closes_at: row.closes_at,
+            club_deadline: row.club_deadline,
+            closed: row.closed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Nomination, Poll, PollMethod};
+
+    fn poll(method: PollMethod) -> Poll {
+        Poll {
+            id: 0,
+            guild_id: 0,
+            opened_by: 0,
+            opened_at: 0,
+            method,
+            closes_at: Some(0),
+            club_deadline: None,
+            closed: false,
+        }
+    }
+
+    fn nominations(book_ids: &[&str]) -> Vec<Nomination> {
+        book_ids
+            .iter()
+            .map(|book_id| Nomination {
+                book_id: book_id.to_string(),
+                title: format!("Book {}", book_id),
+                author: "Ursula K. Le Guin".to_string(),
+                url: format!("https://www.goodreads.com/book/show/{}", book_id),
+                image_url: String::new(),
+                nominated_by: 0,
+                nominated_at: 0,
+            })
+            .collect()
+    }
+
+    fn ballots(ballots: &[&[&str]]) -> Vec<Vec<String>> {
+        ballots
+            .iter()
+            .map(|ballot| ballot.iter().map(|book_id| book_id.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn approval_polls_go_to_the_most_approved_book() {
+        let tally = poll(PollMethod::Approval).tally(
+            &nominations(&["a", "b", "c"]),
+            &ballots(&[&["a", "b"], &["b"], &["c", "b", "a"]]),
+        );
+
+        assert_eq!(tally.winner.as_deref(), Some("b"));
+        assert_eq!(
+            tally.rounds,
+            vec![vec![
+                ("b".to_string(), 3),
+                ("a".to_string(), 2),
+                ("c".to_string(), 1)
+            ]]
+        );
+    }
+
+    #[test]
+    fn ties_go_to_the_first_nomination() {
+        let tally = poll(PollMethod::Approval)
+            .tally(&nominations(&["a", "b"]), &ballots(&[&["b"], &["a"]]));
+
+        assert_eq!(tally.winner.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn ranked_polls_pass_knocked_out_votes_on() {
+        let tally = poll(PollMethod::Ranked).tally(
+            &nominations(&["a", "b", "c"]),
+            &ballots(&[
+                &["a"],
+                &["a", "c"],
+                &["a", "b"],
+                &["a"],
+                &["b", "a"],
+                &["b"],
+                &["b"],
+                &["c", "b"],
+                &["c", "b"],
+            ]),
+        );
+
+        // a leads the first round 4 to 3 to 2, but c's voters would rather have b
+        assert_eq!(tally.rounds.len(), 2);
+        assert_eq!(
+            tally.rounds[1],
+            vec![("b".to_string(), 5), ("a".to_string(), 4)]
+        );
+        assert_eq!(tally.winner.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn ranked_polls_stop_at_a_majority() {
+        let tally = poll(PollMethod::Ranked).tally(
+            &nominations(&["a", "b", "c"]),
+            &ballots(&[&["a", "b"], &["a"], &["b", "c"]]),
+        );
+
+        assert_eq!(tally.rounds.len(), 1);
+        assert_eq!(tally.winner.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn polls_nobody_voted_in_have_no_winner() {
+        for method in [PollMethod::Approval, PollMethod::Ranked] {
+            let tally = poll(method).tally(&nominations(&["a", "b", "c"]), &[]);
+
+            assert_eq!(tally.winner, None);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::anyhow;
+use sqlx::sqlite::SqlitePool;
+
+use crate::model::Book;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preferences {
+    pub user_id: i64,
+    pub min_rating: i64,
+    pub include_review: bool,
+    pub mention: bool,
+    pub hide_unrated: bool,
+    pub skip_shelves: Vec<String>,
+    /// Whether other members may follow this user's reading by DM
+    pub allow_followers: bool,
+    /// Whether the member gets their own year-in-review by DM
+    pub recap: bool,
+    /// Whether announcements say how long the book took and how the member's weekly streak is going
+    pub pace: bool,
+    /// Whether the member's Goodreads to-read shelf is added to their want to read list
+    pub sync_want_to_read: bool,
+}
+
+impl Preferences {
+    /// The preferences every user starts with: announce everything, the same way it always has
+    pub fn default_for(user_id: i64) -> Self {
+        Self {
+            user_id,
+            min_rating: 0,
+            include_review: false,
+            mention: false,
+            hide_unrated: false,
+            skip_shelves: Vec::new(),
+            allow_followers: true,
+            recap: false,
+            pace: false,
+            sync_want_to_read: false,
+        }
+    }
+
+    #[tracing::instrument(name = "Retrieving user preferences", skip(pool))]
+    pub async fn get(pool: &SqlitePool, user_id: i64) -> anyhow::Result<Self> {
+        let mut conn = pool.acquire().await?;
+        let res = sqlx::query!(
+            r#"SELECT * FROM user_preferences WHERE user_id = ?"#,
+            user_id
+        )
+        .fetch_one(&mut conn)
+        .await;
+
+        match res {
+            Ok(row) => Ok(Self {
+                user_id: row.user_id,
+                min_rating: row.min_rating,
+                include_review: row.include_review,
+                mention: row.mention,
+                hide_unrated: row.hide_unrated,
+                skip_shelves: parse_shelves(&row.skip_shelves),
+                allow_followers: row.allow_followers,
+                recap: row.recap,
+                pace: row.pace,
+                sync_want_to_read: row.sync_want_to_read,
+            }),
+            Err(sqlx::Error::RowNotFound) => Ok(Self::default_for(user_id)),
+            Err(e) => Err(anyhow!(e)),
+        }
+    }
+
+    #[tracing::instrument(name = "Saving user preferences", skip(pool))]
+    pub async fn save(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let skip_shelves = self.skip_shelves.join(",");
+
+        sqlx::query!(
+            r#"
+            INSERT INTO user_preferences (user_id, min_rating, include_review, mention, hide_unrated, skip_shelves, allow_followers, recap, pace, sync_want_to_read)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (user_id) DO UPDATE SET
+                min_rating = excluded.min_rating,
+                include_review = excluded.include_review,
+                mention = excluded.mention,
+                hide_unrated = excluded.hide_unrated,
+                skip_shelves = excluded.skip_shelves,
+                allow_followers = excluded.allow_followers,
+                recap = excluded.recap,
+                pace = excluded.pace,
+                sync_want_to_read = excluded.sync_want_to_read
+            "#,
+            self.user_id,
+            self.min_rating,
+            self.include_review,
+            self.mention,
+            self.hide_unrated,
+            skip_shelves,
+            self.allow_followers,
+            self.recap,
+            self.pace,
+            self.sync_want_to_read,
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Discord ids of the guild's members who want their own year-in-review
+    #[tracing::instrument(name = "Retrieving members with recaps", skip(pool))]
+    pub async fn recap_members(pool: &SqlitePool, guild_id: i64) -> anyhow::Result<Vec<i64>> {
+        let mut conn = pool.acquire().await?;
+        let members = sqlx::query!(
+            r#"
+            SELECT users.discord_user_id FROM users
+            JOIN user_preferences ON user_preferences.user_id = users.id
+            WHERE users.discord_guild_id = ? AND user_preferences.recap = TRUE
+            "#,
+            guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| row.discord_user_id)
+        .collect();
+
+        Ok(members)
+    }
+
+    /// Discord ids of the guild's members who want to hear about their reading pace
+    #[tracing::instrument(name = "Retrieving members with pace", skip(pool))]
+    pub async fn pace_members(pool: &SqlitePool, guild_id: i64) -> anyhow::Result<Vec<i64>> {
+        let mut conn = pool.acquire().await?;
+        let members = sqlx::query!(
+            r#"
+            SELECT users.discord_user_id FROM users
+            JOIN user_preferences ON user_preferences.user_id = users.id
+            WHERE users.discord_guild_id = ? AND user_preferences.pace = TRUE
+            "#,
+            guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| row.discord_user_id)
+        .collect();
+
+        Ok(members)
+    }
+
+    /// Whether a book with the given rating and shelves may be shared with the server.
+    /// A rating of 0 means the book was never rated on Goodreads.
+    pub fn allows(&self, rating: usize, shelves: &[String]) -> bool {
+        if shelves
+            .iter()
+            .any(|shelf| self.skip_shelves.contains(shelf))
+        {
+            return false;
+        }
+        if rating == 0 {
+            return !self.hide_unrated;
+        }
+        (rating as i64) >= self.min_rating
+    }
+
+    pub fn should_announce(&self, book: &Book) -> bool {
+        self.allows(book.rating(), book.shelves())
+    }
+
+    pub fn set_skip_shelves(&mut self, shelves: &str) {
+        self.skip_shelves = parse_shelves(shelves);
+    }
+}
+
+fn parse_shelves(shelves: &str) -> Vec<String> {
+    shelves
+        .split(',')
+        .map(|shelf| shelf.trim().to_lowercase())
+        .filter(|shelf| !shelf.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::Preferences;
+
+    #[test]
+    fn default_preferences_allow_everything() {
+        let prefs = Preferences::default_for(1);
+        assert!(prefs.allows(0, &[]));
+        assert!(prefs.allows(1, &["guilty-pleasures".to_string()]));
+        assert!(prefs.allows(5, &[]));
+    }
+
+    #[test]
+    fn ratings_below_the_minimum_are_not_allowed() {
+        let mut prefs = Preferences::default_for(1);
+        prefs.min_rating = 3;
+        assert!(!prefs.allows(1, &[]));
+        assert!(!prefs.allows(2, &[]));
+        assert!(prefs.allows(3, &[]));
+        assert!(prefs.allows(5, &[]));
+    }
+
+    #[test]
+    fn unrated_books_are_only_hidden_when_asked() {
+        let mut prefs = Preferences::default_for(1);
+        prefs.min_rating = 3;
+        assert!(prefs.allows(0, &[]));
+        prefs.hide_unrated = true;
+        assert!(!prefs.allows(0, &[]));
+    }
+
+    #[test]
+    fn skipped_shelves_are_matched_case_insensitively() {
+        let mut prefs = Preferences::default_for(1);
+        prefs.set_skip_shelves("Guilty-Pleasures, romance");
+        assert_eq!(prefs.skip_shelves, vec!["guilty-pleasures", "romance"]);
+        assert!(!prefs.allows(4, &["guilty-pleasures".to_string()]));
+        assert!(prefs.allows(4, &["sci-fi".to_string()]));
+    }
+
+    #[test]
+    fn skipped_shelves_hide_unrated_books_too() {
+        let mut prefs = Preferences::default_for(1);
+        prefs.set_skip_shelves("guilty-pleasures");
+        assert!(!prefs.allows(0, &["guilty-pleasures".to_string()]));
+        assert!(prefs.allows(0, &["sci-fi".to_string()]));
+    }
+}
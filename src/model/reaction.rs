@@ -0,0 +1,156 @@
+use chrono::offset::Utc;
+use sqlx::sqlite::SqlitePool;
+
+/// The buttons under an announcement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReactionKind {
+    Congrats,
+    WantToRead,
+    MeToo,
+}
+
+impl ReactionKind {
+    pub const ALL: [ReactionKind; 3] = [
+        ReactionKind::Congrats,
+        ReactionKind::WantToRead,
+        ReactionKind::MeToo,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|kind| kind.as_str() == value)
+            .copied()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReactionKind::Congrats => "congrats",
+            ReactionKind::WantToRead => "want_to_read",
+            ReactionKind::MeToo => "me_too",
+        }
+    }
+}
+
+/// How many members clicked each button on an announcement
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReactionCounts {
+    pub congrats: i64,
+    pub want_to_read: i64,
+    pub me_too: i64,
+}
+
+impl ReactionCounts {
+    pub fn get(&self, kind: ReactionKind) -> i64 {
+        match kind {
+            ReactionKind::Congrats => self.congrats,
+            ReactionKind::WantToRead => self.want_to_read,
+            ReactionKind::MeToo => self.me_too,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.congrats == 0 && self.want_to_read == 0 && self.me_too == 0
+    }
+
+    #[tracing::instrument(name = "Counting announcement reactions", skip(pool))]
+    pub async fn for_announcement(pool: &SqlitePool, announcement_id: i64) -> anyhow::Result<Self> {
+        let mut conn = pool.acquire().await?;
+        let rows = sqlx::query!(
+            r#"
+            SELECT kind, COUNT(*) as "count!: i64" FROM announcement_reactions
+            WHERE announcement_id = ?
+            GROUP BY kind
+            "#,
+            announcement_id
+        )
+        .fetch_all(&mut conn)
+        .await?;
+
+        let mut counts = Self::default();
+        for row in rows {
+            match ReactionKind::parse(&row.kind) {
+                Some(ReactionKind::Congrats) => counts.congrats = row.count,
+                Some(ReactionKind::WantToRead) => counts.want_to_read = row.count,
+                Some(ReactionKind::MeToo) => counts.me_too = row.count,
+                None => {}
+            }
+        }
+        Ok(counts)
+    }
+}
+
+pub struct Reaction;
+
+impl Reaction {
+    /// Add the member's reaction, or take it back if they already reacted. Returns whether the
+    /// reaction is now there.
+    #[tracing::instrument(name = "Toggling announcement reaction", skip(pool))]
+    pub async fn toggle(
+        pool: &SqlitePool,
+        announcement_id: i64,
+        discord_user_id: i64,
+        kind: ReactionKind,
+    ) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+        let kind = kind.as_str();
+
+        let removed = sqlx::query!(
+            r#"
+            DELETE FROM announcement_reactions
+            WHERE announcement_id = ? AND discord_user_id = ? AND kind = ?
+            "#,
+            announcement_id,
+            discord_user_id,
+            kind
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+        if removed > 0 {
+            return Ok(false);
+        }
+
+        let reacted_at = Utc::now().timestamp();
+        sqlx::query!(
+            r#"
+            INSERT INTO announcement_reactions (announcement_id, discord_user_id, kind, reacted_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+            announcement_id,
+            discord_user_id,
+            kind,
+            reacted_at
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ReactionCounts, ReactionKind};
+    use claim::{assert_none, assert_some};
+
+    #[test]
+    fn reaction_kinds_round_trip_through_their_names() {
+        for kind in ReactionKind::ALL {
+            assert_eq!(assert_some!(ReactionKind::parse(kind.as_str())), kind);
+        }
+        assert_none!(ReactionKind::parse("boo"));
+    }
+
+    #[test]
+    fn counts_are_looked_up_by_kind() {
+        let counts = ReactionCounts {
+            congrats: 3,
+            want_to_read: 0,
+            me_too: 1,
+        };
+        assert_eq!(counts.get(ReactionKind::Congrats), 3);
+        assert_eq!(counts.get(ReactionKind::MeToo), 1);
+        assert!(!counts.is_empty());
+        assert!(ReactionCounts::default().is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{Compatibility, HistoryEntry};
+
+/// Ratings at or above this count as a member vouching for a book
+const LIKED_RATING: i64 = 4;
+
+/// A book similar members loved that the member hasn't read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    /// The first similar member's read of it, for the title and link
+    pub entry: HistoryEntry,
+    pub score: f64,
+    /// Discord ids of the similar members who rated it highly
+    pub recommenders: Vec<i64>,
+}
+
+impl Recommendation {
+    /// Books the guild's readers rated highly, weighted by how compatible each reader is with
+    /// `member`, best first. Anything the member has read, or is in `exclude`, is left out.
+    pub fn for_member(member: i64, entries: &[HistoryEntry], exclude: &HashSet<&str>) -> Vec<Self> {
+        let similarity: HashMap<i64, f64> = Compatibility::with_member(member, entries)
+            .into_iter()
+            .filter(|pair| pair.score > 0)
+            .map(|pair| (pair.second, pair.score as f64 / 100.0))
+            .collect();
+        let read: HashSet<&str> = entries
+            .iter()
+            .filter(|entry| entry.discord_user_id == member)
+            .map(|entry| entry.book_id.as_str())
+            .collect();
+
+        let mut candidates: HashMap<&str, Recommendation> = HashMap::new();
+        for entry in entries {
+            let weight = match similarity.get(&entry.discord_user_id) {
+                Some(weight) => *weight,
+                None => continue,
+            };
+            let book_id = entry.book_id.as_str();
+            if entry.rating < LIKED_RATING || read.contains(book_id) || exclude.contains(book_id) {
+                continue;
+            }
+            let candidate = candidates.entry(book_id).or_insert_with(|| Recommendation {
+                entry: entry.clone(),
+                score: 0.0,
+                recommenders: Vec::new(),
+            });
+            // A re-read doesn't make it twice as good
+            if !candidate.recommenders.contains(&entry.discord_user_id) {
+                // Five stars counts twice as much as four
+                candidate.score += weight * (entry.rating - LIKED_RATING + 1) as f64;
+                candidate.recommenders.push(entry.discord_user_id);
+            }
+        }
+
+        let mut recommendations: Vec<Self> = candidates.into_values().collect();
+        recommendations.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.recommenders.len().cmp(&a.recommenders.len()))
+                .then_with(|| a.entry.title.cmp(&b.entry.title))
+        });
+        recommendations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{HistoryEntry, Recommendation};
+    use std::collections::HashSet;
+
+    fn book_ids(recommendations: &[Recommendation]) -> Vec<&str> {
+        recommendations
+            .iter()
+            .map(|recommendation| recommendation.entry.book_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn similar_members_count_for_more() {
+        let entries = [
+            HistoryEntry::test_read(1, "a").with_rating(5),
+            HistoryEntry::test_read(1, "b").with_rating(2),
+            // Agrees with member 1 on both
+            HistoryEntry::test_read(2, "a").with_rating(5),
+            HistoryEntry::test_read(2, "b").with_rating(2),
+            HistoryEntry::test_read(2, "x").with_rating(4),
+            // Disagrees on both
+            HistoryEntry::test_read(3, "a").with_rating(1),
+            HistoryEntry::test_read(3, "b").with_rating(5),
+            HistoryEntry::test_read(3, "y").with_rating(4),
+        ];
+        let recommendations = Recommendation::for_member(1, &entries, &HashSet::new());
+
+        assert_eq!(book_ids(&recommendations), ["x", "y"]);
+        assert_eq!(recommendations[0].recommenders, [2]);
+    }
+
+    #[test]
+    fn read_excluded_and_poorly_rated_books_are_left_out() {
+        let entries = [
+            HistoryEntry::test_read(1, "a").with_rating(5),
+            HistoryEntry::test_read(2, "a").with_rating(5),
+            HistoryEntry::test_read(2, "b").with_rating(5),
+            HistoryEntry::test_read(2, "c").with_rating(3),
+            HistoryEntry::test_read(2, "d").with_rating(4),
+        ];
+        let exclude: HashSet<&str> = ["b"].into_iter().collect();
+        let recommendations = Recommendation::for_member(1, &entries, &exclude);
+
+        assert_eq!(book_ids(&recommendations), ["d"]);
+    }
+
+    #[test]
+    fn members_with_nothing_in_common_recommend_nothing() {
+        let entries = [
+            HistoryEntry::test_read(1, "a").with_rating(5),
+            HistoryEntry::test_read(2, "b").with_rating(5),
+        ];
+
+        assert!(Recommendation::for_member(1, &entries, &HashSet::new()).is_empty());
+    }
+}
@@ -0,0 +1,261 @@
+use chrono::offset::Utc;
+use serenity::model::prelude::ChannelId;
+use sqlx::sqlite::SqlitePool;
+use std::fmt;
+
+use crate::model::Book;
+
+/// Goodreads' own shelves for books that aren't finished. Only books on the read shelf are ever
+/// announced, so a rule for one of these could never match.
+const UNFINISHED_SHELVES: &[&str] = &["currently-reading", "to-read"];
+
+/// What a book needs for a routing rule to send its announcement somewhere else
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteCondition {
+    /// The book is on this shelf. Goodreads has no genres in the feed, so genre shelves do the job.
+    Shelf(String),
+    /// The book was rated at least this many stars
+    Rating(i64),
+}
+
+impl RouteCondition {
+    pub fn parse(kind: &str, value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        match kind.trim().to_lowercase().as_str() {
+            "shelf" if !value.is_empty() && !value.contains(',') => Some(Self::Shelf(value)),
+            "rating" => value
+                .parse::<i64>()
+                .ok()
+                .filter(|rating| (1..=5).contains(rating))
+                .map(Self::Rating),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RouteCondition::Shelf(_) => "shelf",
+            RouteCondition::Rating(_) => "rating",
+        }
+    }
+
+    pub fn value(&self) -> String {
+        match self {
+            RouteCondition::Shelf(shelf) => shelf.clone(),
+            RouteCondition::Rating(rating) => rating.to_string(),
+        }
+    }
+
+    /// Whether an announced book could ever meet the condition
+    pub fn can_match(&self) -> bool {
+        match self {
+            RouteCondition::Shelf(shelf) => !UNFINISHED_SHELVES.contains(&shelf.as_str()),
+            RouteCondition::Rating(_) => true,
+        }
+    }
+
+    pub fn matches(&self, book: &Book) -> bool {
+        match self {
+            RouteCondition::Shelf(shelf) => book.shelves().contains(shelf),
+            RouteCondition::Rating(rating) => book.rating() as i64 >= *rating,
+        }
+    }
+}
+
+impl fmt::Display for RouteCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteCondition::Shelf(shelf) => write!(f, "on the `{}` shelf", shelf),
+            RouteCondition::Rating(5) => write!(f, "rated ⭐⭐⭐⭐⭐"),
+            RouteCondition::Rating(rating) => {
+                write!(f, "rated {} or more", "⭐".repeat(*rating as usize))
+            }
+        }
+    }
+}
+
+/// Sends announcements for matching books to a channel other than the notification channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutingRule {
+    pub id: i64,
+    pub guild_id: i64,
+    pub condition: RouteCondition,
+    pub channel_id: i64,
+}
+
+impl RoutingRule {
+    pub fn channel(&self) -> ChannelId {
+        ChannelId(self.channel_id as u64)
+    }
+
+    /// The guild's rules in the order they're checked, which is the order they were added
+    #[tracing::instrument(name = "Retrieving routing rules", skip(pool))]
+    pub async fn list(pool: &SqlitePool, guild_id: i64) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let rules = sqlx::query!(
+            r#"
+            SELECT id as "id!", guild_id, kind, value, channel_id
+            FROM routing_rules
+            WHERE guild_id = ?
+            ORDER BY id
+            "#,
+            guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            let condition = RouteCondition::parse(&row.kind, &row.value);
+            if condition.is_none() {
+                tracing::warn!("Ignoring unreadable routing rule {}", row.id);
+            }
+            Some(Self {
+                id: row.id,
+                guild_id: row.guild_id,
+                condition: condition?,
+                channel_id: row.channel_id,
+            })
+        })
+        .collect();
+
+        Ok(rules)
+    }
+
+    /// Add a rule after the guild's existing ones
+    #[tracing::instrument(name = "Adding routing rule", skip(pool))]
+    pub async fn add(
+        pool: &SqlitePool,
+        guild_id: i64,
+        condition: RouteCondition,
+        channel_id: i64,
+    ) -> anyhow::Result<Self> {
+        let mut conn = pool.acquire().await?;
+        let kind = condition.kind();
+        let value = condition.value();
+        let created_at = Utc::now().timestamp();
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO routing_rules (guild_id, kind, value, channel_id, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+            guild_id,
+            kind,
+            value,
+            channel_id,
+            created_at
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(Self {
+            id: result.last_insert_rowid(),
+            guild_id,
+            condition,
+            channel_id,
+        })
+    }
+
+    #[tracing::instrument(name = "Removing routing rule", skip(pool))]
+    pub async fn remove(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"DELETE FROM routing_rules WHERE id = ? AND guild_id = ?"#,
+            self.id,
+            self.guild_id
+        )
+        .execute(&mut conn)
+        .await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "Clearing routing rules", skip(pool))]
+    pub async fn clear(pool: &SqlitePool, guild_id: i64) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(r#"DELETE FROM routing_rules WHERE guild_id = ?"#, guild_id)
+            .execute(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    /// The channel of the first rule the book matches, if any
+    pub fn route(rules: &[Self], book: &Book) -> Option<ChannelId> {
+        rules
+            .iter()
+            .find(|rule| rule.condition.matches(book))
+            .map(|rule| rule.channel())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Book, RouteCondition, RoutingRule};
+    use claim::{assert_none, assert_some};
+    use serenity::model::prelude::ChannelId;
+
+    fn book(rating: usize, shelves: &str) -> Book {
+        assert_some!(Book::new(
+            "Piranesi",
+            "https://www.goodreads.com/book/show/50202953",
+            "Fri, 26 Aug 2022 22:41:09 -0700",
+            "50202953",
+            rating,
+            "Susanna Clarke",
+            "",
+        ))
+        .with_shelves(shelves)
+    }
+
+    fn rule(id: i64, condition: RouteCondition, channel_id: i64) -> RoutingRule {
+        RoutingRule {
+            id,
+            guild_id: 1,
+            condition,
+            channel_id,
+        }
+    }
+
+    #[test]
+    fn conditions_are_parsed_from_a_kind_and_value() {
+        assert_eq!(
+            assert_some!(RouteCondition::parse("shelf", " Book-Club ")),
+            RouteCondition::Shelf("book-club".to_string())
+        );
+        assert_eq!(
+            assert_some!(RouteCondition::parse("Rating", "5")),
+            RouteCondition::Rating(5)
+        );
+        assert_none!(RouteCondition::parse("rating", "6"));
+        assert_none!(RouteCondition::parse("shelf", ""));
+        assert_none!(RouteCondition::parse("genre", "fantasy"));
+    }
+
+    #[test]
+    fn shelves_of_unfinished_books_can_never_match() {
+        assert!(!assert_some!(RouteCondition::parse("shelf", "currently-reading")).can_match());
+        assert!(!assert_some!(RouteCondition::parse("shelf", "To-Read")).can_match());
+        assert!(assert_some!(RouteCondition::parse("shelf", "book-club")).can_match());
+        assert!(RouteCondition::Rating(4).can_match());
+    }
+
+    #[test]
+    fn the_first_matching_rule_wins() {
+        let rules = vec![
+            rule(1, RouteCondition::Shelf("book-club".to_string()), 10),
+            rule(2, RouteCondition::Rating(5), 20),
+        ];
+
+        let both = book(5, "book-club, fantasy");
+        assert_eq!(
+            assert_some!(RoutingRule::route(&rules, &both)),
+            ChannelId(10)
+        );
+        let loved = book(5, "fantasy");
+        assert_eq!(
+            assert_some!(RoutingRule::route(&rules, &loved)),
+            ChannelId(20)
+        );
+        let liked = book(4, "fantasy");
+        assert_none!(RoutingRule::route(&rules, &liked));
+    }
+}
@@ -0,0 +1,379 @@
+use chrono::Datelike;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use crate::model::{HistoryEntry, ReadingPace};
+
+/// How many authors `~stats` lists as favourites
+const FAVOURITE_AUTHORS: usize = 3;
+
+/// How many books make the top-rated list
+const TOP_RATED: usize = 5;
+
+/// A member's reading over some stretch of their history
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReadingStats {
+    pub books: usize,
+    /// Only books Goodreads knew the page count of
+    pub pages: i64,
+    /// Unrated books are left out
+    pub average_rating: Option<f64>,
+    /// The most read authors with how many of their books were finished, most first
+    pub favourite_authors: Vec<(String, usize)>,
+    pub longest: Option<HistoryEntry>,
+    pub shortest: Option<HistoryEntry>,
+    /// The best rated books, each book once however many times it was read
+    pub top_rated: Vec<HistoryEntry>,
+    /// The book finished by the most members, with how many, when more than one read it
+    pub most_shared: Option<(HistoryEntry, usize)>,
+    /// Books given each rating from one star to five
+    pub ratings: [usize; 5],
+    /// Books finished in each month, January first
+    pub monthly: [usize; 12],
+    /// Pages read in each month, January first
+    pub monthly_pages: [i64; 12],
+    /// Only when some of the books can be timed
+    pub pace: Option<ReadingPace>,
+}
+
+impl ReadingStats {
+    pub fn new(entries: &[HistoryEntry]) -> Self {
+        let pages = entries.iter().filter_map(|entry| entry.pages).sum();
+
+        let ratings: Vec<i64> = entries
+            .iter()
+            .map(|entry| entry.rating)
+            .filter(|rating| *rating > 0)
+            .collect();
+        let average_rating = if ratings.is_empty() {
+            None
+        } else {
+            Some(ratings.iter().sum::<i64>() as f64 / ratings.len() as f64)
+        };
+
+        let mut authors: HashMap<&str, usize> = HashMap::new();
+        for entry in entries {
+            *authors.entry(entry.author.as_str()).or_default() += 1;
+        }
+        let mut favourite_authors: Vec<(String, usize)> = authors
+            .into_iter()
+            .map(|(author, books)| (author.to_string(), books))
+            .collect();
+        favourite_authors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        favourite_authors.truncate(FAVOURITE_AUTHORS);
+
+        // The first of equally long books wins, so re-reads don't shuffle the answer
+        let with_pages = entries.iter().filter(|entry| entry.pages.is_some());
+        let longest = with_pages
+            .clone()
+            .fold(
+                None,
+                |longest: Option<&HistoryEntry>, entry| match longest {
+                    Some(longest) if longest.pages >= entry.pages => Some(longest),
+                    _ => Some(entry),
+                },
+            )
+            .cloned();
+        let shortest = with_pages
+            .fold(
+                None,
+                |shortest: Option<&HistoryEntry>, entry| match shortest {
+                    Some(shortest) if shortest.pages <= entry.pages => Some(shortest),
+                    _ => Some(entry),
+                },
+            )
+            .cloned();
+
+        let mut rated: Vec<&HistoryEntry> =
+            entries.iter().filter(|entry| entry.rating > 0).collect();
+        rated.sort_by_key(|entry| (Reverse(entry.rating), entry.read_on));
+        let mut seen = HashSet::new();
+        let top_rated = rated
+            .into_iter()
+            .filter(|entry| seen.insert(entry.book_id.as_str()))
+            .take(TOP_RATED)
+            .cloned()
+            .collect();
+
+        let mut readers: HashMap<&str, (&HistoryEntry, HashSet<i64>)> = HashMap::new();
+        for entry in entries {
+            readers
+                .entry(entry.book_id.as_str())
+                .or_insert_with(|| (entry, HashSet::new()))
+                .1
+                .insert(entry.discord_user_id);
+        }
+        // Ties go to the book that was read first
+        let most_shared = readers
+            .into_values()
+            .map(|(entry, readers)| (entry, readers.len()))
+            .filter(|(_, readers)| *readers > 1)
+            .min_by_key(|(entry, readers)| (Reverse(*readers), entry.read_on, entry.recorded_at))
+            .map(|(entry, readers)| (entry.clone(), readers));
+
+        let mut ratings = [0; 5];
+        for entry in entries
+            .iter()
+            .filter(|entry| (1..=5).contains(&entry.rating))
+        {
+            ratings[entry.rating as usize - 1] += 1;
+        }
+
+        let mut monthly = [0; 12];
+        let mut monthly_pages = [0; 12];
+        for entry in entries {
+            monthly[entry.read_on.month0() as usize] += 1;
+            monthly_pages[entry.read_on.month0() as usize] += entry.pages.unwrap_or_default();
+        }
+
+        Self {
+            books: entries.len(),
+            pages,
+            average_rating,
+            favourite_authors,
+            longest,
+            shortest,
+            top_rated,
+            most_shared,
+            ratings,
+            monthly,
+            monthly_pages,
+            pace: ReadingPace::new(entries),
+        }
+    }
+
+    /// Statistics for the books finished in `year`
+    pub fn for_year(entries: &[HistoryEntry], year: i32) -> Self {
+        let in_year: Vec<HistoryEntry> = entries
+            .iter()
+            .filter(|entry| entry.read_on.year() == year)
+            .cloned()
+            .collect();
+        Self::new(&in_year)
+    }
+}
+
+/// What the leaderboard ranks members by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LeaderboardMetric {
+    Books,
+    Pages,
+}
+
+impl LeaderboardMetric {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "books" => Some(LeaderboardMetric::Books),
+            "pages" => Some(LeaderboardMetric::Pages),
+            _ => None,
+        }
+    }
+}
+
+/// One member's place on the leaderboard
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Standing {
+    pub user_id: i64,
+    pub discord_user_id: i64,
+    pub books: i64,
+    pub pages: i64,
+}
+
+impl Standing {
+    /// Everyone who finished a book in `entries`, best first. Ties are broken by the other metric.
+    pub fn rank(entries: &[HistoryEntry], metric: LeaderboardMetric) -> Vec<Self> {
+        let mut standings: HashMap<i64, Self> = HashMap::new();
+        for entry in entries {
+            let standing = standings.entry(entry.user_id).or_insert(Self {
+                user_id: entry.user_id,
+                discord_user_id: entry.discord_user_id,
+                books: 0,
+                pages: 0,
+            });
+            standing.books += 1;
+            standing.pages += entry.pages.unwrap_or_default();
+        }
+
+        let mut standings: Vec<Self> = standings.into_values().collect();
+        standings.sort_by_key(|standing| {
+            let (first, second) = match metric {
+                LeaderboardMetric::Books => (standing.books, standing.pages),
+                LeaderboardMetric::Pages => (standing.pages, standing.books),
+            };
+            (Reverse(first), Reverse(second), standing.discord_user_id)
+        });
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{HistoryEntry, LeaderboardMetric, ReadingStats, Standing};
+    use chrono::NaiveDate;
+    use claim::{assert_none, assert_some};
+
+    #[test]
+    fn stats_sum_up_a_year_of_reading() {
+        let history = vec![
+            HistoryEntry::test_read(2, "A Wizard of Earthsea")
+                .with_title("A Wizard of Earthsea")
+                .with_author("Ursula K. Le Guin")
+                .with_rating(5)
+                .with_pages(Some(183))
+                .with_read_on(NaiveDate::from_ymd(2022, 1, 9)),
+            HistoryEntry::test_read(2, "The Dispossessed")
+                .with_title("The Dispossessed")
+                .with_author("Ursula K. Le Guin")
+                .with_rating(4)
+                .with_pages(Some(387))
+                .with_read_on(NaiveDate::from_ymd(2022, 3, 2)),
+            HistoryEntry::test_read(2, "Piranesi")
+                .with_title("Piranesi")
+                .with_author("Susanna Clarke")
+                .with_rating(0)
+                .with_pages(Some(245))
+                .with_read_on(NaiveDate::from_ymd(2022, 3, 20)),
+            HistoryEntry::test_read(2, "Gideon the Ninth")
+                .with_title("Gideon the Ninth")
+                .with_author("Tamsyn Muir")
+                .with_rating(3)
+                .with_pages(None)
+                .with_read_on(NaiveDate::from_ymd(2022, 8, 26)),
+            HistoryEntry::test_read(2, "Kindred")
+                .with_title("Kindred")
+                .with_author("Octavia E. Butler")
+                .with_rating(5)
+                .with_pages(Some(264))
+                .with_read_on(NaiveDate::from_ymd(2021, 12, 31)),
+        ];
+
+        let stats = ReadingStats::for_year(&history, 2022);
+
+        assert_eq!(stats.books, 4);
+        assert_eq!(stats.pages, 815);
+        assert_eq!(assert_some!(stats.average_rating), 4.0);
+        assert_eq!(
+            stats.favourite_authors,
+            vec![
+                ("Ursula K. Le Guin".to_string(), 2),
+                ("Susanna Clarke".to_string(), 1),
+                ("Tamsyn Muir".to_string(), 1),
+            ]
+        );
+        assert_eq!(assert_some!(stats.longest).title, "The Dispossessed");
+        assert_eq!(assert_some!(stats.shortest).title, "A Wizard of Earthsea");
+        assert_eq!(
+            stats
+                .top_rated
+                .iter()
+                .map(|entry| entry.title.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "A Wizard of Earthsea",
+                "The Dispossessed",
+                "Gideon the Ninth"
+            ]
+        );
+        assert_none!(stats.most_shared);
+        assert_eq!(stats.ratings, [0, 0, 1, 1, 1]);
+        assert_eq!(stats.monthly, [1, 0, 2, 0, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert_eq!(
+            stats.monthly_pages,
+            [183, 0, 632, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn the_most_shared_book_is_the_one_most_members_read() {
+        let read_on = NaiveDate::from_ymd(2022, 9, 1);
+        let mut history = vec![
+            HistoryEntry::test_read(2, "Piranesi")
+                .with_title("Piranesi")
+                .with_author("Susanna Clarke")
+                .with_rating(5)
+                .with_pages(Some(245))
+                .with_read_on(read_on),
+            HistoryEntry::test_read(2, "Kindred")
+                .with_title("Kindred")
+                .with_author("Octavia E. Butler")
+                .with_rating(5)
+                .with_pages(Some(264))
+                .with_read_on(read_on),
+            HistoryEntry::test_read(2, "Kindred")
+                .with_title("Kindred")
+                .with_author("Octavia E. Butler")
+                .with_rating(4)
+                .with_pages(Some(264))
+                .with_read_on(read_on),
+            HistoryEntry::test_read(2, "Kindred")
+                .with_title("Kindred")
+                .with_author("Octavia E. Butler")
+                .with_rating(5)
+                .with_pages(Some(264))
+                .with_read_on(read_on),
+        ];
+        history[2].discord_user_id = 20;
+        history[3].discord_user_id = 30;
+
+        let stats = ReadingStats::new(&history);
+
+        let (book, readers) = assert_some!(stats.most_shared);
+        assert_eq!(book.title, "Kindred");
+        assert_eq!(readers, 3);
+        // Each book only makes the top-rated list once
+        assert_eq!(stats.top_rated.len(), 2);
+    }
+
+    #[test]
+    fn stats_are_empty_without_history() {
+        let stats = ReadingStats::new(&[]);
+
+        assert_eq!(stats.books, 0);
+        assert_eq!(stats.pages, 0);
+        assert_none!(stats.average_rating);
+        assert!(stats.favourite_authors.is_empty());
+        assert_none!(stats.longest);
+        assert_none!(stats.shortest);
+        assert_none!(stats.pace);
+    }
+
+    #[test]
+    fn leaderboards_rank_by_books_or_pages() {
+        let read_on = NaiveDate::from_ymd(2022, 9, 1);
+        let mut history = vec![
+            HistoryEntry::test_read(2, "Piranesi")
+                .with_title("Piranesi")
+                .with_author("Susanna Clarke")
+                .with_rating(5)
+                .with_pages(Some(245))
+                .with_read_on(read_on),
+            HistoryEntry::test_read(2, "Kindred")
+                .with_title("Kindred")
+                .with_author("Octavia E. Butler")
+                .with_rating(5)
+                .with_pages(Some(264))
+                .with_read_on(read_on),
+            HistoryEntry::test_read(2, "Middlemarch")
+                .with_title("Middlemarch")
+                .with_author("George Eliot")
+                .with_rating(4)
+                .with_pages(Some(880))
+                .with_read_on(read_on),
+        ];
+        history[2].user_id = 10;
+        history[2].discord_user_id = 20;
+
+        let by_books = Standing::rank(&history, LeaderboardMetric::Books);
+        assert_eq!(
+            by_books
+                .iter()
+                .map(|standing| (standing.discord_user_id, standing.books, standing.pages))
+                .collect::<Vec<_>>(),
+            vec![(2, 2, 509), (20, 1, 880)]
+        );
+
+        let by_pages = Standing::rank(&history, LeaderboardMetric::Pages);
+        assert_eq!(by_pages[0].discord_user_id, 20);
+        assert_eq!(by_pages[1].discord_user_id, 2);
+    }
+}
@@ -3,6 +3,8 @@ use chrono::offset::Utc;
 use serenity::model::prelude::ChannelId;
 use sqlx::sqlite::{SqlitePool, SqliteQueryResult};
 
+use crate::model::{Book, RoutingRule};
+
 #[derive(Debug)]
 pub struct User {
     pub id: i64,
@@ -78,6 +80,35 @@ impl User {
             None,
         ))
     }
+    #[tracing::instrument(name = "Finding user", skip(pool))]
+    pub async fn find(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        discord_guild_id: i64,
+    ) -> anyhow::Result<Option<Self>> {
+        let mut conn = pool.acquire().await?;
+        let result = sqlx::query!(
+            r#"SELECT * FROM users WHERE discord_user_id = ? AND discord_guild_id = ?"#,
+            discord_user_id,
+            discord_guild_id,
+        )
+        .fetch_optional(&mut conn)
+        .await?
+        .map(|record| {
+            User::new(
+                record.id,
+                record.discord_user_id,
+                record.discord_guild_id,
+                record.goodreads_user_id,
+                record.last_etag,
+                record.last_checked,
+                record.last_book_id,
+            )
+        });
+
+        Ok(result)
+    }
+
     #[tracing::instrument(
         name = "Getting all refreshable users",
         skip(pool, last_refreshed_minutes_ago)
@@ -151,8 +182,19 @@ impl User {
         }
     }
 
-    #[tracing::instrument(name = "Retrieving channel id for user", skip(pool))]
-    pub async fn get_channel_id(&self, pool: &SqlitePool) -> anyhow::Result<ChannelId> {
+    /// Where to announce the book: the first of the guild's routing rules it matches, otherwise
+    /// the notification channel
+    #[tracing::instrument(name = "Retrieving channel id for user", skip(pool, book))]
+    pub async fn get_channel_id(
+        &self,
+        pool: &SqlitePool,
+        book: &Book,
+    ) -> anyhow::Result<ChannelId> {
+        let rules = RoutingRule::list(pool, self.discord_guild_id).await?;
+        if let Some(channel) = RoutingRule::route(&rules, book) {
+            return Ok(channel);
+        }
+
         let mut conn = pool.acquire().await?;
         let res = sqlx::query!(
             r#"SELECT notify_channel_id FROM guilds JOIN users on guilds.guild_id = users.discord_guild_id WHERE users.id = ?"#,
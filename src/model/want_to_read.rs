@@ -0,0 +1,210 @@
+use chrono::offset::Utc;
+use sqlx::sqlite::SqlitePool;
+
+use crate::model::Book;
+
+/// A book a member wants to read, kept per guild
+#[derive(Debug, Clone, PartialEq)]
+pub struct WantToRead {
+    pub id: i64,
+    pub discord_user_id: i64,
+    pub discord_guild_id: i64,
+    pub book_id: String,
+    pub title: String,
+    pub author: String,
+    pub url: String,
+    pub added_at: i64,
+    /// When the book turned up on the member's read shelf
+    pub finished_at: Option<i64>,
+    /// Added from the member's Goodreads to-read shelf rather than in Discord
+    pub from_goodreads: bool,
+}
+
+impl WantToRead {
+    pub fn new(
+        discord_user_id: i64,
+        discord_guild_id: i64,
+        book_id: &str,
+        title: &str,
+        author: &str,
+        url: &str,
+    ) -> Self {
+        Self {
+            id: 0,
+            discord_user_id,
+            discord_guild_id,
+            book_id: book_id.to_string(),
+            title: title.to_string(),
+            author: author.to_string(),
+            url: url.to_string(),
+            added_at: Utc::now().timestamp(),
+            finished_at: None,
+            from_goodreads: false,
+        }
+    }
+
+    /// Add the book to the member's list, or put a book they've finished back on it to read again.
+    /// Returns false when it was already there.
+    #[tracing::instrument(name = "Adding book to want to read list", skip(pool))]
+    pub async fn add(&self, pool: &SqlitePool) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+
+        let added = sqlx::query!(
+            r#"
+            INSERT INTO want_to_read (discord_user_id, discord_guild_id, book_id, title, author, url, added_at, from_goodreads)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (discord_user_id, discord_guild_id, book_id) DO UPDATE SET
+                added_at = excluded.added_at,
+                finished_at = NULL
+            WHERE finished_at IS NOT NULL
+            "#,
+            self.discord_user_id,
+            self.discord_guild_id,
+            self.book_id,
+            self.title,
+            self.author,
+            self.url,
+            self.added_at,
+            self.from_goodreads,
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(added > 0)
+    }
+
+    /// Take the book off the member's list. Returns false when it wasn't there.
+    #[tracing::instrument(name = "Removing book from want to read list", skip(pool))]
+    pub async fn remove(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        discord_guild_id: i64,
+        book_id: &str,
+    ) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+
+        let removed = sqlx::query!(
+            r#"
+            DELETE FROM want_to_read
+            WHERE discord_user_id = ? AND discord_guild_id = ? AND book_id = ?
+            "#,
+            discord_user_id,
+            discord_guild_id,
+            book_id
+        )
+        .execute(&mut conn)
+        .await?
+        .rows_affected();
+
+        Ok(removed > 0)
+    }
+
+    /// Add the books on the member's Goodreads to-read shelf that aren't on their list yet. Books
+    /// they've since finished are left alone, and nothing is taken off. Returns how many were added.
+    #[tracing::instrument(name = "Importing to-read shelf", skip(pool, books))]
+    pub async fn import(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        discord_guild_id: i64,
+        books: &[Book],
+    ) -> anyhow::Result<u64> {
+        let mut tx = pool.begin().await?;
+        let added_at = Utc::now().timestamp();
+
+        let mut added = 0;
+        for book in books {
+            let book_id = book.id();
+            let title = book.title();
+            let author = book.author();
+            let url = book.url();
+            added += sqlx::query!(
+                r#"
+                INSERT INTO want_to_read (discord_user_id, discord_guild_id, book_id, title, author, url, added_at, from_goodreads)
+                VALUES (?, ?, ?, ?, ?, ?, ?, TRUE)
+                ON CONFLICT (discord_user_id, discord_guild_id, book_id) DO NOTHING
+                "#,
+                discord_user_id,
+                discord_guild_id,
+                book_id,
+                title,
+                author,
+                url,
+                added_at
+            )
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(added)
+    }
+
+    /// Tick the book off once it turns up on the member's read shelf
+    #[tracing::instrument(name = "Marking want to read book finished", skip(pool))]
+    pub async fn mark_finished(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        discord_guild_id: i64,
+        book_id: &str,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        let finished_at = Utc::now().timestamp();
+
+        sqlx::query!(
+            r#"
+            UPDATE want_to_read SET finished_at = ?
+            WHERE discord_user_id = ? AND discord_guild_id = ? AND book_id = ? AND finished_at IS NULL
+            "#,
+            finished_at,
+            discord_user_id,
+            discord_guild_id,
+            book_id
+        )
+        .execute(&mut conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Everything on the member's list, most recently added first
+    #[tracing::instrument(name = "Retrieving want to read list", skip(pool))]
+    pub async fn list(
+        pool: &SqlitePool,
+        discord_user_id: i64,
+        discord_guild_id: i64,
+    ) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+
+        let books = sqlx::query!(
+            r#"
+            SELECT id as "id!", discord_user_id, discord_guild_id, book_id, title, author, url, added_at,
+                finished_at, from_goodreads
+            FROM want_to_read
+            WHERE discord_user_id = ? AND discord_guild_id = ?
+            ORDER BY added_at DESC
+            "#,
+            discord_user_id,
+            discord_guild_id
+        )
+        .fetch_all(&mut conn)
+        .await?
+        .into_iter()
+        .map(|row| Self {
+            id: row.id,
+            discord_user_id: row.discord_user_id,
+            discord_guild_id: row.discord_guild_id,
+            book_id: row.book_id,
+            title: row.title,
+            author: row.author,
+            url: row.url,
+            added_at: row.added_at,
+            finished_at: row.finished_at,
+            from_goodreads: row.from_goodreads,
+        })
+        .collect();
+
+        Ok(books)
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serenity::builder::CreateEmbed;
+use serenity::http::Http;
+use serenity::utils::Colour;
+use sqlx::SqlitePool;
+
+use crate::model::{GroupRead, GroupReadMember, GuildSettings};
+use crate::scheduler::digest::plural;
+
+/// Post progress on the guild's group read as members finish it, and a wrap-up of everyone's
+/// ratings once the deadline has passed. Both wait for quiet hours to end.
+#[tracing::instrument(name = "Sending group read updates", skip(http, pool))]
+pub async fn send_club_updates(
+    http: &Http,
+    pool: &SqlitePool,
+    group_read: &GroupRead,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let settings = GuildSettings::get(pool, group_read.guild_id).await?;
+    if settings
+        .quiet_hours
+        .and_then(|quiet| quiet.release_at(settings.time_zone, now))
+        .is_some()
+    {
+        return Ok(());
+    }
+    let members = group_read.members(pool).await?;
+
+    if now.timestamp() >= group_read.deadline {
+        let embed = wrap_up_embed(group_read, &members);
+        settings
+            .notify_channel()
+            .send_message(http, |m| m.set_embed(embed))
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to send group read wrap-up to discord channel {}",
+                    settings.notify_channel_id
+                )
+            })?;
+        return group_read.wrap_up(pool).await;
+    }
+
+    let newly_finished: Vec<&GroupReadMember> = members
+        .iter()
+        .filter(|member| member.finished() && !member.reported)
+        .collect();
+    if newly_finished.is_empty() {
+        return Ok(());
+    }
+    let mut lines: Vec<String> = newly_finished
+        .iter()
+        .map(|member| {
+            format!(
+                "📖 <@{}> finished **{}** for the group read!",
+                member.discord_user_id, group_read.title
+            )
+        })
+        .collect();
+    lines.push(progress_line(&members));
+    settings
+        .notify_channel()
+        .send_message(http, |m| m.content(lines.join("\n")))
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to send group read progress to discord channel {}",
+                settings.notify_channel_id
+            )
+        })?;
+    group_read.mark_reported(pool).await
+}
+
+fn progress_line(members: &[GroupReadMember]) -> String {
+    let finished = members.iter().filter(|member| member.finished()).count();
+    if finished == members.len() {
+        format!("Everyone's done: {} of {}!", finished, members.len())
+    } else {
+        format!("{} of {} finished so far.", finished, members.len())
+    }
+}
+
+fn wrap_up_embed(group_read: &GroupRead, members: &[GroupReadMember]) -> CreateEmbed {
+    let finished: Vec<&GroupReadMember> =
+        members.iter().filter(|member| member.finished()).collect();
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(format!("📚 Group read wrap-up: {}", group_read.title))
+        .url(&group_read.url)
+        .colour(Colour::TEAL)
+        .description(wrap_up_totals(members));
+
+    if !finished.is_empty() {
+        let lines: Vec<String> = finished.iter().map(|member| rating_line(member)).collect();
+        embed.field("Ratings", lines.join("\n"), false);
+    }
+    let reading: Vec<String> = members
+        .iter()
+        .filter(|member| !member.finished())
+        .map(|member| format!("<@{}>", member.discord_user_id))
+        .collect();
+    if !reading.is_empty() {
+        embed.field("Still reading", reading.join(", "), false);
+    }
+    embed
+}
+
+/// How many finished, and the average of the ratings members were happy to share
+fn wrap_up_totals(members: &[GroupReadMember]) -> String {
+    let finished = members.iter().filter(|member| member.finished()).count();
+    if finished == 0 {
+        return "Nobody finished in time. There's always the next one!".to_string();
+    }
+    let ratings: Vec<i64> = members
+        .iter()
+        .filter_map(|member| member.rating)
+        .filter(|rating| *rating > 0)
+        .collect();
+    let mut totals = format!(
+        "{} of {} finished",
+        finished,
+        plural(members.len() as i64, "member")
+    );
+    if !ratings.is_empty() {
+        let average = ratings.iter().sum::<i64>() as f64 / ratings.len() as f64;
+        totals.push_str(&format!(" · average rating {:.1} ⭐", average));
+    }
+    totals
+}
+
+/// Members whose preferences keep the book out of announcements are listed without a rating
+fn rating_line(member: &GroupReadMember) -> String {
+    match member.rating {
+        Some(rating) if rating > 0 => format!(
+            "<@{}> {}",
+            member.discord_user_id,
+            "⭐".repeat(rating as usize)
+        ),
+        Some(_) => format!("<@{}> unrated", member.discord_user_id),
+        None => format!("<@{}>", member.discord_user_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::GroupReadMember;
+    use crate::scheduler::club::wrap_up_totals;
+
+    fn member(finished: bool, rating: Option<i64>) -> GroupReadMember {
+        GroupReadMember {
+            discord_user_id: 0,
+            joined_at: 0,
+            finished_at: if finished { Some(0) } else { None },
+            rating,
+            reported: false,
+        }
+    }
+
+    #[test]
+    fn wrap_ups_average_only_shared_ratings() {
+        let members = [
+            member(true, Some(5)),
+            member(true, Some(2)),
+            member(true, Some(0)),
+            member(true, None),
+            member(false, None),
+        ];
+
+        assert_eq!(
+            wrap_up_totals(&members),
+            "4 of 5 members finished · average rating 3.5 ⭐"
+        );
+    }
+
+    #[test]
+    fn wrap_ups_without_finishers_say_so() {
+        assert_eq!(
+            wrap_up_totals(&[member(false, None)]),
+            "Nobody finished in time. There's always the next one!"
+        );
+    }
+}
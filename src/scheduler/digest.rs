@@ -0,0 +1,297 @@
+use anyhow::Context;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use serenity::builder::CreateEmbed;
+use serenity::http::Http;
+use serenity::utils::Colour;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+
+use crate::model::{from_local, DigestSchedule, GuildSettings, HistoryEntry};
+
+/// Discord refuses embed descriptions longer than this, so leave some room for the last line
+const MAX_DESCRIPTION_LENGTH: usize = 4000;
+
+/// Post the guild's digest if one has come due since the last, covering every book shared since
+#[tracing::instrument(name = "Sending digest", skip(http, pool))]
+pub async fn send_digest_if_due(
+    http: &Http,
+    pool: &SqlitePool,
+    settings: &GuildSettings,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let slot = match latest_slot(
+        settings.digest_schedule,
+        settings.digest_time,
+        settings.time_zone,
+        now,
+    ) {
+        Some(slot) => slot.timestamp(),
+        None => return Ok(()),
+    };
+    let last_digest_at = match settings.last_digest_at {
+        Some(last_digest_at) => last_digest_at,
+        // Nothing to go on, so start counting from here
+        None => return GuildSettings::set_last_digest_at(pool, settings.guild_id, slot).await,
+    };
+    if slot <= last_digest_at {
+        return Ok(());
+    }
+
+    let entries =
+        HistoryEntry::shared_between(pool, settings.guild_id, last_digest_at, slot).await?;
+    if !entries.is_empty() {
+        let embed = digest_embed(settings.digest_schedule, &entries);
+        settings
+            .notify_channel()
+            .send_message(http, |m| m.set_embed(embed))
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to send digest to discord channel {}",
+                    settings.notify_channel_id
+                )
+            })?;
+    }
+    GuildSettings::set_last_digest_at(pool, settings.guild_id, slot).await
+}
+
+/// The most recent time a digest was due at or before `now`. Weekly digests go out on Mondays
+/// and monthly digests on the 1st, at `time` in the guild's time zone.
+pub fn latest_slot(
+    schedule: DigestSchedule,
+    time: NaiveTime,
+    time_zone: Tz,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let local_now = now.with_timezone(&time_zone).naive_local();
+    let today = local_now.date();
+
+    let (date, previous) = match schedule {
+        DigestSchedule::Off => return None,
+        DigestSchedule::Daily => (today, today - Duration::days(1)),
+        DigestSchedule::Weekly => {
+            let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+            (monday, monday - Duration::weeks(1))
+        }
+        DigestSchedule::Monthly => {
+            let first = NaiveDate::from_ymd(today.year(), today.month(), 1);
+            let last_month = first - Duration::days(1);
+            (
+                first,
+                NaiveDate::from_ymd(last_month.year(), last_month.month(), 1),
+            )
+        }
+    };
+    let slot = if date.and_time(time) <= local_now {
+        date.and_time(time)
+    } else {
+        previous.and_time(time)
+    };
+
+    Some(from_local(time_zone, slot))
+}
+
+fn digest_embed(schedule: DigestSchedule, entries: &[HistoryEntry]) -> CreateEmbed {
+    let title = match schedule {
+        DigestSchedule::Daily => "📚 Daily reading digest",
+        DigestSchedule::Monthly => "📚 Monthly reading digest",
+        _ => "📚 Weekly reading digest",
+    };
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(title)
+        .colour(Colour::BLUE)
+        .description(digest_lines(entries))
+        .footer(|f| f.text(digest_totals(entries)));
+    embed
+}
+
+/// One line per finished book, cut short with a count of the rest when there are too many
+fn digest_lines(entries: &[HistoryEntry]) -> String {
+    let mut description = String::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let mut line = format!(
+            "<@{}> finished [{}]({}) by {}",
+            entry.discord_user_id, entry.title, entry.url, entry.author
+        );
+        if entry.rating > 0 {
+            line.push(' ');
+            line.push_str(&"⭐".repeat(entry.rating as usize));
+        }
+
+        let remaining = entries.len() - index;
+        if description.chars().count() + line.chars().count() + 1 > MAX_DESCRIPTION_LENGTH {
+            description.push_str(&format!("…and {} more", remaining));
+            break;
+        }
+        description.push_str(&line);
+        description.push('\n');
+    }
+    description.trim_end().to_string()
+}
+
+fn digest_totals(entries: &[HistoryEntry]) -> String {
+    let readers: HashSet<i64> = entries.iter().map(|entry| entry.discord_user_id).collect();
+    let mut totals = vec![
+        plural(entries.len() as i64, "book"),
+        plural(readers.len() as i64, "reader"),
+    ];
+
+    let pages: i64 = entries.iter().filter_map(|entry| entry.pages).sum();
+    if pages > 0 {
+        totals.push(plural(pages, "page"));
+    }
+    let ratings: Vec<i64> = entries
+        .iter()
+        .map(|entry| entry.rating)
+        .filter(|rating| *rating > 0)
+        .collect();
+    if !ratings.is_empty() {
+        let average = ratings.iter().sum::<i64>() as f64 / ratings.len() as f64;
+        totals.push(format!("average rating {:.1} ⭐", average));
+    }
+
+    totals.join(" · ")
+}
+
+pub(super) fn plural(count: i64, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{DigestSchedule, HistoryEntry};
+    use crate::scheduler::digest::{
+        digest_lines, digest_totals, latest_slot, MAX_DESCRIPTION_LENGTH,
+    };
+    use chrono::{NaiveTime, TimeZone, Utc};
+    use chrono_tz::Tz;
+    use claim::{assert_none, assert_some};
+
+    #[test]
+    fn daily_slot_is_today_once_the_time_has_passed() {
+        let nine = NaiveTime::from_hms(9, 0, 0);
+        // Friday August 26th 2022
+        let slot = assert_some!(latest_slot(
+            DigestSchedule::Daily,
+            nine,
+            Tz::UTC,
+            Utc.ymd(2022, 8, 26).and_hms(10, 0, 0)
+        ));
+        assert_eq!(slot, Utc.ymd(2022, 8, 26).and_hms(9, 0, 0));
+
+        let slot = assert_some!(latest_slot(
+            DigestSchedule::Daily,
+            nine,
+            Tz::UTC,
+            Utc.ymd(2022, 8, 26).and_hms(8, 0, 0)
+        ));
+        assert_eq!(slot, Utc.ymd(2022, 8, 25).and_hms(9, 0, 0));
+    }
+
+    #[test]
+    fn weekly_slot_is_on_monday() {
+        let slot = assert_some!(latest_slot(
+            DigestSchedule::Weekly,
+            NaiveTime::from_hms(9, 0, 0),
+            Tz::UTC,
+            Utc.ymd(2022, 8, 26).and_hms(10, 0, 0)
+        ));
+        assert_eq!(slot, Utc.ymd(2022, 8, 22).and_hms(9, 0, 0));
+
+        let slot = assert_some!(latest_slot(
+            DigestSchedule::Weekly,
+            NaiveTime::from_hms(9, 0, 0),
+            Tz::UTC,
+            Utc.ymd(2022, 8, 22).and_hms(8, 0, 0)
+        ));
+        assert_eq!(slot, Utc.ymd(2022, 8, 15).and_hms(9, 0, 0));
+    }
+
+    #[test]
+    fn monthly_slot_is_on_the_first() {
+        let slot = assert_some!(latest_slot(
+            DigestSchedule::Monthly,
+            NaiveTime::from_hms(9, 0, 0),
+            Tz::UTC,
+            Utc.ymd(2022, 1, 1).and_hms(8, 0, 0)
+        ));
+        assert_eq!(slot, Utc.ymd(2021, 12, 1).and_hms(9, 0, 0));
+    }
+
+    #[test]
+    fn slots_are_in_the_guilds_local_time() {
+        // 09:00 in Berlin during summer time is 07:00 UTC
+        let slot = assert_some!(latest_slot(
+            DigestSchedule::Daily,
+            NaiveTime::from_hms(9, 0, 0),
+            Tz::Europe__Berlin,
+            Utc.ymd(2022, 8, 26).and_hms(7, 30, 0)
+        ));
+        assert_eq!(slot, Utc.ymd(2022, 8, 26).and_hms(7, 0, 0));
+    }
+
+    #[test]
+    fn no_slot_when_digests_are_off() {
+        assert_none!(latest_slot(
+            DigestSchedule::Off,
+            NaiveTime::from_hms(9, 0, 0),
+            Tz::UTC,
+            Utc.ymd(2022, 8, 26).and_hms(10, 0, 0)
+        ));
+    }
+
+    #[test]
+    fn totals_count_books_readers_pages_and_ratings() {
+        let entries = vec![
+            HistoryEntry::test_read(1, "1")
+                .with_title("The Dispossessed")
+                .with_rating(5)
+                .with_pages(Some(387)),
+            HistoryEntry::test_read(1, "1")
+                .with_title("The Lathe of Heaven")
+                .with_rating(0)
+                .with_pages(None),
+            HistoryEntry::test_read(2, "1")
+                .with_title("A Wizard of Earthsea")
+                .with_rating(4)
+                .with_pages(Some(183)),
+        ];
+        assert_eq!(
+            digest_totals(&entries),
+            "3 books · 2 readers · 570 pages · average rating 4.5 ⭐"
+        );
+    }
+
+    #[test]
+    fn lines_list_who_finished_what() {
+        let entries = vec![HistoryEntry::test_read(1, "1")
+            .with_title("The Dispossessed")
+            .with_rating(5)
+            .with_pages(Some(387))];
+        assert_eq!(
+            digest_lines(&entries),
+            "<@1> finished [The Dispossessed](https://www.goodreads.com/book/show/1) by Ursula K. Le Guin ⭐⭐⭐⭐⭐"
+        );
+    }
+
+    #[test]
+    fn lines_are_cut_short_when_there_are_too_many() {
+        let entries: Vec<HistoryEntry> = (0..100)
+            .map(|i| {
+                HistoryEntry::test_read(i, "1")
+                    .with_title("The Left Hand of Darkness")
+                    .with_rating(5)
+                    .with_pages(None)
+            })
+            .collect();
+        let lines = digest_lines(&entries);
+        assert!(lines.chars().count() <= MAX_DESCRIPTION_LENGTH + 20);
+        assert!(lines.ends_with("more"));
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serenity::CacheAndHttp;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+use crate::discord::post_milestone;
+use crate::model::{GuildSettings, HeldMilestone, HistoryEntry, Preferences, User};
+
+/// Announce a goal milestone that was reached during quiet hours, once they're over
+#[tracing::instrument(name = "Releasing held goal milestone", skip(cache_and_http, pool))]
+pub async fn release_milestone(
+    cache_and_http: &Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    held: &HeldMilestone,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let settings = GuildSettings::get(pool, held.guild_id).await?;
+    if settings
+        .quiet_hours
+        .and_then(|quiet| quiet.release_at(settings.time_zone, now))
+        .is_some()
+    {
+        return Ok(());
+    }
+    // Marked first, so a message discord won't take isn't retried every minute
+    held.mark_announced(pool).await?;
+    let user = match User::find(pool, held.discord_user_id, held.guild_id).await? {
+        Some(user) if user.id == held.goal.user_id => user,
+        _ => return Ok(()),
+    };
+    let prefs = Preferences::get(pool, user.id).await?;
+    let books = HistoryEntry::count_in_year(pool, user.id, held.goal.year).await?;
+
+    post_milestone(
+        cache_and_http,
+        &user,
+        &prefs,
+        &settings,
+        &held.goal,
+        held.milestone,
+        books,
+    )
+    .await
+}
@@ -0,0 +1,10 @@
+mod club;
+mod digest;
+mod goals;
+mod outbox;
+mod poll;
+mod recap;
+mod scheduler;
+mod streak;
+
+pub use scheduler::schedule;
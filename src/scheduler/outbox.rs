@@ -0,0 +1,72 @@
+use anyhow::anyhow;
+use chrono::offset::Utc;
+use serenity::CacheAndHttp;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+use crate::discord::{post_book, FollowerDms};
+use crate::model::{GuildSettings, OutboxEntry, Preferences, User};
+
+/// Post an announcement that was held for quiet hours, using the member's current preferences
+#[tracing::instrument(
+    name = "Releasing held announcement",
+    skip(cache_and_http, pool, follower_dms)
+)]
+pub async fn release(
+    cache_and_http: Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    follower_dms: &FollowerDms,
+    entry: &OutboxEntry,
+) -> anyhow::Result<()> {
+    let user = match User::find(pool, entry.discord_user_id, entry.guild_id).await? {
+        Some(user) if user.id == entry.user_id => user,
+        // They unlurked while the announcement was waiting
+        _ => return entry.delete(pool).await,
+    };
+    let book = match entry.book() {
+        Some(book) => book,
+        None => {
+            // Trying again won't help
+            entry.delete(pool).await?;
+            return Err(anyhow!("Unable to read back held book {}", entry.book_id));
+        }
+    };
+    let prefs = Preferences::get(pool, user.id).await?;
+    if !prefs.should_announce(&book) {
+        return entry.delete(pool).await;
+    }
+    let settings = GuildSettings::get(pool, entry.guild_id).await?;
+    let channel = user.get_channel_id(pool, &book).await?;
+
+    // Taken out before posting, so a database error afterwards can't post it twice
+    if !entry.claim(pool).await? {
+        return Ok(());
+    }
+    if let Err(why) = post_book(
+        cache_and_http.clone(),
+        pool,
+        &book,
+        &user,
+        &prefs,
+        &settings,
+        channel,
+    )
+    .await
+    {
+        if entry.expired(Utc::now().timestamp()) {
+            tracing::warn!(
+                "Giving up on held announcement of {} for user ({}) because: {}",
+                entry.book_id,
+                user.id,
+                why
+            );
+            return Ok(());
+        }
+        entry.put_back(pool).await?;
+        return Err(why);
+    }
+
+    follower_dms
+        .notify(cache_and_http, pool, &book, &user, &prefs, &settings)
+        .await
+}
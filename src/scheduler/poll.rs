@@ -0,0 +1,138 @@
+use anyhow::Context;
+use chrono::{DateTime, TimeZone, Utc};
+use serenity::builder::CreateEmbed;
+use serenity::http::Http;
+use serenity::utils::Colour;
+use sqlx::SqlitePool;
+
+use crate::model::{GroupRead, GuildSettings, Nomination, Poll, PollMethod, Tally};
+use crate::scheduler::digest::plural;
+
+/// Embed fields only hold 1024 characters, and titles can be long
+const STANDINGS_SHOWN: usize = 10;
+
+/// Count the votes once a poll has closed, announce the winner, and start a group read of it if
+/// the poll asked for one. Waits for quiet hours to end.
+#[tracing::instrument(name = "Closing poll", skip(http, pool))]
+pub async fn close_poll(
+    http: &Http,
+    pool: &SqlitePool,
+    poll: &Poll,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let settings = GuildSettings::get(pool, poll.guild_id).await?;
+    if settings
+        .quiet_hours
+        .and_then(|quiet| quiet.release_at(settings.time_zone, now))
+        .is_some()
+    {
+        return Ok(());
+    }
+    let nominations = poll.nominations(pool).await?;
+    let ballots = poll.ballots(pool).await?;
+    let tally = poll.tally(&nominations, &ballots);
+    let winner = tally.winner.as_ref().and_then(|winner| {
+        nominations
+            .iter()
+            .find(|nomination| &nomination.book_id == winner)
+    });
+
+    let mut embed = results_embed(poll, &nominations, &tally, winner, ballots.len());
+    if let (Some(winner), Some(club_deadline)) = (winner, poll.club_deadline) {
+        let group_read = GroupRead::new(
+            poll.guild_id,
+            poll.opened_by,
+            &winner.book_id,
+            &winner.title,
+            &winner.author,
+            &winner.url,
+            Utc.timestamp(club_deadline, 0),
+        );
+        let club = match group_read.start(pool).await? {
+            Some(group_read) => format!(
+                "The group read starts now: finish by {}! Type `~club join` to take part.",
+                group_read.last_day(settings.time_zone).format("%A %B %-d")
+            ),
+            None => "The server already has a group read going, so type `~club start` with this one once it's over.".to_string(),
+        };
+        embed.field("📚 Group read", club, false);
+    }
+    settings
+        .notify_channel()
+        .send_message(http, |m| m.set_embed(embed))
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to send poll results to discord channel {}",
+                settings.notify_channel_id
+            )
+        })?;
+    poll.close(pool).await
+}
+
+fn results_embed(
+    poll: &Poll,
+    nominations: &[Nomination],
+    tally: &Tally,
+    winner: Option<&Nomination>,
+    voters: usize,
+) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed.colour(Colour::PURPLE);
+
+    match winner {
+        Some(winner) => {
+            embed
+                .title(format!("🗳️ The votes are in: {}", winner.title))
+                .url(&winner.url)
+                .description(format!(
+                    "by {}\nnominated by <@{}> · {} voted",
+                    winner.author,
+                    winner.nominated_by,
+                    plural(voters as i64, "member")
+                ));
+            if !winner.image_url.is_empty() {
+                embed.thumbnail(&winner.image_url);
+            }
+        }
+        None => {
+            embed
+                .title("🗳️ The votes are in")
+                .description("Nobody voted, so there's no winner this time.");
+            return embed;
+        }
+    }
+
+    if let Some(standings) = tally.rounds.last() {
+        let mut lines: Vec<String> = standings
+            .iter()
+            .take(STANDINGS_SHOWN)
+            .filter_map(|(book_id, votes)| {
+                nominations
+                    .iter()
+                    .find(|nomination| &nomination.book_id == book_id)
+                    .map(|nomination| {
+                        format!(
+                            "**{}** · {}",
+                            nomination.title,
+                            plural(*votes as i64, "vote")
+                        )
+                    })
+            })
+            .collect();
+        if standings.len() > STANDINGS_SHOWN {
+            lines.push(format!("…and {} more", standings.len() - STANDINGS_SHOWN));
+        }
+        let heading = match (poll.method, tally.rounds.len()) {
+            (PollMethod::Ranked, rounds) if rounds > 1 => {
+                format!(
+                    "Final round, after {}",
+                    plural(rounds as i64 - 1, "knockout")
+                )
+            }
+            _ => "Votes".to_string(),
+        };
+        embed.field(heading, lines.join("\n"), false);
+    }
+    embed
+}
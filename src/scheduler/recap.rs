@@ -0,0 +1,213 @@
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use serenity::builder::CreateEmbed;
+use serenity::model::id::UserId;
+use serenity::utils::Colour;
+use serenity::CacheAndHttp;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::discord::attach_reading_chart;
+use crate::model::{GuildSettings, HistoryEntry, Preferences, ReadingStats};
+use crate::scheduler::digest::plural;
+
+/// Post the guild's year-in-review once its recap date comes round, then DM it to the members
+/// who asked for their own
+#[tracing::instrument(name = "Sending year-in-review", skip(cache_and_http, pool))]
+pub async fn send_recap_if_due(
+    cache_and_http: &Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    settings: &GuildSettings,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let date = match settings.recap_date {
+        Some(date) => date,
+        None => return Ok(()),
+    };
+    let year = match date.due(
+        settings.digest_time,
+        settings.time_zone,
+        now,
+        settings.last_recap_year,
+    ) {
+        Some(year) => year,
+        None => return Ok(()),
+    };
+    let covers = date.covers(year);
+
+    let history = HistoryEntry::shared_read_between(
+        pool,
+        settings.guild_id,
+        NaiveDate::from_ymd(covers, 1, 1),
+        NaiveDate::from_ymd(covers, 12, 31),
+    )
+    .await?;
+    if !history.is_empty() {
+        let readers: HashSet<i64> = history.iter().map(|entry| entry.discord_user_id).collect();
+        let stats = ReadingStats::new(&history);
+        let mut embed = recap_embed(
+            &format!("📚 {} in books", covers),
+            &stats,
+            Some(readers.len()),
+        );
+        let chart = attach_reading_chart(&mut embed, &stats)?;
+        settings
+            .notify_channel()
+            .send_message(&cache_and_http.http, |m| {
+                m.content(format!("Here's {}'s year in review!", settings.guild_name))
+                    .set_embed(embed)
+                    .add_file(chart)
+            })
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to send year-in-review to discord channel {}",
+                    settings.notify_channel_id
+                )
+            })?;
+    }
+    GuildSettings::set_last_recap_year(pool, settings.guild_id, year).await?;
+
+    for discord_user_id in Preferences::recap_members(pool, settings.guild_id).await? {
+        // One member's closed DMs shouldn't keep the others from getting theirs
+        if let Err(why) =
+            send_member_recap(cache_and_http, pool, settings, discord_user_id, covers).await
+        {
+            tracing::warn!(
+                "Unable to DM year-in-review to member ({}) because: {}",
+                discord_user_id,
+                why
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn send_member_recap(
+    cache_and_http: &Arc<CacheAndHttp>,
+    pool: &SqlitePool,
+    settings: &GuildSettings,
+    discord_user_id: i64,
+    year: i32,
+) -> anyhow::Result<()> {
+    // It's only going to them, so the books they keep to themselves count too
+    let history = HistoryEntry::for_member(pool, discord_user_id, settings.guild_id, true).await?;
+    let stats = ReadingStats::for_year(&history, year);
+    if stats.books == 0 {
+        return Ok(());
+    }
+
+    let mut embed = recap_embed(&format!("📚 Your {} in books", year), &stats, None);
+    let chart = attach_reading_chart(&mut embed, &stats)?;
+    UserId(discord_user_id as u64)
+        .create_dm_channel(cache_and_http)
+        .await?
+        .send_message(&cache_and_http.http, |m| {
+            m.content(format!(
+                "Here's your year in review from **{}**!",
+                settings.guild_name
+            ))
+            .set_embed(embed)
+            .add_file(chart)
+        })
+        .await?;
+    Ok(())
+}
+
+fn recap_embed(title: &str, stats: &ReadingStats, readers: Option<usize>) -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+    embed
+        .title(title)
+        .colour(Colour::GOLD)
+        .description(recap_totals(stats, readers));
+
+    if !stats.top_rated.is_empty() {
+        let lines: Vec<String> = stats
+            .top_rated
+            .iter()
+            .map(|entry| {
+                format!(
+                    "{} {}",
+                    book_line(entry),
+                    "⭐".repeat(entry.rating as usize)
+                )
+            })
+            .collect();
+        embed.field("Top-rated reads", lines.join("\n"), false);
+    }
+    if !stats.favourite_authors.is_empty() {
+        let authors: Vec<String> = stats
+            .favourite_authors
+            .iter()
+            .map(|(author, books)| format!("{} ({})", author, plural(*books as i64, "book")))
+            .collect();
+        embed.field("Most-read authors", authors.join("\n"), false);
+    }
+    if let Some(longest) = &stats.longest {
+        embed.field(
+            "Longest book",
+            format!(
+                "{} · {}",
+                book_line(longest),
+                plural(longest.pages.unwrap_or_default(), "page")
+            ),
+            false,
+        );
+    }
+    if let Some((book, readers)) = &stats.most_shared {
+        embed.field(
+            "Most shared book",
+            format!(
+                "{}, finished by {}",
+                book_line(book),
+                plural(*readers as i64, "member")
+            ),
+            false,
+        );
+    }
+
+    embed
+}
+
+fn recap_totals(stats: &ReadingStats, readers: Option<usize>) -> String {
+    let mut totals = vec![plural(stats.books as i64, "book")];
+    if stats.pages > 0 {
+        totals.push(plural(stats.pages, "page"));
+    }
+    if let Some(readers) = readers {
+        totals.push(plural(readers as i64, "reader"));
+    }
+    totals.join(" · ")
+}
+
+fn book_line(entry: &HistoryEntry) -> String {
+    format!("[{}]({}) by {}", entry.title, entry.url, entry.author)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{HistoryEntry, ReadingStats};
+    use crate::scheduler::recap::recap_totals;
+
+    #[test]
+    fn totals_leave_out_readers_for_a_members_own_recap() {
+        let stats = ReadingStats::new(&[
+            HistoryEntry::test_read(1, "1")
+                .with_title("The Left Hand of Darkness")
+                .with_rating(5)
+                .with_pages(Some(304)),
+            HistoryEntry::test_read(2, "1")
+                .with_title("The Left Hand of Darkness")
+                .with_rating(5)
+                .with_pages(None),
+        ]);
+
+        assert_eq!(
+            recap_totals(&stats, Some(2)),
+            "2 books · 304 pages · 2 readers"
+        );
+        assert_eq!(recap_totals(&stats, None), "2 books · 304 pages");
+    }
+}
@@ -0,0 +1,133 @@
+use chrono::offset::Utc;
+use serenity::CacheAndHttp;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+use crate::discord::FollowerDms;
+use crate::model::{GroupRead, GuildSettings, HeldMilestone, OutboxEntry, Poll};
+use crate::scheduler::club::send_club_updates;
+use crate::scheduler::digest::send_digest_if_due;
+use crate::scheduler::goals::release_milestone;
+use crate::scheduler::outbox::release;
+use crate::scheduler::poll::close_poll;
+use crate::scheduler::recap::send_recap_if_due;
+use crate::scheduler::streak::send_streak_reminders;
+
+/// Runs everything that happens on the clock rather than in response to a crawl or a command
+pub async fn schedule(
+    cache_and_http: Arc<CacheAndHttp>,
+    pool: Arc<SqlitePool>,
+    follower_dms: Arc<FollowerDms>,
+) -> anyhow::Result<()> {
+    let pool = &*pool;
+
+    loop {
+        let now = Utc::now();
+        for settings in or_log(GuildSettings::with_digests(pool).await, "digest guilds") {
+            if let Err(why) = send_digest_if_due(&cache_and_http.http, pool, &settings, now).await {
+                tracing::error!(
+                    error.cause_chain = ?why,
+                    error.message = %why,
+                    "Unable to send digest for guild ({}) because: {}",
+                    settings.guild_id,
+                    why
+                );
+            }
+        }
+        for settings in or_log(
+            GuildSettings::with_recaps(pool).await,
+            "year-in-review guilds",
+        ) {
+            if let Err(why) = send_recap_if_due(&cache_and_http, pool, &settings, now).await {
+                tracing::error!(
+                    error.cause_chain = ?why,
+                    error.message = %why,
+                    "Unable to send year-in-review for guild ({}) because: {}",
+                    settings.guild_id,
+                    why
+                );
+            }
+        }
+        for settings in or_log(
+            GuildSettings::with_pace(pool).await,
+            "streak reminder guilds",
+        ) {
+            if let Err(why) =
+                send_streak_reminders(&cache_and_http.http, pool, &settings, now).await
+            {
+                tracing::error!(
+                    error.cause_chain = ?why,
+                    error.message = %why,
+                    "Unable to send streak reminders for guild ({}) because: {}",
+                    settings.guild_id,
+                    why
+                );
+            }
+        }
+        for group_read in or_log(GroupRead::all_running(pool).await, "group reads") {
+            if let Err(why) = send_club_updates(&cache_and_http.http, pool, &group_read, now).await
+            {
+                tracing::error!(
+                    error.cause_chain = ?why,
+                    error.message = %why,
+                    "Unable to send group read updates for guild ({}) because: {}",
+                    group_read.guild_id,
+                    why
+                );
+            }
+        }
+        for poll in or_log(Poll::due(pool, now.timestamp()).await, "polls due to close") {
+            if let Err(why) = close_poll(&cache_and_http.http, pool, &poll, now).await {
+                tracing::error!(
+                    error.cause_chain = ?why,
+                    error.message = %why,
+                    "Unable to close poll for guild ({}) because: {}",
+                    poll.guild_id,
+                    why
+                );
+            }
+        }
+        for held in or_log(HeldMilestone::all(pool).await, "held goal milestones") {
+            if let Err(why) = release_milestone(&cache_and_http, pool, &held, now).await {
+                tracing::error!(
+                    error.cause_chain = ?why,
+                    error.message = %why,
+                    "Unable to announce held goal milestone for user ({}) because: {}",
+                    held.goal.user_id,
+                    why
+                );
+            }
+        }
+        for entry in or_log(
+            OutboxEntry::due(pool, now.timestamp()).await,
+            "held announcements",
+        ) {
+            // Left in the outbox to try again next time round
+            if let Err(why) = release(cache_and_http.clone(), pool, &follower_dms, &entry).await {
+                tracing::error!(
+                    error.cause_chain = ?why,
+                    error.message = %why,
+                    "Unable to release held announcement ({}) because: {}",
+                    entry.id,
+                    why
+                );
+            }
+        }
+        sleep(Duration::from_millis(1000 * 60)).await;
+    }
+}
+
+/// Each job carries on without its list when it can't be fetched, rather than stopping the others
+fn or_log<T>(found: anyhow::Result<Vec<T>>, job: &str) -> Vec<T> {
+    found.unwrap_or_else(|why| {
+        tracing::error!(
+            error.cause_chain = ?why,
+            error.message = %why,
+            "Unable to look up {} because: {}",
+            job,
+            why
+        );
+        Vec::new()
+    })
+}
@@ -0,0 +1,91 @@
+use anyhow::Context;
+use chrono::{DateTime, Datelike, NaiveDateTime, NaiveTime, Utc, Weekday};
+use serenity::http::Http;
+use sqlx::SqlitePool;
+
+use crate::model::{GuildSettings, HistoryEntry, Preferences, User, WeeklyStreak};
+
+/// Warn the guild's members who asked about their pace when a weekly streak is about to end.
+/// Each streak gets one reminder a week, held until quiet hours are over.
+#[tracing::instrument(name = "Sending streak reminders", skip(http, pool))]
+pub async fn send_streak_reminders(
+    http: &Http,
+    pool: &SqlitePool,
+    settings: &GuildSettings,
+    now: DateTime<Utc>,
+) -> anyhow::Result<()> {
+    let local_now = now.with_timezone(&settings.time_zone).naive_local();
+    if !reminder_due(local_now)
+        || settings
+            .quiet_hours
+            .and_then(|quiet| quiet.release_at(settings.time_zone, now))
+            .is_some()
+    {
+        return Ok(());
+    }
+    let today = local_now.date();
+
+    for discord_user_id in Preferences::pace_members(pool, settings.guild_id).await? {
+        let user = match User::find(pool, discord_user_id, settings.guild_id).await? {
+            Some(user) => user,
+            None => continue,
+        };
+        let history =
+            HistoryEntry::for_member(pool, discord_user_id, settings.guild_id, true).await?;
+        let streak = WeeklyStreak::current(history.iter().map(|entry| entry.read_on), today);
+        if !streak.at_risk || streak.weeks < 2 {
+            continue;
+        }
+        if !WeeklyStreak::record_reminder(pool, user.id, today).await? {
+            continue;
+        }
+
+        settings
+            .notify_channel()
+            .say(
+                http,
+                format!(
+                    "🔥 <@{}>'s {} week reading streak ends tonight unless they finish a book!",
+                    discord_user_id, streak.weeks
+                ),
+            )
+            .await
+            .with_context(|| {
+                format!(
+                    "Unable to send streak reminder to discord channel {}",
+                    settings.notify_channel_id
+                )
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Sunday is the last chance to keep a streak going. Noon leaves the member the rest of the day
+/// to finish something.
+fn reminder_due(local_now: NaiveDateTime) -> bool {
+    local_now.weekday() == Weekday::Sun && local_now.time() >= NaiveTime::from_hms(12, 0, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scheduler::streak::reminder_due;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn reminders_go_out_on_sunday_afternoons() {
+        // 2022-10-09 was a Sunday
+        assert!(reminder_due(
+            NaiveDate::from_ymd(2022, 10, 9).and_hms(12, 0, 0)
+        ));
+        assert!(reminder_due(
+            NaiveDate::from_ymd(2022, 10, 9).and_hms(23, 59, 0)
+        ));
+        assert!(!reminder_due(
+            NaiveDate::from_ymd(2022, 10, 9).and_hms(11, 59, 0)
+        ));
+        assert!(!reminder_due(
+            NaiveDate::from_ymd(2022, 10, 8).and_hms(18, 0, 0)
+        ));
+    }
+}
@@ -4,8 +4,9 @@ use std::fmt::{Debug, Display};
 use std::str::FromStr;
 use std::sync::Arc;
 
-use crate::crawler::crawl;
-use crate::discord::get_discord_client;
+use crate::crawler::{crawl, watch_authors};
+use crate::discord::{get_discord_client, FollowerDms};
+use crate::scheduler::schedule;
 
 pub async fn run_until_stopped() -> anyhow::Result<()> {
     let database = Arc::new(
@@ -19,12 +20,16 @@ pub async fn run_until_stopped() -> anyhow::Result<()> {
             .expect("Couldn't connect to database"),
     );
 
+    let follower_dms = Arc::new(FollowerDms::default());
+
     loop {
         let mut discord_client = get_discord_client(database.clone()).await;
         let cache_and_http = discord_client.cache_and_http.clone();
         tokio::select! {
             r = discord_client.start() => { report_exit("Discord Client", r) },
-            r = crawl(cache_and_http, database.clone()) => { report_exit("Crawler", r)},
+            r = crawl(cache_and_http.clone(), database.clone(), follower_dms.clone()) => { report_exit("Crawler", r)},
+            r = watch_authors(cache_and_http.clone(), database.clone()) => { report_exit("Author watcher", r)},
+            r = schedule(cache_and_http, database.clone(), follower_dms.clone()) => { report_exit("Scheduler", r)},
         };
     }
 }